@@ -1,5 +1,4 @@
 mod parse;
-mod rtt;
 mod stats;
 #[cfg(test)]
 mod test;
@@ -11,9 +10,10 @@ use log4rs::append::file::FileAppender;
 use log4rs::config::{Appender, Config, Root};
 use std::{collections::HashSet, fs, io, path::PathBuf, time::Duration};
 use viam_rust_utils::rpc::dial::{self, ViamChannel, VIAM_MDNS_SERVICE_NAME};
+use viam_rust_utils::rpc::rtt;
 
 /// dialdbg gives information on how rust-utils' dial function makes connections.
-#[derive(Parser, Debug, Default)]
+#[derive(Parser, Debug)]
 #[command(author, version, about)]
 pub(crate) struct Args {
     /// Whether direct gRPC connection should not be examined. If not provided, gRPC connection
@@ -31,6 +31,11 @@ pub(crate) struct Args {
     #[arg(long, action)]
     nortt: bool,
 
+    /// Number of echo requests to measure round-trip-time over. Must be at least 1. If not
+    /// provided, round-trip-time will be measured over 10 requests.
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    rtt_pings: u32,
+
     /// Filepath for output of dialdbg (file will be overwritten). If not provided, dialdbg will
     /// output to STDOUT.
     #[arg(short, long)]
@@ -47,6 +52,12 @@ pub(crate) struct Args {
     #[arg(short('t'), long, requires("credential"))]
     credential_type: Option<String>,
 
+    /// Whether the credential may be sent over a connection without TLS, where anyone on the
+    /// network path can read it. Can only be provided with "--credential". If not provided,
+    /// dialdbg will not fall back to plain http when dialing with a credential.
+    #[arg(long, action, requires("credential"))]
+    allow_insecure_credentials: bool,
+
     /// Authentication entity with which to connect to the URI. Can only be provided with
     /// "--credential" and must be provided with "--credential-type api-key".
     #[arg(
@@ -58,6 +69,12 @@ pub(crate) struct Args {
     )]
     entity: Option<String>,
 
+    /// Filepath for a JSON diagnostic bundle (environment, dial outcome, RTT and WebRTC stats)
+    /// captured from an additional WebRTC dial (file will be overwritten). If not provided, no
+    /// bundle will be captured.
+    #[arg(long)]
+    diagnostics: Option<PathBuf>,
+
     /// URI to dial. Must be provided.
     #[arg(short, long, required(true), display_order(0))]
     uri: Option<String>,
@@ -68,6 +85,7 @@ async fn dial_grpc(
     credential: &str,
     credential_type: &str,
     entity: Option<String>,
+    allow_insecure_credentials: bool,
 ) -> Option<ViamChannel> {
     let dial_result = match credential {
         "" => {
@@ -85,13 +103,16 @@ async fn dial_grpc(
                 credential_type.to_string(),
                 credential.to_string(),
             );
-            dial::DialOptions::builder()
+            let builder = dial::DialOptions::builder()
                 .uri(uri)
                 .with_credentials(creds)
                 .disable_webrtc()
-                .allow_downgrade()
-                .connect()
-                .await
+                .allow_downgrade();
+            if allow_insecure_credentials {
+                builder.allow_insecure_credentials().connect().await
+            } else {
+                builder.connect().await
+            }
         }
     };
 
@@ -100,48 +121,67 @@ async fn dial_grpc(
     match dial_result {
         Ok(ch) => Some(ch),
         Err(e) => {
-            log::error!("{}: {e}", parse::DIAL_ERROR_PREFIX);
+            log::error!("{}: {e:#}", parse::DIAL_ERROR_PREFIX);
             None
         }
     }
 }
 
-async fn dial_webrtc(
+fn webrtc_builder(
     uri: &str,
     credential: &str,
     credential_type: &str,
     entity: Option<String>,
-) -> Option<ViamChannel> {
-    let dial_result = match credential {
-        "" => {
-            dial::DialOptions::builder()
-                .uri(uri)
-                .without_credentials()
-                .allow_downgrade()
-                .connect()
-                .await
-        }
+    allow_insecure_credentials: bool,
+) -> dial::ConfiguredDialBuilder {
+    match credential {
+        "" => dial::DialOptions::builder()
+            .uri(uri)
+            .without_credentials()
+            .allow_downgrade()
+            .into(),
         _ => {
             let creds = dial::RPCCredentials::new(
                 entity,
                 credential_type.to_string(),
                 credential.to_string(),
             );
-            dial::DialOptions::builder()
+            let builder = dial::DialOptions::builder()
                 .uri(uri)
                 .with_credentials(creds)
-                .allow_downgrade()
-                .connect()
-                .await
+                .allow_downgrade();
+            if allow_insecure_credentials {
+                builder.allow_insecure_credentials().into()
+            } else {
+                builder.into()
+            }
         }
-    };
+    }
+}
+
+async fn dial_webrtc(
+    uri: &str,
+    credential: &str,
+    credential_type: &str,
+    entity: Option<String>,
+    allow_insecure_credentials: bool,
+) -> Option<ViamChannel> {
+    let dial_result = webrtc_builder(
+        uri,
+        credential,
+        credential_type,
+        entity,
+        allow_insecure_credentials,
+    )
+    .connect()
+    .await;
 
     // `connect` may propagate an error here; log the error with a prefix so we can still
     // process logs and not immediately return from the main function.
     match dial_result {
         Ok(ch) => Some(ch),
         Err(e) => {
-            log::error!("{}: {e}", parse::DIAL_ERROR_PREFIX);
+            log::error!("{}: {e:#}", parse::DIAL_ERROR_PREFIX);
             None
         }
     }
@@ -189,6 +229,14 @@ async fn all_mdns_addresses() -> Result<HashSet<String>> {
     Ok(responses)
 }
 
+// If an RTT is less than 1ms, report < 1ms instead of floored "0ms" value.
+fn rtt_millis_str(rtt: Duration) -> String {
+    match rtt.as_millis() {
+        0 => "<1".to_string(),
+        millis => millis.to_string(),
+    }
+}
+
 pub(crate) async fn main_inner(args: Args) -> Result<()> {
     let uri = args.uri.unwrap_or_default();
     let credential = args.credential.unwrap_or_default();
@@ -224,6 +272,7 @@ pub(crate) async fn main_inner(args: Args) -> Result<()> {
             credential.as_str(),
             credential_type.as_str(),
             args.entity.clone(),
+            args.allow_insecure_credentials,
         )
         .await;
         let grpc_res = parse::parse_grpc_logs(log_path.clone(), &mut out)?;
@@ -231,21 +280,16 @@ pub(crate) async fn main_inner(args: Args) -> Result<()> {
 
         if let Some(ch) = ch {
             if !args.nortt {
-                let average_rtt = rtt::measure_rtt(ch, 10).await?.as_millis();
-
-                // If average RTT is less than 1ms, report < 1ms instead of
-                // floored "0ms" value.
-                let millis_str = if average_rtt < 1 {
-                    "<1".to_string()
-                } else {
-                    average_rtt.to_string()
-                };
+                let rtt = rtt::measure_rtt(ch.clone(), args.rtt_pings).await?;
                 writeln!(
                     out,
-                    "average RTT across established gRPC connection: {}ms",
-                    millis_str,
+                    "average RTT across established gRPC connection: {}ms (min {}ms, max {}ms)",
+                    rtt_millis_str(rtt.avg),
+                    rtt_millis_str(rtt.min),
+                    rtt_millis_str(rtt.max),
                 )?;
             }
+            ch.close().await?;
         }
 
         // If mDNS could not be used to connect; show discovered mDNS addresses on current
@@ -287,6 +331,7 @@ pub(crate) async fn main_inner(args: Args) -> Result<()> {
             credential.as_str(),
             credential_type.as_str(),
             args.entity.clone(),
+            args.allow_insecure_credentials,
         )
         .await;
         let wrtc_res = parse::parse_webrtc_logs(log_path.clone(), &mut out)?;
@@ -294,26 +339,28 @@ pub(crate) async fn main_inner(args: Args) -> Result<()> {
 
         if let Some(ch) = ch {
             if !args.nortt {
-                let average_rtt = rtt::measure_rtt(ch.clone(), 10).await?.as_millis();
-
-                // If average RTT is less than 1ms, report < 1ms instead of
-                // floored "0ms" value.
-                let millis_str = if average_rtt < 1 {
-                    "<1".to_string()
-                } else {
-                    average_rtt.to_string()
-                };
+                let rtt = rtt::measure_rtt(ch.clone(), args.rtt_pings).await?;
                 writeln!(
                     out,
-                    "average RTT across established WebRTC connection: {}ms",
-                    millis_str,
+                    "average RTT across established WebRTC connection: {}ms (min {}ms, max {}ms)",
+                    rtt_millis_str(rtt.avg),
+                    rtt_millis_str(rtt.min),
+                    rtt_millis_str(rtt.max),
                 )?;
             }
 
-            if let ViamChannel::WebRTC(ch) = ch {
+            if let ViamChannel::WebRTC(ch) = &ch {
+                if let Some(latency) = ch.estimate_one_way_latency().await {
+                    writeln!(
+                        out,
+                        "estimated one-way latency across established WebRTC connection: {}ms",
+                        latency.as_millis(),
+                    )?;
+                }
                 let sr = stats::StatsReport(ch.get_stats().await);
                 write!(out, "{sr}")?;
             }
+            ch.close().await?;
         }
 
         // If mDNS could not be used to connect; show discovered mDNS addresses on current
@@ -329,6 +376,22 @@ pub(crate) async fn main_inner(args: Args) -> Result<()> {
 
         writeln!(out, "\nDone debugging dial with WebRTC.")?;
     }
+    if let Some(diagnostics) = args.diagnostics {
+        writeln!(out, "\nCapturing diagnostic bundle...")?;
+        let opts = webrtc_builder(
+            uri.as_str(),
+            credential.as_str(),
+            credential_type.as_str(),
+            args.entity.clone(),
+            args.allow_insecure_credentials,
+        );
+        dial::capture_diagnostics(opts, &diagnostics).await?;
+        writeln!(
+            out,
+            "diagnostic bundle written to {}",
+            diagnostics.display()
+        )?;
+    }
 
     Ok(())
 }
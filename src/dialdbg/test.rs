@@ -1,14 +1,63 @@
 /// Tests dialdbg against an echo server running on localhost:$SERVER_PORT.
 use crate::{main_inner, Args};
+use clap::Parser;
 use std::env;
 
 #[tokio::test]
 async fn dial() {
-    let mut args = Args::default();
     let port = env::var("SERVER_PORT").unwrap().to_owned();
-    args.uri = Some(["localhost:".to_string(), port].join(""));
+    let uri = ["localhost:".to_string(), port].join("");
+    let args = Args::parse_from(["viam-dialdbg", "--uri", &uri]);
 
     // NOTE(benjirewis): simply assert that main_inner returned no error. It may be overkill right
     // now to assert anything about the output.
     assert!(main_inner(args).await.is_ok());
 }
+
+#[test]
+fn rtt_pings_must_be_positive() {
+    let args = Args::parse_from(["viam-dialdbg", "--uri", "localhost:8080"]);
+    assert_eq!(args.rtt_pings, 10);
+
+    let args = Args::parse_from([
+        "viam-dialdbg",
+        "--uri",
+        "localhost:8080",
+        "--rtt-pings",
+        "3",
+    ]);
+    assert_eq!(args.rtt_pings, 3);
+
+    let res = Args::try_parse_from([
+        "viam-dialdbg",
+        "--uri",
+        "localhost:8080",
+        "--rtt-pings",
+        "0",
+    ]);
+    assert!(res.is_err());
+}
+
+#[test]
+fn allow_insecure_credentials_needs_a_credential() {
+    let args = Args::parse_from(["viam-dialdbg", "--uri", "localhost:8080"]);
+    assert!(!args.allow_insecure_credentials);
+
+    let args = Args::parse_from([
+        "viam-dialdbg",
+        "--uri",
+        "localhost:8080",
+        "--credential",
+        "secret",
+        "--allow-insecure-credentials",
+    ]);
+    assert!(args.allow_insecure_credentials);
+
+    let res = Args::try_parse_from([
+        "viam-dialdbg",
+        "--uri",
+        "localhost:8080",
+        "--allow-insecure-credentials",
+    ]);
+    assert!(res.is_err());
+}
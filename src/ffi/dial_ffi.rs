@@ -4,12 +4,13 @@
 //! to implement webRTC or authentication. The module creates a UDS socket that a gRPC client can connect to
 //!
 
-use http::uri::Uri;
-use std::{ptr, time::Duration};
+use http::{uri::Uri, HeaderMap, Request, Response};
+use std::{fmt::Display, ptr, time::Duration};
 use tokio::runtime::Runtime;
 use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use tokio::time::timeout;
-use tracing::Level;
+use tracing::{Level, Span};
 
 use crate::rpc::dial::{
     DialBuilder, DialOptions, RPCCredentials, ViamChannel, WithCredentials, WithoutCredentials,
@@ -17,11 +18,16 @@ use crate::rpc::dial::{
 use libc::c_char;
 
 use crate::proxy;
+use crate::rpc::rtt::measure_rtt;
 use hyper::Server;
 use std::ffi::{CStr, CString};
 use tower::{make::Shared, ServiceBuilder};
 use tower_http::{
-    trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer},
+    classify::{GrpcErrorsAsFailures, SharedClassifier},
+    trace::{
+        DefaultMakeSpan, DefaultOnBodyChunk, DefaultOnEos, DefaultOnFailure, DefaultOnRequest,
+        DefaultOnResponse, MakeSpan, OnEos, OnFailure, OnRequest, OnResponse, TraceLayer,
+    },
     LatencyUnit,
 };
 
@@ -29,18 +35,57 @@ use anyhow::Result;
 
 use crate::proxy::grpc_proxy::GRPCProxy;
 
+// How long to wait for buffered webRTC writes to be sent when freeing the runtime
+const WEBRTC_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+// How long to wait for the proxy servers to finish serving once signaled to shut down
+const SERVER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// The DialFfi interface, returned as a pointer by init_rust_runtime. User should keep this pointer until freeing the runtime.
 pub struct DialFfi {
     runtime: Option<Runtime>,
-    sigs: Option<Vec<oneshot::Sender<()>>>,
-    channels: Vec<ViamChannel>,
+    // the shutdown signals, servers and channels of the proxies, by UDS path
+    sigs: Option<Vec<(String, oneshot::Sender<()>)>>,
+    servers: Vec<(String, JoinHandle<()>)>,
+    channels: Vec<(String, ViamChannel)>,
+    // how the proxies dialed from now on trace the requests they serve
+    trace: ProxyTrace,
 }
 
 impl Drop for DialFfi {
     fn drop(&mut self) {
         log::debug!("FFI runtime closing");
-        if let Some(r) = self.runtime.take() {
-            r.shutdown_timeout(Duration::from_secs(1));
+        // Stop the proxy servers before the runtime goes away, so that dropping a DialFfi
+        // without calling free_rust_runtime doesn't tear down servers mid-serve.
+        for (_, sig) in self.sigs.take().unwrap_or_default() {
+            let _ = sig.send(());
+        }
+        let servers = std::mem::take(&mut self.servers);
+        let runtime = self.runtime.take();
+        match tokio::runtime::Handle::try_current() {
+            // Blocking would panic inside a runtime, so finish the cleanup on it instead.
+            Ok(handle) => {
+                handle.spawn(async move {
+                    wait_for_servers(servers).await;
+                    if let Some(r) = runtime {
+                        r.shutdown_background();
+                    }
+                });
+            }
+            Err(_) => {
+                if let Some(r) = runtime {
+                    r.block_on(wait_for_servers(servers));
+                    r.shutdown_timeout(Duration::from_secs(1));
+                }
+            }
+        }
+    }
+}
+
+// Waits for each proxy server, already signaled to shut down, to finish serving.
+async fn wait_for_servers(servers: Vec<(String, JoinHandle<()>)>) {
+    for (path, server) in servers {
+        if timeout(SERVER_SHUTDOWN_TIMEOUT, server).await.is_err() {
+            log::debug!("Proxy server at {path} didn't shut down in time");
         }
     }
 }
@@ -50,18 +95,79 @@ impl DialFfi {
         Self {
             runtime: Some(Runtime::new().unwrap()),
             sigs: None,
+            servers: vec![],
             channels: vec![],
+            trace: ProxyTrace::default(),
         }
     }
-    fn push_signal(&mut self, sig: oneshot::Sender<()>) {
+    fn push_signal(&mut self, path: String, sig: oneshot::Sender<()>) {
         match self.sigs {
-            Some(ref mut v) => v.push(sig),
+            Some(ref mut v) => v.push((path, sig)),
             None => {
-                let v: Vec<oneshot::Sender<()>> = vec![sig];
+                let v: Vec<(String, oneshot::Sender<()>)> = vec![(path, sig)];
                 self.sigs = Some(v);
             }
         }
     }
+    // Signals every proxy server to shut down and waits for them to finish serving.
+    fn shutdown_servers(&mut self) {
+        let sigs = self.sigs.take().unwrap_or_default();
+        let servers = std::mem::take(&mut self.servers);
+        self.stop_servers(sigs, servers);
+    }
+    // Shuts down the proxy serving the given path and closes its channel, leaving the other
+    // proxies running. Returns whether there was such a proxy.
+    fn close_proxy(&mut self, path: &str) -> bool {
+        let (sigs, others) = self
+            .sigs
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|(p, _)| p == path);
+        self.sigs = Some(others);
+        let (servers, others) = std::mem::take(&mut self.servers)
+            .into_iter()
+            .partition(|(p, _)| p == path);
+        self.servers = others;
+        let (channels, others): (Vec<_>, _) = std::mem::take(&mut self.channels)
+            .into_iter()
+            .partition(|(p, _)| p == path);
+        self.channels = others;
+
+        let found = !sigs.is_empty() || !servers.is_empty() || !channels.is_empty();
+        self.stop_servers(sigs, servers);
+        for (_, channel) in &channels {
+            self.close_channel(channel);
+        }
+        found
+    }
+    fn stop_servers(
+        &self,
+        sigs: Vec<(String, oneshot::Sender<()>)>,
+        servers: Vec<(String, JoinHandle<()>)>,
+    ) {
+        for (_, sig) in sigs {
+            let _ = sig.send(());
+        }
+        if let Some(runtime) = &self.runtime {
+            runtime.block_on(wait_for_servers(servers));
+        }
+    }
+    fn close_channel(&self, channel: &ViamChannel) {
+        if let Some(rt) = self.runtime.as_ref() {
+            rt.block_on(async move {
+                // let the tail of any in-flight request reach the robot before closing
+                if let ViamChannel::WebRTC(chan) = channel {
+                    if !chan.drain_writes(WEBRTC_DRAIN_TIMEOUT).await {
+                        log::debug!("Closing webRTC channel with writes still buffered");
+                    }
+                }
+                if let Err(e) = channel.close().await {
+                    log::debug!("Error closing channel: {e}");
+                }
+            })
+        }
+    }
 }
 /// Initialize a tokio runtime to run a gRPC client/sever, user should call this function before trying to dial to a Robot
 /// Returns a pointer to a [`DialFfi`]
@@ -92,6 +198,7 @@ fn dial_with_cred(
     r#type: &str,
     payload: &str,
     allow_insec: bool,
+    allow_insecure_credentials: bool,
     disable_webrtc: bool,
 ) -> Result<DialBuilder<WithCredentials>> {
     let creds = RPCCredentials::new(entity, String::from(r#type), String::from(payload));
@@ -102,9 +209,138 @@ fn dial_with_cred(
         c
     };
     let c = if allow_insec { c.allow_downgrade() } else { c };
+    let c = if allow_insecure_credentials {
+        c.allow_insecure_credentials()
+    } else {
+        c
+    };
     Ok(c)
 }
 
+// Settings for the proxy server dial sets up, `None` leaving hyper's or the OS's default.
+#[derive(Default)]
+struct ProxyOptions {
+    max_concurrent_streams: Option<u32>,
+    backlog: Option<i32>,
+}
+
+// How much of the requests it serves a proxy traces, each level adding to the one before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum TraceVerbosity {
+    Off,
+    // failed requests only
+    Error,
+    // every request and response
+    Info,
+    // the end of response streams as well
+    Debug,
+}
+
+impl TraceVerbosity {
+    fn from_c(verbosity: i32) -> Option<Self> {
+        match verbosity {
+            0 => Some(Self::Off),
+            1 => Some(Self::Error),
+            2 => Some(Self::Info),
+            3 => Some(Self::Debug),
+            _ => None,
+        }
+    }
+}
+
+// The tracing of a proxy's requests, standing in for each of the TraceLayer callbacks so that
+// the callbacks the verbosity leaves out trace nothing.
+#[derive(Clone, Copy, Debug)]
+struct ProxyTrace {
+    verbosity: TraceVerbosity,
+    include_headers: bool,
+}
+
+impl Default for ProxyTrace {
+    fn default() -> Self {
+        Self {
+            verbosity: TraceVerbosity::Info,
+            include_headers: true,
+        }
+    }
+}
+
+// Classifies responses by their gRPC status, so that calls failing with one are traced as
+// failures even though their HTTP status is 200.
+type ProxyTraceLayer = TraceLayer<
+    SharedClassifier<GrpcErrorsAsFailures>,
+    ProxyTrace,
+    ProxyTrace,
+    ProxyTrace,
+    DefaultOnBodyChunk,
+    ProxyTrace,
+    ProxyTrace,
+>;
+
+impl ProxyTrace {
+    fn layer(self) -> ProxyTraceLayer {
+        TraceLayer::new_for_grpc()
+            .make_span_with(self)
+            .on_request(self)
+            .on_response(self)
+            .on_eos(self)
+            .on_failure(self)
+    }
+}
+
+impl<B> MakeSpan<B> for ProxyTrace {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        let level = match self.verbosity {
+            TraceVerbosity::Off => return Span::none(),
+            TraceVerbosity::Error => Level::ERROR,
+            TraceVerbosity::Info | TraceVerbosity::Debug => Level::DEBUG,
+        };
+        DefaultMakeSpan::new()
+            .level(level)
+            .include_headers(self.include_headers)
+            .make_span(request)
+    }
+}
+
+impl<B> OnRequest<B> for ProxyTrace {
+    fn on_request(&mut self, request: &Request<B>, span: &Span) {
+        if self.verbosity >= TraceVerbosity::Info {
+            DefaultOnRequest::new()
+                .level(Level::INFO)
+                .on_request(request, span);
+        }
+    }
+}
+
+impl<B> OnResponse<B> for ProxyTrace {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        if self.verbosity >= TraceVerbosity::Info {
+            DefaultOnResponse::new()
+                .level(Level::INFO)
+                .latency_unit(LatencyUnit::Micros)
+                .on_response(response, latency, span);
+        }
+    }
+}
+
+impl OnEos for ProxyTrace {
+    fn on_eos(self, trailers: Option<&HeaderMap>, stream_duration: Duration, span: &Span) {
+        if self.verbosity >= TraceVerbosity::Debug {
+            DefaultOnEos::new().on_eos(trailers, stream_duration, span);
+        }
+    }
+}
+
+impl<FailureClass: Display> OnFailure<FailureClass> for ProxyTrace {
+    fn on_failure(&mut self, failure: FailureClass, latency: Duration, span: &Span) {
+        if self.verbosity >= TraceVerbosity::Error {
+            DefaultOnFailure::new()
+                .latency_unit(LatencyUnit::Micros)
+                .on_failure(failure, latency, span);
+        }
+    }
+}
+
 /// Returns a path to a UDS proxy to a robot
 /// # Safety
 ///
@@ -115,7 +351,10 @@ fn dial_with_cred(
 /// * `c_uri` a C-style string representing the address of robot you want to connect to
 /// * `c_type` a C-style string representing the type of robot's secret you want to use, set to NULL if you don't need authentication
 /// * `c_payload` a C-style string that is the robot's secret, set to NULL if you don't need authentication
-/// * `c_allow_insecure` a bool, set to true when allowing insecure connection to your robot
+/// * `c_allow_insecure` a bool, set to true when allowing insecure connection to your robot.
+///   Credentials are never sent over an insecure connection, so when `c_type` and `c_payload`
+///   are set the dial refuses to fall back to one. Use [`dial_with_options`] with
+///   `c_allow_insecure_credentials` set to true to allow it
 /// * `c_timeout` a float, set how many seconds we should try to dial before timing out
 /// * `rt_ptr` a pointer to a rust runtime previously obtained with init_rust_runtime
 #[no_mangle]
@@ -127,6 +366,76 @@ pub unsafe extern "C" fn dial(
     c_allow_insec: bool,
     c_timeout: f32,
     rt_ptr: Option<&mut DialFfi>,
+) -> *mut c_char {
+    dial_with_proxy_options(
+        c_uri,
+        c_entity,
+        c_type,
+        c_payload,
+        c_allow_insec,
+        false,
+        c_timeout,
+        ProxyOptions::default(),
+        rt_ptr,
+    )
+}
+
+/// Returns a path to a UDS proxy to a robot, like [`dial`], with the proxy tuned for host apps
+/// that make many concurrent calls through it
+/// # Safety
+///
+/// The same as for [`dial`]
+/// # Arguments
+/// The same as for [`dial`], plus:
+/// * `c_allow_insecure_credentials` a bool, set to true to send the credentials even when the
+///   connection to the robot ends up without TLS, where anyone on the network path can read
+///   them. `c_allow_insecure` alone never sends them over plain http
+/// * `c_max_concurrent_streams` how many calls each connection to the proxy can have in flight
+///   at once, further calls waiting for one to finish. 0 leaves it unlimited
+/// * `c_backlog` how many connections to the proxy can wait to be accepted before further ones
+///   are refused. 0 or less leaves the OS default
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn dial_with_options(
+    c_uri: *const c_char,
+    c_entity: *const c_char,
+    c_type: *const c_char,
+    c_payload: *const c_char,
+    c_allow_insec: bool,
+    c_allow_insecure_credentials: bool,
+    c_timeout: f32,
+    c_max_concurrent_streams: u32,
+    c_backlog: i32,
+    rt_ptr: Option<&mut DialFfi>,
+) -> *mut c_char {
+    let options = ProxyOptions {
+        max_concurrent_streams: Some(c_max_concurrent_streams).filter(|n| *n > 0),
+        backlog: Some(c_backlog).filter(|n| *n > 0),
+    };
+    dial_with_proxy_options(
+        c_uri,
+        c_entity,
+        c_type,
+        c_payload,
+        c_allow_insec,
+        c_allow_insecure_credentials,
+        c_timeout,
+        options,
+        rt_ptr,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn dial_with_proxy_options(
+    c_uri: *const c_char,
+    c_entity: *const c_char,
+    c_type: *const c_char,
+    c_payload: *const c_char,
+    c_allow_insec: bool,
+    c_allow_insecure_credentials: bool,
+    c_timeout: f32,
+    proxy_options: ProxyOptions,
+    rt_ptr: Option<&mut DialFfi>,
 ) -> *mut c_char {
     let uri = {
         if c_uri.is_null() {
@@ -161,6 +470,13 @@ pub unsafe extern "C" fn dial(
             return ptr::null_mut();
         }
     };
+    if let Some(backlog) = proxy_options.backlog {
+        if let Err(e) = conn.set_backlog(backlog) {
+            log::error!("Error setting the UDS proxy's backlog {e:?}");
+            return ptr::null_mut();
+        }
+    }
+    let path_str = conn.get_path().to_string();
     let path = match CString::new(conn.get_path()) {
         Ok(s) => s,
         Err(e) => {
@@ -170,6 +486,7 @@ pub unsafe extern "C" fn dial(
     };
     let (tx, rx) = oneshot::channel::<()>();
     let uri_str = uri.to_string();
+    let trace = ctx.trace;
 
     // if the uri is local then we can connect directly.
     let disable_webrtc;
@@ -218,6 +535,7 @@ pub unsafe extern "C" fn dial(
                         t.to_str()?,
                         p.to_str()?,
                         allow_insec,
+                        c_allow_insecure_credentials,
                         disable_webrtc,
                     )?
                     .connect(),
@@ -236,20 +554,10 @@ pub unsafe extern "C" fn dial(
         }?;
         let dial = channel.clone();
         let g = GRPCProxy::new(dial, uri);
-        let service = ServiceBuilder::new()
-            .layer(
-                TraceLayer::new_for_http()
-                    .make_span_with(DefaultMakeSpan::new().include_headers(true))
-                    .on_request(DefaultOnRequest::new().level(Level::INFO))
-                    .on_response(
-                        DefaultOnResponse::new()
-                            .level(Level::INFO)
-                            .latency_unit(LatencyUnit::Micros),
-                    ),
-            )
-            .service(g);
+        let service = ServiceBuilder::new().layer(trace.layer()).service(g);
         let server = Server::builder(conn)
             .http2_only(true)
+            .http2_max_concurrent_streams(proxy_options.max_concurrent_streams)
             .serve(Shared::new(service));
         Ok::<_, Box<dyn std::error::Error>>((server, channel))
     }) {
@@ -259,17 +567,45 @@ pub unsafe extern "C" fn dial(
             return ptr::null_mut();
         }
     };
-    ctx.channels.push(channel);
+    ctx.channels.push((path_str.clone(), channel));
     let server = server.with_graceful_shutdown(async {
         rx.await.ok();
     });
-    let _ = runtime.spawn(async {
+    let server = runtime.spawn(async {
         let _ = server.await;
     });
-    ctx.push_signal(tx);
+    ctx.servers.push((path_str.clone(), server));
+    ctx.push_signal(path_str, tx);
     path.into_raw()
 }
 
+/// Sets how much the proxies dialed afterwards with this runtime trace of the requests they
+/// serve. Returns 0 on success and -1 if the verbosity isn't one of the levels below
+/// # Arguments
+/// * `rt_ptr` a pointer to a rust runtime previously obtained with init_rust_runtime
+/// * `c_verbosity` 0 to trace nothing, 1 for failed requests only, 2 for every request and
+///   response (the default) and 3 to trace the end of response streams as well
+/// * `c_include_headers` a bool, set to false to leave the request headers out of the traces
+#[no_mangle]
+pub extern "C" fn dial_set_trace_verbosity(
+    rt_ptr: Option<&mut DialFfi>,
+    c_verbosity: i32,
+    c_include_headers: bool,
+) -> i32 {
+    let Some(ctx) = rt_ptr else {
+        return -1;
+    };
+    let Some(verbosity) = TraceVerbosity::from_c(c_verbosity) else {
+        log::error!("{c_verbosity} is not a valid trace verbosity");
+        return -1;
+    };
+    ctx.trace = ProxyTrace {
+        verbosity,
+        include_headers: c_include_headers,
+    };
+    0
+}
+
 /// This function must be used to free the path returned by the [`dial`] function
 /// # Safety
 ///
@@ -285,6 +621,136 @@ pub unsafe extern "C" fn free_string(s: *mut c_char) {
     let _ = CString::from_raw(s);
 }
 
+/// Closes a single connection opened by [`dial`]: the proxy serving the given UDS path is shut
+/// down and its connection to the robot is closed, while any other connection made with the same
+/// runtime keeps working. Returns 0 on success and -1 if no proxy serves the path.
+/// # Safety
+///
+/// `c_path` must be a valid C-style string. The path string itself is not freed and should still
+/// be freed with [`free_string`]
+/// # Arguments
+/// * `rt_ptr` a pointer to a rust runtime previously obtained with init_rust_runtime
+/// * `c_path` a C-style string that is the path returned by [`dial`]
+#[no_mangle]
+pub unsafe extern "C" fn close_dial(rt_ptr: Option<&mut DialFfi>, c_path: *const c_char) -> i32 {
+    let ctx = match rt_ptr {
+        Some(ctx) => ctx,
+        None => {
+            return -1;
+        }
+    };
+    if c_path.is_null() {
+        return -1;
+    }
+    let path = match CStr::from_ptr(c_path).to_str() {
+        Ok(path) => path,
+        Err(e) => {
+            log::error!("Error unexpectedly received an invalid path string {e:?}");
+            return -1;
+        }
+    };
+    if !ctx.close_proxy(path) {
+        log::error!("No proxy serves {path}");
+        return -1;
+    }
+    log::debug!("Closed the proxy at {path}");
+    0
+}
+
+/// Measures the average round-trip-time, in milliseconds, of the connection behind a UDS path
+/// returned by [`dial`] by sending it `pings` echo requests one after another. Returns a
+/// negative number if no connection is served at the path, `pings` is 0, none of the pings
+/// reach the robot, or the runtime is gone.
+/// # Safety
+///
+/// `c_path` must be a valid C-style string
+/// # Arguments
+/// * `rt_ptr` a pointer to a rust runtime previously obtained with init_rust_runtime
+/// * `c_path` a C-style string that is the path returned by [`dial`]
+/// * `pings` how many round trips to average over
+#[no_mangle]
+pub unsafe extern "C" fn dial_measure_rtt(
+    rt_ptr: Option<&mut DialFfi>,
+    c_path: *const c_char,
+    pings: u32,
+) -> f64 {
+    let ctx = match rt_ptr {
+        Some(ctx) => ctx,
+        None => {
+            return -1.0;
+        }
+    };
+    if c_path.is_null() {
+        return -1.0;
+    }
+    let path = match CStr::from_ptr(c_path).to_str() {
+        Ok(path) => path,
+        Err(e) => {
+            log::error!("Error unexpectedly received an invalid path string {e:?}");
+            return -1.0;
+        }
+    };
+    let channel = match ctx.channels.iter().find(|(p, _)| p == path) {
+        Some((_, channel)) => channel.clone(),
+        None => {
+            log::error!("No connection is served at {path}");
+            return -1.0;
+        }
+    };
+    let runtime = match &ctx.runtime {
+        Some(r) => r,
+        None => {
+            return -1.0;
+        }
+    };
+    match runtime.block_on(measure_rtt(channel, pings)) {
+        Ok(rtt) => rtt.avg.as_secs_f64() * 1000.0,
+        Err(e) => {
+            log::error!("Error measuring the round-trip-time: {e}");
+            -1.0
+        }
+    }
+}
+
+/// Returns how the connection behind a UDS path returned by [`dial`] reaches the robot: 0 for a
+/// direct gRPC connection, 1 for webRTC and -1 if no connection is served at the path
+/// # Safety
+///
+/// `c_path` must be a valid C-style string
+/// # Arguments
+/// * `rt_ptr` a pointer to a rust runtime previously obtained with init_rust_runtime
+/// * `c_path` a C-style string that is the path returned by [`dial`]
+#[no_mangle]
+pub unsafe extern "C" fn dial_get_transport(
+    rt_ptr: Option<&mut DialFfi>,
+    c_path: *const c_char,
+) -> i32 {
+    let ctx = match rt_ptr {
+        Some(ctx) => ctx,
+        None => {
+            return -1;
+        }
+    };
+    if c_path.is_null() {
+        return -1;
+    }
+    let path = match CStr::from_ptr(c_path).to_str() {
+        Ok(path) => path,
+        Err(e) => {
+            log::error!("Error unexpectedly received an invalid path string {e:?}");
+            return -1;
+        }
+    };
+    match ctx.channels.iter().find(|(p, _)| p == path) {
+        Some((_, ViamChannel::Direct(_) | ViamChannel::DirectPreAuthorized(_))) => 0,
+        Some((_, ViamChannel::WebRTC(_))) => 1,
+        None => {
+            log::error!("No connection is served at {path}");
+            -1
+        }
+    }
+}
+
 /// This function must be used the free a rust runtime returned by [`init_rust_runtime`] the function will signal any
 /// opened server to shutdown. Further transaction on any UDS will not work anymore.
 /// # Safety
@@ -300,23 +766,450 @@ pub extern "C" fn free_rust_runtime(rt_ptr: Option<Box<DialFfi>>) -> i32 {
             return -1;
         }
     };
-    if let Some(sigs) = ctx.sigs.take() {
-        for sig in sigs {
-            let _ = sig.send(());
+    ctx.shutdown_servers();
+
+    for (_, channel) in &ctx.channels {
+        ctx.close_channel(channel);
+    }
+    log::debug!("Freeing rust runtime");
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        close_dial, dial, dial_get_transport, dial_measure_rtt, dial_set_trace_verbosity,
+        dial_with_options, free_string, DialFfi, ProxyTrace, TraceVerbosity,
+    };
+    use crate::gen::proto::rpc::examples::echo::v1::{
+        echo_service_client::EchoServiceClient,
+        echo_service_server::{EchoService, EchoServiceServer},
+        EchoBiDiRequest, EchoBiDiResponse, EchoMultipleRequest, EchoMultipleResponse, EchoRequest,
+        EchoResponse,
+    };
+    use crate::proxy::uds::UDSConnector;
+    use crate::rpc::{
+        client_channel::WebRTCClientChannel, dial::ViamChannel,
+        webrtc::new_peer_connection_for_client,
+    };
+    use hyper::{service::service_fn, Body, Request, Response, Server};
+    use std::{
+        convert::Infallible,
+        ffi::{CStr, CString},
+        io,
+        path::Path,
+        ptr,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+    use tokio::{net::UnixStream, sync::oneshot};
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tower::{make::Shared, Layer, ServiceExt};
+
+    // An echo service that counts the calls it gets.
+    #[derive(Clone, Default)]
+    struct Echo(Arc<AtomicUsize>);
+
+    #[tonic::async_trait]
+    impl EchoService for Echo {
+        type EchoMultipleStream =
+            futures::stream::Empty<Result<EchoMultipleResponse, tonic::Status>>;
+        type EchoBiDiStream = futures::stream::Empty<Result<EchoBiDiResponse, tonic::Status>>;
+
+        async fn echo(
+            &self,
+            request: tonic::Request<EchoRequest>,
+        ) -> Result<tonic::Response<EchoResponse>, tonic::Status> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(tonic::Response::new(EchoResponse {
+                message: request.into_inner().message,
+            }))
+        }
+
+        async fn echo_multiple(
+            &self,
+            _request: tonic::Request<EchoMultipleRequest>,
+        ) -> Result<tonic::Response<Self::EchoMultipleStream>, tonic::Status> {
+            Err(tonic::Status::unimplemented("echo_multiple"))
+        }
+
+        async fn echo_bi_di(
+            &self,
+            _request: tonic::Request<tonic::Streaming<EchoBiDiRequest>>,
+        ) -> Result<tonic::Response<Self::EchoBiDiStream>, tonic::Status> {
+            Err(tonic::Status::unimplemented("echo_bi_di"))
         }
     }
 
-    for channel in &ctx.channels {
-        match channel {
-            ViamChannel::Direct(_) => (),
-            ViamChannel::DirectPreAuthorized(_) => (),
-            ViamChannel::WebRTC(chan) => ctx
-                .runtime
-                .as_ref()
-                .map(|rt| rt.block_on(async move { chan.close().await }))
-                .unwrap_or_default(),
+    // Serves a proxy stand-in that answers every request with "pong" and registers it with
+    // the runtime. The returned flag is set once the server has finished serving.
+    fn spawn_proxy(ctx: &mut DialFfi) -> (String, Arc<AtomicBool>) {
+        let finished = Arc::new(AtomicBool::new(false));
+        let runtime = ctx.runtime.as_ref().unwrap();
+        let conn = runtime
+            .block_on(async { UDSConnector::new_random() })
+            .unwrap();
+        let path = conn.get_path().to_string();
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = Server::builder(conn)
+            .http2_only(true)
+            .serve(Shared::new(service_fn(|_: Request<Body>| async {
+                Ok::<_, Infallible>(Response::new(Body::from("pong")))
+            })))
+            .with_graceful_shutdown(async {
+                rx.await.ok();
+            });
+        let server_finished = finished.clone();
+        let server = runtime.spawn(async move {
+            let _ = server.await;
+            server_finished.store(true, Ordering::SeqCst);
+        });
+        ctx.servers.push((path.clone(), server));
+        ctx.push_signal(path.clone(), tx);
+        (path, finished)
+    }
+
+    // Makes a request through the proxy at path, returning whether it succeeded.
+    async fn ping(path: &str) -> bool {
+        let Ok(stream) = UnixStream::connect(path).await else {
+            return false;
+        };
+        let (mut sender, connection) = hyper::client::conn::Builder::new()
+            .http2_only(true)
+            .handshake::<_, Body>(stream)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        let request = Request::get("http://proxy/").body(Body::empty()).unwrap();
+        match sender.send_request(request).await {
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
         }
     }
-    log::debug!("Freeing rust runtime");
-    0
+
+    #[test]
+    fn drop_shuts_down_active_proxy_servers() {
+        let mut ctx = DialFfi::new();
+        let (path, finished) = spawn_proxy(&mut ctx);
+
+        // keep a client connected to the proxy while it is torn down
+        assert!(ctx.runtime.as_ref().unwrap().block_on(ping(&path)));
+        drop(ctx);
+
+        assert!(finished.load(Ordering::SeqCst));
+        assert!(!Path::new(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn drop_inside_a_runtime_shuts_down_proxy_servers() {
+        let (ctx, path, finished) = tokio::task::spawn_blocking(|| {
+            let mut ctx = DialFfi::new();
+            let (path, finished) = spawn_proxy(&mut ctx);
+            (ctx, path, finished)
+        })
+        .await
+        .unwrap();
+
+        // dropping here, on a runtime worker, must not block or panic
+        drop(ctx);
+
+        let shut_down = async {
+            while !finished.load(Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), shut_down)
+            .await
+            .expect("proxy server was not shut down");
+        assert!(!Path::new(&path).exists());
+    }
+
+    #[test]
+    fn close_dial_closes_only_the_given_proxy() {
+        let mut ctx = DialFfi::new();
+        let (closed_path, closed_finished) = spawn_proxy(&mut ctx);
+        let (open_path, open_finished) = spawn_proxy(&mut ctx);
+
+        let c_path = CString::new(closed_path.clone()).unwrap();
+        assert_eq!(unsafe { close_dial(Some(&mut ctx), c_path.as_ptr()) }, 0);
+        assert!(closed_finished.load(Ordering::SeqCst));
+        assert!(!Path::new(&closed_path).exists());
+        // closing it again finds nothing to close
+        assert_eq!(unsafe { close_dial(Some(&mut ctx), c_path.as_ptr()) }, -1);
+
+        let runtime = ctx.runtime.as_ref().unwrap();
+        assert!(!runtime.block_on(ping(&closed_path)));
+        assert!(runtime.block_on(ping(&open_path)));
+        assert!(!open_finished.load(Ordering::SeqCst));
+        assert_eq!(ctx.servers.len(), 1);
+        assert_eq!(ctx.sigs.as_ref().unwrap().len(), 1);
+    }
+
+    // Serves the echo service on the runtime, returning the uri to dial it at.
+    fn serve_echo(ctx: &DialFfi, echo: &Echo) -> CString {
+        let address = ctx.runtime.as_ref().unwrap().block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            tokio::spawn(
+                tonic::transport::Server::builder()
+                    .add_service(EchoServiceServer::new(echo.clone()))
+                    .serve_with_incoming(TcpListenerStream::new(listener)),
+            );
+            address
+        });
+        CString::new(format!("localhost:{}", address.port())).unwrap()
+    }
+
+    #[test]
+    fn dial_measure_rtt_pings_the_dialed_robot() {
+        let mut ctx = DialFfi::new();
+        let echo = Echo::default();
+        let uri = serve_echo(&ctx, &echo);
+        let path = unsafe {
+            dial(
+                uri.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                true,
+                5.0,
+                Some(&mut ctx),
+            )
+        };
+        assert!(!path.is_null());
+
+        let rtt = unsafe { dial_measure_rtt(Some(&mut ctx), path, 3) };
+        assert!(rtt >= 0.0, "{rtt}");
+        assert_eq!(echo.0.load(Ordering::SeqCst), 3);
+        assert!(unsafe { dial_measure_rtt(Some(&mut ctx), path, 0) } < 0.0);
+
+        assert_eq!(unsafe { close_dial(Some(&mut ctx), path) }, 0);
+        assert!(unsafe { dial_measure_rtt(Some(&mut ctx), path, 3) } < 0.0);
+        unsafe { free_string(path) };
+    }
+
+    #[test]
+    fn dial_measure_rtt_fails_on_a_closed_connection() {
+        let mut ctx = DialFfi::new();
+        let channel = ctx.runtime.as_ref().unwrap().block_on(async {
+            let (peer_connection, data_channel) =
+                new_peer_connection_for_client(Default::default(), true, None, None, None)
+                    .await
+                    .unwrap();
+            let channel = WebRTCClientChannel::new(peer_connection, data_channel).await;
+            channel.close().await;
+            channel
+        });
+        ctx.channels
+            .push(("closed.sock".to_string(), ViamChannel::WebRTC(channel)));
+        let path = CString::new("closed.sock").unwrap();
+
+        assert_eq!(
+            unsafe { dial_measure_rtt(Some(&mut ctx), path.as_ptr(), 3) },
+            -1.0
+        );
+    }
+
+    #[test]
+    fn dial_get_transport_reports_the_connection_kind() {
+        let mut ctx = DialFfi::new();
+        let echo = Echo::default();
+        let uri = serve_echo(&ctx, &echo);
+        let path = unsafe {
+            dial(
+                uri.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                true,
+                5.0,
+                Some(&mut ctx),
+            )
+        };
+        assert!(!path.is_null());
+        assert_eq!(unsafe { dial_get_transport(Some(&mut ctx), path) }, 0);
+
+        // a webRTC connection registered the way dial registers one
+        let channel = ctx.runtime.as_ref().unwrap().block_on(async {
+            let (peer_connection, data_channel) =
+                new_peer_connection_for_client(Default::default(), true, None, None, None)
+                    .await
+                    .unwrap();
+            WebRTCClientChannel::new(peer_connection, data_channel).await
+        });
+        ctx.channels
+            .push(("webrtc.sock".to_string(), ViamChannel::WebRTC(channel)));
+        let webrtc_path = CString::new("webrtc.sock").unwrap();
+        assert_eq!(
+            unsafe { dial_get_transport(Some(&mut ctx), webrtc_path.as_ptr()) },
+            1
+        );
+
+        assert_eq!(unsafe { close_dial(Some(&mut ctx), path) }, 0);
+        assert_eq!(unsafe { dial_get_transport(Some(&mut ctx), path) }, -1);
+        assert_eq!(
+            unsafe { dial_get_transport(None, webrtc_path.as_ptr()) },
+            -1
+        );
+        unsafe { free_string(path) };
+    }
+
+    #[test]
+    fn tuned_proxy_completes_many_concurrent_calls() {
+        let mut ctx = DialFfi::new();
+        let echo = Echo::default();
+        let uri = serve_echo(&ctx, &echo);
+        let path = unsafe {
+            dial_with_options(
+                uri.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                true,
+                false,
+                5.0,
+                4,
+                256,
+                Some(&mut ctx),
+            )
+        };
+        assert!(!path.is_null());
+        let proxy_path = unsafe { CStr::from_ptr(path) }
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        // many connections to the proxy, each with more calls in flight than it allows at once
+        let (connections, calls_per_connection) = (20, 10);
+        let runtime = ctx.runtime.as_ref().unwrap();
+        let replies = runtime.block_on(futures::future::join_all((0..connections).map(|i| {
+            let proxy_path = proxy_path.clone();
+            async move {
+                let channel = tonic::transport::Endpoint::from_static("http://proxy")
+                    .connect_with_connector(tower::service_fn(move |_| {
+                        UnixStream::connect(proxy_path.clone())
+                    }))
+                    .await
+                    .unwrap();
+                let calls = (0..calls_per_connection).map(|j| {
+                    let mut client = EchoServiceClient::new(channel.clone());
+                    async move {
+                        let message = format!("{i}-{j}");
+                        let request = EchoRequest {
+                            message: message.clone(),
+                        };
+                        let reply = client.echo(request).await.unwrap().into_inner().message;
+                        reply == message
+                    }
+                });
+                futures::future::join_all(calls).await
+            }
+        })));
+        let replies: Vec<bool> = replies.into_iter().flatten().collect();
+        assert_eq!(replies.len(), connections * calls_per_connection);
+        assert!(replies.iter().all(|matched| *matched));
+        assert_eq!(
+            echo.0.load(Ordering::SeqCst),
+            connections * calls_per_connection
+        );
+        unsafe { free_string(path) };
+    }
+
+    // Collects what a tracing subscriber writes.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Serves a request through a service traced with the given verbosity, returning the traces.
+    fn traces(verbosity: TraceVerbosity) -> String {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_writer(move || writer.clone())
+            .finish();
+        let trace = ProxyTrace {
+            verbosity,
+            include_headers: true,
+        };
+        let service = trace.layer().layer(service_fn(|_: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::from("pong")))
+        }));
+        tracing::subscriber::with_default(subscriber, || {
+            let request = Request::get("http://proxy/").body(Body::empty()).unwrap();
+            let response = futures::executor::block_on(service.oneshot(request)).unwrap();
+            futures::executor::block_on(hyper::body::to_bytes(response.into_body())).unwrap();
+        });
+        let traces = captured.0.lock().unwrap();
+        String::from_utf8_lossy(&traces).into_owned()
+    }
+
+    #[test]
+    fn trace_verbosity_controls_request_traces() {
+        assert_eq!(traces(TraceVerbosity::Off), "");
+        // a successful request is no failure
+        assert_eq!(traces(TraceVerbosity::Error), "");
+        let info = traces(TraceVerbosity::Info);
+        assert!(info.contains("started processing request"), "{info}");
+        assert!(info.contains("finished processing request"), "{info}");
+
+        let mut ctx = DialFfi::new();
+        assert_eq!(dial_set_trace_verbosity(Some(&mut ctx), 0, false), 0);
+        assert_eq!(ctx.trace.verbosity, TraceVerbosity::Off);
+        assert!(!ctx.trace.include_headers);
+        assert_eq!(dial_set_trace_verbosity(Some(&mut ctx), 4, true), -1);
+        assert_eq!(ctx.trace.verbosity, TraceVerbosity::Off);
+    }
+
+    #[tokio::test]
+    async fn calls_failing_with_a_grpc_status_are_traced_as_failures() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _subscriber = tracing::subscriber::set_default(subscriber);
+        let trace = ProxyTrace {
+            verbosity: TraceVerbosity::Error,
+            include_headers: false,
+        };
+        let mut client =
+            EchoServiceClient::new(trace.layer().layer(EchoServiceServer::new(Echo::default())));
+
+        client
+            .echo(EchoRequest {
+                message: "hi".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&captured.0.lock().unwrap()), "");
+
+        let status = client
+            .echo_multiple(EchoMultipleRequest {
+                message: "hi".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+        let traces = String::from_utf8_lossy(&captured.0.lock().unwrap()).into_owned();
+        assert!(traces.contains("response failed"), "{traces}");
+        assert!(
+            traces.contains(&format!("Code: {}", tonic::Code::Unimplemented as i32)),
+            "{traces}"
+        );
+    }
 }
@@ -0,0 +1,84 @@
+//! The FFI interface for checking the memory layout of the spatialmath types
+//! shared with other languages. Bindings that read or write these types
+//! directly should compare the reported version against the one they were
+//! written for.
+
+use std::mem::{offset_of, size_of};
+
+use nalgebra::{Quaternion, Rotation3, Vector3};
+
+use crate::spatialmath::{
+    pose::Pose,
+    similarity::Similarity,
+    utils::{AxisAngle, EulerAngles, OrientationVector},
+};
+
+/// The version of the memory layout of the spatialmath types passed across the
+/// FFI boundary. Any change to the layouts asserted below must bump this.
+pub const SPATIALMATH_ABI_VERSION: u32 = 1;
+
+// A layout change fails compilation here. After updating these assertions,
+// bump SPATIALMATH_ABI_VERSION and the expected version in the tests below.
+const _: () = {
+    // nalgebra stores quaternions as [i, j, k, real]
+    assert!(size_of::<Quaternion<f64>>() == 32);
+    assert!(size_of::<Vector3<f64>>() == 24);
+    assert!(size_of::<Rotation3<f64>>() == 72);
+
+    assert!(size_of::<EulerAngles>() == 24);
+    assert!(offset_of!(EulerAngles, roll) == 0);
+    assert!(offset_of!(EulerAngles, pitch) == 8);
+    assert!(offset_of!(EulerAngles, yaw) == 16);
+
+    assert!(size_of::<AxisAngle>() == 32);
+    assert!(offset_of!(AxisAngle, axis) == 0);
+    assert!(offset_of!(AxisAngle, theta) == 24);
+
+    assert!(size_of::<OrientationVector>() == 32);
+    assert!(offset_of!(OrientationVector, o_vector) == 0);
+    assert!(offset_of!(OrientationVector, theta) == 24);
+
+    assert!(size_of::<Pose>() == 56);
+    assert!(offset_of!(Pose, translation) == 0);
+    assert!(offset_of!(Pose, orientation) == 24);
+
+    assert!(size_of::<Similarity>() == 64);
+    assert!(offset_of!(Similarity, translation) == 0);
+    assert!(offset_of!(Similarity, orientation) == 24);
+    assert!(offset_of!(Similarity, scale) == 56);
+};
+
+/// Returns the version of the memory layout of the spatialmath types
+/// exposed over FFI
+#[no_mangle]
+pub extern "C" fn spatialmath_abi_version() -> u32 {
+    SPATIALMATH_ABI_VERSION
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Quaternion, Vector3};
+
+    use super::spatialmath_abi_version;
+    use crate::spatialmath::{pose::Pose, utils::EulerAngles};
+
+    fn as_doubles<T, const N: usize>(value: &T) -> [f64; N] {
+        assert_eq!(std::mem::size_of::<T>(), N * std::mem::size_of::<f64>());
+        unsafe { *(value as *const T as *const [f64; N]) }
+    }
+
+    #[test]
+    fn abi_version_matches_layout() {
+        assert_eq!(spatialmath_abi_version(), 1);
+
+        // version 1 layouts, component by component
+        let quat = Quaternion::new(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(as_doubles(&quat), [2.0, 3.0, 4.0, 1.0]);
+        let vec = Vector3::new(1.0, 2.0, 3.0);
+        assert_eq!(as_doubles(&vec), [1.0, 2.0, 3.0]);
+        let euler_angles = EulerAngles::new(1.0, 2.0, 3.0);
+        assert_eq!(as_doubles(&euler_angles), [1.0, 2.0, 3.0]);
+        let pose = Pose::new(vec, Quaternion::new(1.0, 0.0, 0.0, 0.0));
+        assert_eq!(as_doubles(&pose), [1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0]);
+    }
+}
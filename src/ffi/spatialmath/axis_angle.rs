@@ -1,7 +1,9 @@
 use ffi_helpers::null_pointer_check;
-use nalgebra::Quaternion;
+use nalgebra::{Quaternion, Vector3};
 
-use crate::spatialmath::utils::AxisAngle;
+use crate::{
+    ffi::spatialmath::vector3::to_raw_pointer as vec_to_raw_pointer, spatialmath::utils::AxisAngle,
+};
 
 /// The FFI interface for initializing axis angles. These are
 /// R4 axis angles (meaning they are represented by the x, y, z
@@ -64,3 +66,38 @@ pub unsafe extern "C" fn axis_angle_from_quaternion(
     };
     to_raw_pointer(&axis_angle)
 }
+
+/// Converts an R4 axis angle into a rotation vector, which is the axis
+/// scaled by the rotation angle theta
+///
+/// # Safety
+///
+/// When finished with the underlying axis angle passed to this function
+/// the caller must remember to free the axis angle memory using the
+/// free_axis_angles_memory FFI function and the vector memory using
+/// the free_vector_memory function
+#[no_mangle]
+pub unsafe extern "C" fn axis_angle_to_rotation_vector(
+    aa_ptr: *const AxisAngle,
+) -> *mut Vector3<f64> {
+    null_pointer_check!(aa_ptr);
+    vec_to_raw_pointer((*aa_ptr).to_rotation_vector())
+}
+
+/// Converts a rotation vector into an R4 axis angle whose theta is the
+/// magnitude of the vector and whose axis is its direction. A zero vector
+/// returns a zero axis angle.
+///
+/// # Safety
+///
+/// When finished with the underlying vector passed to this function
+/// the caller must remember to free the vector memory using the
+/// free_vector_memory FFI function and the axis angle memory using
+/// the free_axis_angles_memory function
+#[no_mangle]
+pub unsafe extern "C" fn axis_angle_from_rotation_vector(
+    vec_ptr: *const Vector3<f64>,
+) -> *mut AxisAngle {
+    null_pointer_check!(vec_ptr);
+    to_raw_pointer(&AxisAngle::from_rotation_vector(&*vec_ptr))
+}
@@ -61,3 +61,87 @@ pub unsafe extern "C" fn euler_angles_from_quaternion(
     let euler_angles: EulerAngles = (*quat_ptr).into();
     to_raw_pointer(&euler_angles)
 }
+
+/// Converts a contiguous array of count quaternions into euler angles (in radians),
+/// as euler_angles_from_quaternion does for each one, returning a pointer to a
+/// contiguous array of count euler angles. Returns a null pointer if count is zero.
+///
+/// # Safety
+///
+/// quat_array_ptr must point to count quaternions laid out one after another. When
+/// finished with the euler angles the caller must remember to free their memory using
+/// the free_euler_angles_array FFI function, passing the same count
+#[no_mangle]
+pub unsafe extern "C" fn euler_angles_from_quaternions(
+    quat_array_ptr: *const Quaternion<f64>,
+    count: usize,
+) -> *mut EulerAngles {
+    null_pointer_check!(quat_array_ptr);
+    if count == 0 {
+        return std::ptr::null_mut();
+    }
+    let euler_angles: Box<[EulerAngles]> = std::slice::from_raw_parts(quat_array_ptr, count)
+        .iter()
+        .map(|&quat| quat.into())
+        .collect();
+    Box::into_raw(euler_angles) as *mut EulerAngles
+}
+
+/// Free memory at the address of an array of euler angles returned by
+/// euler_angles_from_quaternions.
+///
+/// # Safety
+///
+/// count must be the number of euler angles in the array, and the function must not
+/// be called more than once with the same pointer
+#[no_mangle]
+pub unsafe extern "C" fn free_euler_angles_array(ptr: *mut EulerAngles, count: usize) {
+    if ptr.is_null() {
+        return;
+    }
+    let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, count));
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Quaternion;
+
+    use super::{
+        euler_angles_from_quaternion, euler_angles_from_quaternions, free_euler_angles_array,
+        free_euler_angles_memory,
+    };
+
+    #[test]
+    fn batch_conversion_matches_single_conversions() {
+        let quats = [
+            Quaternion::identity(),
+            Quaternion::new(
+                0.2705980500730985,
+                -0.6532814824381882,
+                0.27059805007309856,
+                0.6532814824381883,
+            ),
+            Quaternion::new(
+                0.816632212270443,
+                -0.17555966025413142,
+                0.39198397193979817,
+                0.3855375485164001,
+            ),
+        ];
+        unsafe {
+            let batch = euler_angles_from_quaternions(quats.as_ptr(), quats.len());
+            let converted = std::slice::from_raw_parts(batch, quats.len());
+            for (quat, batch_angles) in quats.iter().zip(converted) {
+                let single = euler_angles_from_quaternion(quat);
+                assert_eq!(batch_angles.roll, (*single).roll);
+                assert_eq!(batch_angles.pitch, (*single).pitch);
+                assert_eq!(batch_angles.yaw, (*single).yaw);
+                free_euler_angles_memory(single);
+            }
+            free_euler_angles_array(batch, quats.len());
+
+            assert!(euler_angles_from_quaternions(quats.as_ptr(), 0).is_null());
+            assert!(euler_angles_from_quaternions(std::ptr::null(), 1).is_null());
+        }
+    }
+}
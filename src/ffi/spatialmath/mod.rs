@@ -1,6 +1,9 @@
+pub mod abi;
 pub mod axis_angle;
 pub mod euler_angles;
 pub mod orientation_vector;
+pub mod pose;
 pub mod quaternion;
 pub mod rotation_matrix;
+pub mod similarity;
 pub mod vector3;
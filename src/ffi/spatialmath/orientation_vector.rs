@@ -93,3 +93,73 @@ pub unsafe extern "C" fn orientation_vector_from_quaternion(
     let o_vec: OrientationVector = (*quat_ptr).into();
     to_raw_pointer(&o_vec)
 }
+
+/// Converts a quaternion into an orientation vector and writes its components into
+/// the caller's buffer in the order (o_x, o_y, o_z, theta), without allocating an
+/// orientation vector or a list of components. Returns false, leaving the buffer
+/// untouched, if either pointer is null.
+///
+/// # Safety
+///
+/// out_ptr must point to a buffer of at least 4 doubles. When finished with the
+/// underlying quaternion passed to this function the caller must remember to free
+/// the quaternion memory using the free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_write_orientation_vector(
+    quat_ptr: *const Quaternion<f64>,
+    out_ptr: *mut c_double,
+) -> bool {
+    null_pointer_check!(quat_ptr, false);
+    null_pointer_check!(out_ptr, false);
+    let o_vec: OrientationVector = (*quat_ptr).into();
+    let components = [
+        o_vec.o_vector.x,
+        o_vec.o_vector.y,
+        o_vec.o_vector.z,
+        o_vec.theta,
+    ];
+    std::ptr::copy_nonoverlapping(components.as_ptr(), out_ptr, components.len());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Quaternion;
+
+    use super::{
+        free_orientation_vector_memory, orientation_vector_from_quaternion,
+        orientation_vector_get_components, quaternion_write_orientation_vector,
+    };
+
+    #[test]
+    fn write_orientation_vector_matches_two_step_conversion() {
+        let quat = Quaternion::new(
+            0.816632212270443,
+            -0.17555966025413142,
+            0.39198397193979817,
+            0.3855375485164001,
+        );
+        let mut written = [0.0; 4];
+        unsafe {
+            assert!(quaternion_write_orientation_vector(
+                &quat,
+                written.as_mut_ptr()
+            ));
+
+            let ov_ptr = orientation_vector_from_quaternion(&quat);
+            let components = orientation_vector_get_components(ov_ptr);
+            assert_eq!(written, *(components as *const [f64; 4]));
+            drop(Box::from_raw(components as *mut [f64; 4]));
+            free_orientation_vector_memory(ov_ptr);
+
+            assert!(!quaternion_write_orientation_vector(
+                std::ptr::null(),
+                written.as_mut_ptr()
+            ));
+            assert!(!quaternion_write_orientation_vector(
+                &quat,
+                std::ptr::null_mut()
+            ));
+        }
+    }
+}
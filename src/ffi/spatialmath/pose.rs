@@ -0,0 +1,121 @@
+//! The FFI interface for poses. A pose is a rigid transform made up of a
+//! translation (a 3-vector) and an orientation (a quaternion using the
+//! Real-I-J-K standard).
+
+use ffi_helpers::null_pointer_check;
+use nalgebra::{Quaternion, Vector3};
+
+use crate::{
+    ffi::spatialmath::{
+        quaternion::to_raw_pointer as quat_to_raw_pointer,
+        vector3::to_raw_pointer as vec_to_raw_pointer,
+    },
+    spatialmath::pose::Pose,
+};
+
+/// Allocates a copy of the pose to the heap with a stable memory address and
+/// returns the raw pointer (for use by the FFI interface)
+fn to_raw_pointer(pose: &Pose) -> *mut Pose {
+    Box::into_raw(Box::new(*pose))
+}
+
+/// Free memory at the address of the pose pointer.
+///
+/// # Safety
+///
+/// Outer processes that work with poses via the FFI interface MUST remember
+/// to call this function when finished with a pose instance
+#[no_mangle]
+pub unsafe extern "C" fn free_pose_memory(ptr: *mut Pose) {
+    if ptr.is_null() {
+        return;
+    }
+    let _ = Box::from_raw(ptr);
+}
+
+/// Initialize a pose from a pointer to a translation vector and a pointer to an
+/// orientation quaternion and retrieve the C pointer to its address. The
+/// orientation is normalized.
+///
+/// # Safety
+///
+/// When finished with the underlying pose initialized by this function
+/// the caller must remember to free the pose memory using the
+/// free_pose_memory FFI function. The vector and quaternion are copied, so
+/// they must still be freed separately
+#[no_mangle]
+pub unsafe extern "C" fn new_pose(
+    translation_ptr: *const Vector3<f64>,
+    orientation_ptr: *const Quaternion<f64>,
+) -> *mut Pose {
+    null_pointer_check!(translation_ptr);
+    null_pointer_check!(orientation_ptr);
+    to_raw_pointer(&Pose::new(*translation_ptr, *orientation_ptr))
+}
+
+/// Copies the translation of a pose to a 3-vector and returns a pointer to the
+/// memory address of the resulting vector
+///
+/// # Safety
+///
+/// The caller must remember to free the pose memory and the vector memory when
+/// finished with them using the free_pose_memory and free_vector_memory FFI functions
+#[no_mangle]
+pub unsafe extern "C" fn pose_get_translation(pose_ptr: *const Pose) -> *mut Vector3<f64> {
+    null_pointer_check!(pose_ptr);
+    vec_to_raw_pointer((*pose_ptr).translation)
+}
+
+/// Copies the orientation of a pose to a quaternion and returns a pointer to the
+/// memory address of the resulting quaternion
+///
+/// # Safety
+///
+/// The caller must remember to free the pose memory and the quaternion memory when
+/// finished with them using the free_pose_memory and free_quaternion_memory FFI functions
+#[no_mangle]
+pub unsafe extern "C" fn pose_get_orientation(pose_ptr: *const Pose) -> *mut Quaternion<f64> {
+    null_pointer_check!(pose_ptr);
+    quat_to_raw_pointer(&(*pose_ptr).orientation)
+}
+
+/// Computes the pose stored at pose_ptr relative to the pose stored at
+/// reference_ptr and returns a pointer to the memory of the result. Composing
+/// the reference with the result reconstructs the original pose.
+///
+/// # Safety
+///
+/// The caller must remember to free the pose memory of *both* the input and
+/// output poses when finished with them using the free_pose_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn pose_relative_to(
+    pose_ptr: *const Pose,
+    reference_ptr: *const Pose,
+) -> *mut Pose {
+    null_pointer_check!(pose_ptr);
+    null_pointer_check!(reference_ptr);
+    to_raw_pointer(&(*pose_ptr).relative_to(&*reference_ptr))
+}
+
+/// Averages an array of pose pointers, averaging their translations
+/// arithmetically and their orientations as quaternions, and returns a pointer
+/// to the memory of the result. Returns a null pointer if len is zero.
+///
+/// # Safety
+///
+/// pose_ptrs must point to an array of len pose pointers. The caller must
+/// remember to free the pose memory of *both* the input and output poses when
+/// finished with them using the free_pose_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn pose_get_average(pose_ptrs: *const *const Pose, len: usize) -> *mut Pose {
+    null_pointer_check!(pose_ptrs);
+    let mut poses = Vec::with_capacity(len);
+    for &pose_ptr in std::slice::from_raw_parts(pose_ptrs, len) {
+        null_pointer_check!(pose_ptr);
+        poses.push(*pose_ptr);
+    }
+    match Pose::average(&poses) {
+        Some(average) => to_raw_pointer(&average),
+        None => std::ptr::null_mut(),
+    }
+}
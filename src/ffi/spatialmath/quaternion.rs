@@ -1,10 +1,15 @@
 use ffi_helpers::null_pointer_check;
 use libc::c_double;
 use nalgebra::{Normed, Quaternion, Rotation3, UnitQuaternion, UnitVector3, Vector3};
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
     ffi::spatialmath::vector3::to_raw_pointer as vec_to_raw_pointer,
-    spatialmath::utils::{rotate_vector_by_quaternion, OrientationVector},
+    spatialmath::utils::{
+        self, angular_velocity_to, integrate_angular_velocity, quaternion_angle_about_axis,
+        quaternion_weighted_average, quaternions_same_rotation, rotate_vector_by_quaternion,
+        OrientationVector,
+    },
 };
 
 /// The FFI interface wrapper around the nalgebra crate for Quaternion functions
@@ -16,7 +21,7 @@ use crate::{
 
 /// Allocates a copy of the quaternion to the heap with a stable memory address and
 /// returns the raw pointer (for use by the FFI interface)
-fn to_raw_pointer(quat: &Quaternion<f64>) -> *mut Quaternion<f64> {
+pub(crate) fn to_raw_pointer(quat: &Quaternion<f64>) -> *mut Quaternion<f64> {
     Box::into_raw(Box::new(*quat))
 }
 
@@ -33,6 +38,21 @@ pub extern "C" fn new_quaternion(real: f64, i: f64, j: f64, k: f64) -> *mut Quat
     to_raw_pointer(&Quaternion::new(real, i, j, k))
 }
 
+/// Initialize a random unit quaternion, drawn uniformly from all rotations,
+/// and retrieve the C pointer to its address. The same seed always produces
+/// the same quaternion.
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion initialized by this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub extern "C" fn quaternion_random_uniform(seed: u64) -> *mut Quaternion<f64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    to_raw_pointer(&utils::random_uniform_quaternion(&mut rng))
+}
+
 /// Initialize a quaternion from a real part and a C pointer to a Vector3
 /// and retrieve the C pointer to its address.
 ///
@@ -255,6 +275,90 @@ pub unsafe extern "C" fn quaternion_rotate_vector(
     vec_to_raw_pointer(rotated)
 }
 
+/// Advances the orientation described by a quaternion by an angular velocity (in radians
+/// per second about the axes of the orientation's own frame, as reported by an IMU) held
+/// for dt seconds, and returns the resulting unit quaternion
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion initialized by this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_integrate_angular_velocity(
+    quat_ptr: *const Quaternion<f64>,
+    omega_ptr: *const Vector3<f64>,
+    dt: f64,
+) -> *mut Quaternion<f64> {
+    null_pointer_check!(quat_ptr);
+    null_pointer_check!(omega_ptr);
+    to_raw_pointer(&integrate_angular_velocity(&*quat_ptr, &*omega_ptr, dt))
+}
+
+/// Computes the constant angular velocity (in radians per second about the axes of the
+/// orientation's own frame) that carries the orientation described by one quaternion to
+/// that of a target quaternion in dt seconds. Returns the zero vector if dt is not positive
+///
+/// # Safety
+///
+/// When finished with the underlying vector initialized by this function
+/// the caller must remember to free the vector memory using the
+/// free_vector_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_angular_velocity_to(
+    quat_ptr: *const Quaternion<f64>,
+    target_ptr: *const Quaternion<f64>,
+    dt: f64,
+) -> *mut Vector3<f64> {
+    null_pointer_check!(quat_ptr);
+    null_pointer_check!(target_ptr);
+    vec_to_raw_pointer(angular_velocity_to(&*quat_ptr, &*target_ptr, dt))
+}
+
+/// Spherically interpolates between the rotations of two quaternions, returning the unit
+/// quaternion the fraction t of the way from the first to the second along the shorter way
+/// around
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion initialized by this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_slerp(
+    quat_ptr: *const Quaternion<f64>,
+    other_ptr: *const Quaternion<f64>,
+    t: f64,
+) -> *mut Quaternion<f64> {
+    null_pointer_check!(quat_ptr);
+    null_pointer_check!(other_ptr);
+    to_raw_pointer(&utils::quaternion_slerp(&*quat_ptr, &*other_ptr, t))
+}
+
+/// Limits how far the rotation of a quaternion may be from that of a reference quaternion.
+/// If they are more than max_angle radians apart, the rotation max_angle radians from the
+/// reference towards the quaternion is returned instead of a copy of the quaternion
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion initialized by this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_clamp_angle(
+    quat_ptr: *const Quaternion<f64>,
+    reference_ptr: *const Quaternion<f64>,
+    max_angle: f64,
+) -> *mut Quaternion<f64> {
+    null_pointer_check!(quat_ptr);
+    null_pointer_check!(reference_ptr);
+    to_raw_pointer(&utils::quaternion_clamp_angle(
+        &*quat_ptr,
+        &*reference_ptr,
+        max_angle,
+    ))
+}
+
 /// Converts from euler angles (in radians) to a quaternion. The euler angles are expected to
 /// be represented according to the Tait-Bryan formalism and applied in the Z-Y'-X"
 /// order (where Z -> yaw, Y -> pitch, X -> roll)
@@ -398,6 +502,27 @@ pub unsafe extern "C" fn quaternion_get_conjugate(
     to_raw_pointer(&(*quat_ptr).conjugate())
 }
 
+/// Initializes a quaternion that is the multiplicative inverse of one stored
+/// at the address of a pointer (quat_ptr), its conjugate divided by its squared
+/// norm, and returns a pointer to the memory of the result. Returns a null
+/// pointer if the quaternion's squared norm is within 1e-12 of zero
+///
+/// # Safety
+///
+/// The caller must remember to free the quaternion memory of
+/// *both* the input and output quaternions when finished with them
+/// using the free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_get_inverse(
+    quat_ptr: *const Quaternion<f64>,
+) -> *mut Quaternion<f64> {
+    null_pointer_check!(quat_ptr);
+    match utils::quaternion_inverse(&*quat_ptr) {
+        Some(inverse) => to_raw_pointer(&inverse),
+        None => std::ptr::null_mut(),
+    }
+}
+
 /// Adds two quaternions and returns a pointer to the
 /// memory of the result
 ///
@@ -448,3 +573,151 @@ pub unsafe extern "C" fn quaternion_hamiltonian_product(
     null_pointer_check!(quat_ptr_2);
     to_raw_pointer(&((*quat_ptr_1) * (*quat_ptr_2)))
 }
+
+/// Returns whether two quaternions represent the same rotation to within
+/// epsilon, treating a quaternion and its negation as equal
+///
+/// # Safety
+///
+/// When finished with the underlying quaternions passed to this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_same_rotation(
+    quat_ptr_1: *const Quaternion<f64>,
+    quat_ptr_2: *const Quaternion<f64>,
+    epsilon: f64,
+) -> bool {
+    null_pointer_check!(quat_ptr_1, false);
+    null_pointer_check!(quat_ptr_2, false);
+    quaternions_same_rotation(&*quat_ptr_1, &*quat_ptr_2, epsilon)
+}
+
+/// Returns the angle in radians that the quaternion rotates about the given axis
+/// (the twist of its swing-twist decomposition about that axis), in the range
+/// (-pi, pi]
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion and vector passed to this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function and the vector memory using the
+/// free_vector_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_get_angle_about_axis(
+    quat_ptr: *const Quaternion<f64>,
+    axis_ptr: *const Vector3<f64>,
+) -> f64 {
+    null_pointer_check!(quat_ptr, f64::NAN);
+    null_pointer_check!(axis_ptr, f64::NAN);
+    quaternion_angle_about_axis(&*quat_ptr, &*axis_ptr)
+}
+
+/// Returns the heading (the Tait-Bryan yaw) of the quaternion in radians, measured
+/// as its twist about the Z axis
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion passed to this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_heading(quat_ptr: *const Quaternion<f64>) -> f64 {
+    null_pointer_check!(quat_ptr, f64::NAN);
+    utils::quaternion_heading(&*quat_ptr)
+}
+
+/// Returns the Tait-Bryan pitch of the quaternion in radians, measured as its
+/// twist about the Y axis
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion passed to this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_pitch(quat_ptr: *const Quaternion<f64>) -> f64 {
+    null_pointer_check!(quat_ptr, f64::NAN);
+    utils::quaternion_pitch(&*quat_ptr)
+}
+
+/// Returns the Tait-Bryan roll of the quaternion in radians, measured as its
+/// twist about the X axis
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion passed to this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_roll_about_x(quat_ptr: *const Quaternion<f64>) -> f64 {
+    null_pointer_check!(quat_ptr, f64::NAN);
+    utils::quaternion_roll_about_x(&*quat_ptr)
+}
+
+/// Converts a quaternion into the Rodrigues (Gibbs) parameters of its rotation,
+/// the rotation axis scaled by tan(angle / 2). Returns a null pointer for
+/// rotations by (or within about 1e-9 radians of) pi, where the parameters are
+/// undefined.
+///
+/// # Safety
+///
+/// When finished with the underlying vector initialized by this function
+/// the caller must remember to free the vector memory using the
+/// free_vector_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_to_rodrigues(
+    quat_ptr: *const Quaternion<f64>,
+) -> *mut Vector3<f64> {
+    null_pointer_check!(quat_ptr);
+    match utils::quaternion_to_rodrigues(&*quat_ptr) {
+        Some(rodrigues) => vec_to_raw_pointer(rodrigues),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Converts Rodrigues (Gibbs) parameters, given by a vector, into the unit
+/// quaternion of the rotation they describe
+///
+/// # Safety
+///
+/// When finished with the underlying quaternion initialized by this function
+/// the caller must remember to free the quaternion memory using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_from_rodrigues(
+    vec_ptr: *const Vector3<f64>,
+) -> *mut Quaternion<f64> {
+    null_pointer_check!(vec_ptr);
+    to_raw_pointer(&utils::quaternion_from_rodrigues(&*vec_ptr))
+}
+
+/// Computes the weighted average of the rotations described by an array of
+/// quaternion pointers, weighting each by the entry at the same index of the
+/// weights array. Both arrays must hold len entries. Returns a null pointer
+/// if len is zero or the weights don't sum to a positive value.
+///
+/// # Safety
+///
+/// The caller must remember to free the quaternion memory of *both* the input
+/// and output quaternions when finished with them using the
+/// free_quaternion_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn quaternion_get_weighted_average(
+    quat_ptrs: *const *const Quaternion<f64>,
+    weights: *const f64,
+    len: usize,
+) -> *mut Quaternion<f64> {
+    null_pointer_check!(quat_ptrs);
+    null_pointer_check!(weights);
+    let mut quats = Vec::with_capacity(len);
+    for &quat_ptr in std::slice::from_raw_parts(quat_ptrs, len) {
+        null_pointer_check!(quat_ptr);
+        quats.push(*quat_ptr);
+    }
+    let weights = std::slice::from_raw_parts(weights, len);
+    match quaternion_weighted_average(&quats, weights) {
+        Some(average) => to_raw_pointer(&average),
+        None => std::ptr::null_mut(),
+    }
+}
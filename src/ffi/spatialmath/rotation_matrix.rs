@@ -42,7 +42,8 @@ pub unsafe extern "C" fn new_rotation_matrix(elements: *const [f64; 9]) -> *mut
 }
 
 /// Converts a quaternion into a 3D rotation matrix (a Rotation<f64, 3>
-/// from the nalgebra crate)
+/// from the nalgebra crate). The quaternion FFI's quaternion_from_rotation_matrix
+/// converts back
 ///
 /// # Safety
 ///
@@ -0,0 +1,137 @@
+//! The FFI interface for similarity transforms. A similarity is made up of a
+//! translation (a 3-vector), an orientation (a quaternion using the Real-I-J-K
+//! standard) and a uniform scale.
+
+use ffi_helpers::null_pointer_check;
+use nalgebra::{Quaternion, Vector3};
+
+use crate::{
+    ffi::spatialmath::vector3::to_raw_pointer as vec_to_raw_pointer,
+    spatialmath::similarity::Similarity,
+};
+
+/// Allocates a copy of the similarity to the heap with a stable memory address and
+/// returns the raw pointer (for use by the FFI interface)
+fn to_raw_pointer(similarity: &Similarity) -> *mut Similarity {
+    Box::into_raw(Box::new(*similarity))
+}
+
+/// Free memory at the address of the similarity pointer.
+///
+/// # Safety
+///
+/// Outer processes that work with similarities via the FFI interface MUST remember
+/// to call this function when finished with a similarity instance
+#[no_mangle]
+pub unsafe extern "C" fn free_similarity_memory(ptr: *mut Similarity) {
+    if ptr.is_null() {
+        return;
+    }
+    let _ = Box::from_raw(ptr);
+}
+
+/// Initialize a similarity from a pointer to a translation vector, a pointer to an
+/// orientation quaternion and a scale, and retrieve the C pointer to its address.
+/// The orientation is normalized.
+///
+/// # Safety
+///
+/// When finished with the underlying similarity initialized by this function
+/// the caller must remember to free the similarity memory using the
+/// free_similarity_memory FFI function. The vector and quaternion are copied, so
+/// they must still be freed separately
+#[no_mangle]
+pub unsafe extern "C" fn new_similarity(
+    translation_ptr: *const Vector3<f64>,
+    orientation_ptr: *const Quaternion<f64>,
+    scale: f64,
+) -> *mut Similarity {
+    null_pointer_check!(translation_ptr);
+    null_pointer_check!(orientation_ptr);
+    to_raw_pointer(&Similarity::new(*translation_ptr, *orientation_ptr, scale))
+}
+
+/// Applies the similarity stored at similarity_ptr to the point stored at point_ptr,
+/// rotating, scaling and then translating it, and returns a pointer to the memory
+/// address of the resulting vector
+///
+/// # Safety
+///
+/// The caller must remember to free the similarity memory and the memory of *both*
+/// the input and output vectors when finished with them using the
+/// free_similarity_memory and free_vector_memory FFI functions
+#[no_mangle]
+pub unsafe extern "C" fn similarity_apply_to_point(
+    similarity_ptr: *const Similarity,
+    point_ptr: *const Vector3<f64>,
+) -> *mut Vector3<f64> {
+    null_pointer_check!(similarity_ptr);
+    null_pointer_check!(point_ptr);
+    vec_to_raw_pointer((*similarity_ptr).apply_to_point(*point_ptr))
+}
+
+/// Composes the similarity stored at similarity_ptr with the one stored at other_ptr,
+/// such that other is applied first, followed by similarity, and returns a pointer
+/// to the memory address of the resulting similarity
+///
+/// # Safety
+///
+/// The caller must remember to free the similarity memory of *both* the input and
+/// output similarities when finished with them using the free_similarity_memory
+/// FFI function
+#[no_mangle]
+pub unsafe extern "C" fn similarity_compose(
+    similarity_ptr: *const Similarity,
+    other_ptr: *const Similarity,
+) -> *mut Similarity {
+    null_pointer_check!(similarity_ptr);
+    null_pointer_check!(other_ptr);
+    to_raw_pointer(&(*similarity_ptr).compose(&*other_ptr))
+}
+
+/// Inverts the similarity stored at similarity_ptr and returns a pointer to the
+/// memory address of the similarity that undoes it. Returns a null pointer if the
+/// similarity's scale is zero, as it then has no inverse.
+///
+/// # Safety
+///
+/// The caller must remember to free the similarity memory of *both* the input and
+/// output similarities when finished with them using the free_similarity_memory
+/// FFI function
+#[no_mangle]
+pub unsafe extern "C" fn similarity_inverse(similarity_ptr: *const Similarity) -> *mut Similarity {
+    null_pointer_check!(similarity_ptr);
+    match (*similarity_ptr).inverse() {
+        Some(inverse) => to_raw_pointer(&inverse),
+        None => std::ptr::null_mut(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{Quaternion, Vector3};
+
+    use super::{free_similarity_memory, new_similarity, similarity_compose, similarity_inverse};
+
+    #[test]
+    fn similarity_composed_with_its_inverse_is_identity() {
+        let translation = Vector3::new(-4.0, 0.5, 2.0);
+        let orientation = Quaternion::new(0.0436194, 0.3710372, 0.5565558, 0.7420744);
+        unsafe {
+            let similarity = new_similarity(&translation, &orientation, 2.0);
+            let inverse = similarity_inverse(similarity);
+            let identity = similarity_compose(similarity, inverse);
+            assert_approx_eq!(f64, (*identity).translation.norm(), 0.0, epsilon = 0.0001);
+            assert_approx_eq!(f64, (*identity).orientation.w.abs(), 1.0, epsilon = 0.0001);
+            assert_approx_eq!(f64, (*identity).scale, 1.0);
+            for ptr in [similarity, inverse, identity] {
+                free_similarity_memory(ptr);
+            }
+
+            let collapsed = new_similarity(&translation, &orientation, 0.0);
+            assert!(similarity_inverse(collapsed).is_null());
+            free_similarity_memory(collapsed);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use ffi_helpers::null_pointer_check;
 use libc::c_double;
 
-use nalgebra::Vector3;
+use nalgebra::{Quaternion, Vector3};
+
+use crate::spatialmath::utils::{self, vector_from_spherical, vector_to_spherical};
 
 /// The FFI interface wrapping the nalgebra crate for Vector functions and
 /// initialization. All public functions are meant to be called externally
@@ -202,6 +204,33 @@ pub unsafe extern "C" fn vector_dot_product(
     (*vec_ptr_1).dot(&*vec_ptr_2)
 }
 
+/// Computes the magnitude (Euclidean length) of a vector. Vectors are nalgebra's
+/// Vector3 type, so Rust callers get the same from its norm and norm_squared
+/// methods without going through the FFI interface
+///
+/// # Safety
+///
+/// The caller must remember to free the vector memory of the input vector
+/// when finished with it using the free_vector_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn vector_magnitude(vec_ptr: *const Vector3<f64>) -> f64 {
+    null_pointer_check!(vec_ptr, f64::NAN);
+    (*vec_ptr).norm()
+}
+
+/// Computes the squared magnitude of a vector, which avoids the square root
+/// when only comparing lengths
+///
+/// # Safety
+///
+/// The caller must remember to free the vector memory of the input vector
+/// when finished with it using the free_vector_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn vector_magnitude_squared(vec_ptr: *const Vector3<f64>) -> f64 {
+    null_pointer_check!(vec_ptr, f64::NAN);
+    (*vec_ptr).norm_squared()
+}
+
 /// Computes the cross product of two vectors and returns
 /// a pointer to the memory of the result
 ///
@@ -219,3 +248,146 @@ pub unsafe extern "C" fn vector_cross_product(
     let vec = (*vec_ptr_1).cross(&*vec_ptr_2);
     to_raw_pointer(vec)
 }
+
+/// Interpolates between the directions of two vectors along the great circle
+/// joining them and returns a pointer to the memory of the resulting unit vector,
+/// which is the fraction t of the way from the first direction to the second.
+/// The magnitudes of the vectors are ignored, and if either is zero the result
+/// is the zero vector.
+///
+/// # Safety
+///
+/// The caller must remember to free the vector memory of *both* the input and
+/// output vectors when finished with them using the free_vector_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn vector_direction_slerp(
+    vec_ptr_1: *const Vector3<f64>,
+    vec_ptr_2: *const Vector3<f64>,
+    t: f64,
+) -> *mut Vector3<f64> {
+    null_pointer_check!(vec_ptr_1);
+    null_pointer_check!(vec_ptr_2);
+    to_raw_pointer(utils::vector_direction_slerp(&*vec_ptr_1, &*vec_ptr_2, t))
+}
+
+/// Rotates the point described by a vector by a quaternion about a pivot point
+/// (rather than the origin) and returns a pointer to the memory of the
+/// resulting vector
+///
+/// # Safety
+///
+/// The caller must remember to free the vector memory of *both* the input and
+/// output vectors when finished with them using the free_vector_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn vector_rotate_around_point(
+    vec_ptr: *const Vector3<f64>,
+    pivot_ptr: *const Vector3<f64>,
+    quat_ptr: *const Quaternion<f64>,
+) -> *mut Vector3<f64> {
+    null_pointer_check!(vec_ptr);
+    null_pointer_check!(pivot_ptr);
+    null_pointer_check!(quat_ptr);
+    to_raw_pointer(utils::rotate_vector_around_point(
+        &*vec_ptr,
+        &*pivot_ptr,
+        &*quat_ptr,
+    ))
+}
+
+/// Converts a vector into spherical coordinates and returns them as a list of
+/// C doubles in the order (radius, azimuth, elevation). The azimuth is measured
+/// in radians from the positive X axis towards the positive Y axis and the
+/// elevation in radians from the XY plane towards the positive Z axis. A zero
+/// vector returns zero angles.
+///
+/// # Safety
+///
+/// When finished with the underlying vector, the caller must remember to
+/// free the vector memory using the free_vector_memory FFI function
+#[no_mangle]
+pub unsafe extern "C" fn vector_get_spherical(vec_ptr: *const Vector3<f64>) -> *const c_double {
+    null_pointer_check!(vec_ptr);
+    let (radius, azimuth, elevation) = vector_to_spherical(&*vec_ptr);
+    let coordinates: [c_double; 3] = [radius, azimuth, elevation];
+    Box::into_raw(Box::new(coordinates)) as *const _
+}
+
+/// Initialize a 3-vector from spherical coordinates (see vector_get_spherical
+/// for the angle conventions) and retrieve the C pointer to its address.
+///
+/// # Safety
+///
+/// When finished with the underlying vector initialized by this function
+/// the caller must remember to free the vector memory using the
+/// free_vector_memory FFI function
+#[no_mangle]
+pub extern "C" fn new_vector3_from_spherical(
+    radius: f64,
+    azimuth: f64,
+    elevation: f64,
+) -> *mut Vector3<f64> {
+    to_raw_pointer(vector_from_spherical(radius, azimuth, elevation))
+}
+
+/// Computes the axis-aligned bounding box of a contiguous array of count vectors and
+/// writes its minimum and maximum corners into the vectors at out_min and out_max.
+/// Returns false, leaving them untouched, if count is zero or any pointer is null.
+///
+/// # Safety
+///
+/// array_ptr must point to count vectors laid out one after another. When finished
+/// with the corner vectors the caller must remember to free their memory using the
+/// free_vector_memory FFI function if they were allocated by new_vector3
+#[no_mangle]
+pub unsafe extern "C" fn vector_bounding_box(
+    array_ptr: *const Vector3<f64>,
+    count: usize,
+    out_min: *mut Vector3<f64>,
+    out_max: *mut Vector3<f64>,
+) -> bool {
+    null_pointer_check!(array_ptr, false);
+    null_pointer_check!(out_min, false);
+    null_pointer_check!(out_max, false);
+    if count == 0 {
+        return false;
+    }
+    match utils::vector_bounding_box(std::slice::from_raw_parts(array_ptr, count)) {
+        Some((min, max)) => {
+            *out_min = min;
+            *out_max = max;
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::{vector_bounding_box, vector_magnitude, vector_magnitude_squared};
+
+    #[test]
+    fn vector_magnitude_works() {
+        let vec = Vector3::new(3.0, 0.0, -4.0);
+        unsafe {
+            assert_eq!(vector_magnitude(&vec), 5.0);
+            assert_eq!(vector_magnitude_squared(&vec), 25.0);
+            assert!(vector_magnitude(std::ptr::null()).is_nan());
+            assert!(vector_magnitude_squared(std::ptr::null()).is_nan());
+        }
+    }
+
+    #[test]
+    fn vector_bounding_box_writes_the_corners() {
+        let points = [Vector3::new(1.0, -2.0, 3.0), Vector3::new(-4.0, 5.0, 0.5)];
+        let (mut min, mut max) = (Vector3::zeros(), Vector3::zeros());
+        unsafe {
+            assert!(vector_bounding_box(points.as_ptr(), 2, &mut min, &mut max));
+            assert_eq!(min, Vector3::new(-4.0, -2.0, 0.5));
+            assert_eq!(max, Vector3::new(1.0, 5.0, 3.0));
+            assert!(!vector_bounding_box(points.as_ptr(), 0, &mut min, &mut max));
+        }
+        assert_eq!(min, Vector3::new(-4.0, -2.0, 0.5));
+    }
+}
@@ -1,6 +1,7 @@
 use hyper::server::accept::Accept;
 use rand::distributions::{Alphanumeric, DistString};
 use std::io::Error;
+use std::os::unix::io::AsRawFd;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use tokio::net::{UnixListener, UnixStream};
@@ -23,6 +24,14 @@ impl UDSConnector {
     pub fn get_path(&self) -> &str {
         &self.path
     }
+    /// Sets how many connections can wait to be accepted before new ones are refused.
+    pub fn set_backlog(&self, backlog: i32) -> Result<(), Error> {
+        // listening again on a listening socket only updates its backlog
+        if unsafe { libc::listen(self.inner.as_raw_fd(), backlog) } != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
 }
 
 impl Accept for UDSConnector {
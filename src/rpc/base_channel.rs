@@ -1,10 +1,13 @@
-use super::log_prefixes;
+use super::{
+    dial_log::{current_dial_id, in_dial},
+    log_prefixes,
+};
 use anyhow::Result;
 use std::{
     fmt::Debug,
     sync::{
         atomic::{AtomicBool, AtomicPtr, Ordering},
-        Arc,
+        Arc, RwLock,
     },
 };
 use webrtc::{
@@ -19,6 +22,9 @@ pub struct WebRTCBaseChannel {
     pub(crate) data_channel: Arc<RTCDataChannel>,
     closed_reason: AtomicPtr<Option<anyhow::Error>>,
     closed: AtomicBool,
+    selected_candidate_pair: RwLock<Option<String>>,
+    // the id of the dial that made the channel, which its log lines are tagged with
+    pub(crate) dial_id: Option<String>,
 }
 
 impl Debug for WebRTCBaseChannel {
@@ -32,7 +38,7 @@ impl Debug for WebRTCBaseChannel {
 
 impl Drop for WebRTCBaseChannel {
     fn drop(&mut self) {
-        log::debug!("Dropping base channel {self:?}");
+        dial_log!(dial: self.dial_id, debug, "Dropping base channel {self:?}");
     }
 }
 
@@ -42,37 +48,44 @@ impl WebRTCBaseChannel {
         data_channel: Arc<RTCDataChannel>,
     ) -> Arc<Self> {
         let dc = data_channel.clone();
-        let pc = Arc::downgrade(&peer_connection);
-        peer_connection.on_ice_connection_state_change(Box::new(move |conn_state| {
-            let pc = match pc.upgrade() {
-                Some(pc) => pc,
-                None => return Box::pin(async {}),
-            };
-            Box::pin(async move {
-                // If ICE connection state is connected, log the Selected candidate pair.
-                if conn_state == RTCIceConnectionState::Connected {
-                    let sctp = pc.sctp();
-                    let transport = sctp.transport();
-                    let transport = transport.ice_transport();
-                    let candidate_pair = transport.get_selected_candidate_pair().await;
-
-                    if let Some(cp) = candidate_pair {
-                        log::info!("{}: {cp}", log_prefixes::CANDIDATE_SELECTED);
-                    }
-                }
-            })
-        }));
-
         let channel = Arc::new(Self {
             peer_connection,
             data_channel,
             closed_reason: AtomicPtr::new(&mut None),
             closed: AtomicBool::new(false),
+            selected_candidate_pair: RwLock::new(None),
+            dial_id: current_dial_id(),
         });
 
         let c = Arc::downgrade(&channel);
+        channel
+            .peer_connection
+            .on_ice_connection_state_change(Box::new(move |conn_state| {
+                let c = match c.upgrade() {
+                    Some(c) => c,
+                    None => return Box::pin(async {}),
+                };
+                Box::pin(in_dial(c.dial_id.clone(), async move {
+                    // If ICE connection state is connected, record and log the selected
+                    // candidate pair.
+                    if conn_state == RTCIceConnectionState::Connected {
+                        let sctp = c.peer_connection.sctp();
+                        let transport = sctp.transport();
+                        let transport = transport.ice_transport();
+                        let candidate_pair = transport.get_selected_candidate_pair().await;
+
+                        if let Some(cp) = candidate_pair {
+                            dial_log!(info, "{}: {cp}", log_prefixes::CANDIDATE_SELECTED);
+                            *c.selected_candidate_pair.write().unwrap() = Some(cp.to_string());
+                        }
+                    }
+                }))
+            }));
+
+        let c = Arc::downgrade(&channel);
+        let dial_id = channel.dial_id.clone();
         dc.on_error(Box::new(move |err: webrtc::Error| {
-            log::error!("Data channel error: {err}");
+            dial_log!(dial: dial_id, error, "Data channel error: {err}");
             let c = match c.upgrade() {
                 Some(c) => c,
                 None => return Box::pin(async {}),
@@ -89,7 +102,7 @@ impl WebRTCBaseChannel {
     /// Closes the channel
     #[allow(dead_code)]
     pub async fn close(&self) -> Result<()> {
-        log::debug!("Closing base channel");
+        dial_log!(dial: self.dial_id, debug, "Closing base channel");
         if self.closed.load(Ordering::Acquire) {
             return Ok(());
         }
@@ -106,6 +119,13 @@ impl WebRTCBaseChannel {
     pub fn is_closed(&self) -> bool {
         self.closed.load(Ordering::Acquire)
     }
+
+    /// Returns the stringified ICE candidate pair selected for the connection, or None if the
+    /// connection hasn't been established yet.
+    pub fn selected_candidate_pair(&self) -> Option<String> {
+        self.selected_candidate_pair.read().unwrap().clone()
+    }
+
     /// Returns Some(reason) if the channel closed with error, otherwise None
     #[allow(dead_code)]
     pub fn closed_reason(&self) -> *mut Option<anyhow::Error> {
@@ -1,16 +1,29 @@
+use super::dial_log::in_dial;
 use crate::gen::proto::rpc::webrtc::v1::{PacketMessage, Stream};
 use anyhow::Result;
-use bytes::BufMut;
-use hyper::body::Sender;
-use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use bytes::{BufMut, Bytes};
+use http::HeaderMap;
+use hyper::Body;
+use std::{
+    future::{poll_fn, Future},
+    sync::{
+        atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::mpsc;
 
 const MAX_MESSAGE_SIZE: usize = 1 << 25;
 
+// How many messages a stream's body may have waiting for its reader before the channel waits
+// for the reader to catch up.
+pub(crate) const MAX_QUEUED_MESSAGES: usize = 8;
+
 /// Base elements of a webRTC stream, used in both client and server implementations
 #[allow(dead_code)]
 pub struct WebRTCBaseStream {
     pub(crate) stream: Stream,
-    pub(crate) message_sender: Sender,
+    pub(crate) message_sender: BodySender,
     pub(crate) closed: AtomicBool,
     pub(crate) packet_buffer: Vec<u8>,
     pub(crate) closed_reason: AtomicPtr<Option<anyhow::Error>>,
@@ -52,3 +65,100 @@ impl WebRTCBaseStream {
         self.packet_buffer = vec![]
     }
 }
+
+enum BodyFrame {
+    Data(Bytes),
+    Trailers(HeaderMap),
+}
+
+/// Feeds a stream's body from a task of its own, so that a reader that's a few messages behind
+/// doesn't hold up the channel the stream is on. The bytes of each message sent count towards
+/// the given total until the reader takes it.
+pub(crate) struct BodySender {
+    frames: mpsc::Sender<BodyFrame>,
+    unread_bytes: Arc<AtomicUsize>,
+}
+
+impl BodySender {
+    /// Returns the sender along with the body it feeds, which tags its log lines with the id
+    /// of the dial that made the channel. Must be called within a tokio runtime.
+    pub(crate) fn new(unread_bytes: Arc<AtomicUsize>, dial_id: Option<String>) -> (Self, Body) {
+        let (mut sender, body) = Body::channel();
+        let (frames, mut queued) = mpsc::channel(MAX_QUEUED_MESSAGES);
+        let read_bytes = unread_bytes.clone();
+        tokio::spawn(in_dial(dial_id, async move {
+            while let Some(frame) = queued.recv().await {
+                match frame {
+                    BodyFrame::Data(data) => {
+                        let len = data.len();
+                        // the body only holds one chunk at a time, so it's been read once the
+                        // body is ready for another
+                        let read = match sender.send_data(data).await {
+                            Ok(()) => poll_fn(|cx| sender.poll_ready(cx)).await.is_ok(),
+                            Err(_) => false,
+                        };
+                        read_bytes.fetch_sub(len, Ordering::AcqRel);
+                        if !read {
+                            break;
+                        }
+                    }
+                    BodyFrame::Trailers(trailers) => {
+                        if let Err(e) = sender.send_trailers(trailers).await {
+                            dial_log!(error, "Error sending trailers to http response: {e}");
+                        }
+                    }
+                }
+            }
+            // the body is gone, so nothing still queued for it will be read
+            queued.close();
+            while let Ok(frame) = queued.try_recv() {
+                if let BodyFrame::Data(data) = frame {
+                    read_bytes.fetch_sub(data.len(), Ordering::AcqRel);
+                }
+            }
+        }));
+        (
+            Self {
+                frames,
+                unread_bytes,
+            },
+            body,
+        )
+    }
+
+    /// Returns a future that completes once the body has room for another frame, or is gone.
+    /// The future doesn't borrow the sender, so the stream needn't be held while it's awaited.
+    pub(crate) fn room(&self) -> impl Future<Output = ()> {
+        let frames = self.frames.clone();
+        async move {
+            let _ = frames.reserve().await;
+        }
+    }
+
+    /// Queues data for the body, failing if the body is gone or has no room for it.
+    pub(crate) fn send_data(&self, data: Bytes) -> Result<()> {
+        let len = data.len();
+        self.unread_bytes.fetch_add(len, Ordering::AcqRel);
+        if let Err(e) = self.frames.try_send(BodyFrame::Data(data)) {
+            self.unread_bytes.fetch_sub(len, Ordering::AcqRel);
+            return Err(send_error(e));
+        }
+        Ok(())
+    }
+
+    /// Queues trailers for the body, failing if the body is gone or has no room for them.
+    pub(crate) fn send_trailers(&self, trailers: HeaderMap) -> Result<()> {
+        self.frames
+            .try_send(BodyFrame::Trailers(trailers))
+            .map_err(send_error)
+    }
+}
+
+fn send_error(e: mpsc::error::TrySendError<BodyFrame>) -> anyhow::Error {
+    match e {
+        mpsc::error::TrySendError::Full(_) => {
+            anyhow::anyhow!("body has {MAX_QUEUED_MESSAGES} messages waiting to be read")
+        }
+        mpsc::error::TrySendError::Closed(_) => anyhow::anyhow!("body was dropped"),
+    }
+}
@@ -1,29 +1,37 @@
-use super::{base_channel::*, base_stream::*, client_stream::*};
+use super::{base_channel::*, base_stream::*, client_stream::*, dial_log::in_dial};
 use crate::gen::proto::rpc::webrtc::v1::{
     request::Type, response::Type as RespType, PacketMessage, Request, RequestHeaders,
     RequestMessage, Response, Stream,
 };
 use anyhow::Result;
 use dashmap::DashMap;
+use futures_util::FutureExt;
 use hyper::Body;
 use prost::Message;
 use std::{
     fmt::Debug,
+    future::Future,
+    panic::AssertUnwindSafe,
     sync::{
-        atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering},
         Arc, RwLock,
     },
+    time::Duration,
 };
 use webrtc::{
     data_channel::{data_channel_message::DataChannelMessage, RTCDataChannel},
+    ice::candidate::CandidateType,
     peer_connection::RTCPeerConnection,
+    stats::{ICECandidatePairStats, ICECandidateStats, StatsReport, StatsReportType},
 };
 
 // see golang/client_stream.go
 const MAX_REQUEST_MESSAGE_PACKET_DATA_SIZE: usize = 16373;
 // 256 is an arbitrarily high number for maximum concurrent streams, determined based on
 // analogous value in goutils
-const MAX_CONCURRENT_STREAM_COUNT: usize = 256;
+pub(crate) const MAX_CONCURRENT_STREAM_COUNT: usize = 256;
+// how often to check whether buffered writes have drained
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// The client-side implementation of a webRTC connection channel.
 pub struct WebRTCClientChannel {
@@ -33,12 +41,22 @@ pub struct WebRTCClientChannel {
     pub(crate) receiver_bodies: DashMap<u64, hyper::Body>,
     // String type rather than error type because anyhow::Error does not derive clone
     pub(crate) error: RwLock<Option<String>>,
+    suspended: AtomicBool,
+    // bytes held across all streams, whether of partially received messages or of messages
+    // their readers have yet to take, and the most they may add up to before the streams
+    // receiving more are reset
+    buffered_bytes: Arc<AtomicUsize>,
+    max_buffered_bytes: AtomicUsize,
+    // the most streams that may be open at once, bounding the memory leaked streams hold
+    max_streams: AtomicUsize,
 }
 
 impl Debug for WebRTCClientChannel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("WebRTCClientChannel")
             .field("stream_id_counter", &self.stream_id_counter)
+            .field("suspended", &self.suspended)
+            .field("buffered_bytes", &self.buffered_bytes)
             .field("base channel", &self.base_channel)
             .finish()
     }
@@ -46,7 +64,12 @@ impl Debug for WebRTCClientChannel {
 
 impl Drop for WebRTCClientChannel {
     fn drop(&mut self) {
-        log::debug!("Dropping client channel {:?}", &self);
+        dial_log!(
+            dial: self.base_channel.dial_id,
+            debug,
+            "Dropping client channel {:?}",
+            &self
+        );
     }
 }
 
@@ -69,15 +92,20 @@ impl WebRTCClientChannel {
             streams: DashMap::new(),
             stream_id_counter: AtomicU64::new(0),
             receiver_bodies: DashMap::new(),
+            suspended: AtomicBool::new(false),
+            buffered_bytes: Arc::new(AtomicUsize::new(0)),
+            max_buffered_bytes: AtomicUsize::new(usize::MAX),
+            max_streams: AtomicUsize::new(MAX_CONCURRENT_STREAM_COUNT),
         };
 
         let channel = Arc::new(channel);
         let ret_channel = channel.clone();
+        let dial_id = channel.base_channel.dial_id.clone();
         let channel = Arc::downgrade(&channel);
 
         data_channel.on_message(Box::new(move |msg: DataChannelMessage| {
             let channel = channel.clone();
-            Box::pin(async move {
+            Box::pin(in_dial(dial_id.clone(), async move {
                 let channel = match channel.upgrade() {
                     Some(channel) => channel,
                     None => {
@@ -88,26 +116,77 @@ impl WebRTCClientChannel {
                 let mut err = channel.error.write().unwrap();
                 match maybe_err {
                     Err(e) => {
-                        log::error!("error deserializing message: {e}");
+                        dial_log!(error, "error deserializing message: {e}");
                         *err = Some(e.to_string());
                     }
                     Ok(()) => *err = None,
                 }
-            })
+            }))
         }));
-        log::debug!("Client channel created");
+        dial_log!(debug, "Client channel created");
         ret_channel
     }
 
+    /// Marks the channel dormant, rejecting new streams until it's resumed. Streams that are
+    /// already open carry on, and the underlying peer connection stays up so that resuming
+    /// doesn't require a new dial.
+    pub fn suspend(&self) {
+        dial_log!(dial: self.base_channel.dial_id, debug, "Suspending client channel");
+        self.suspended.store(true, Ordering::Release);
+    }
+
+    /// Reactivates a suspended channel so that it accepts new streams again.
+    pub fn resume(&self) {
+        dial_log!(dial: self.base_channel.dial_id, debug, "Resuming client channel");
+        self.suspended.store(false, Ordering::Release);
+    }
+
+    /// Returns whether the channel is suspended.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::Acquire)
+    }
+
+    /// Caps how many bytes of messages the channel holds across all of its streams, whether
+    /// partially received or waiting to be read. While the cap is reached new streams are rejected, and a stream receiving
+    /// more than fits under the cap is reset rather than buffered.
+    pub(crate) fn set_max_buffered_bytes(&self, max: usize) {
+        self.max_buffered_bytes.store(max, Ordering::Release);
+    }
+
+    /// Caps how many streams may be open on the channel at once. While the cap is reached new
+    /// streams are rejected.
+    pub(crate) fn set_max_streams(&self, max: usize) {
+        self.max_streams.store(max, Ordering::Release);
+    }
+
     pub(crate) fn new_stream(&self) -> Result<Stream> {
-        if self.streams.len() >= MAX_CONCURRENT_STREAM_COUNT {
+        if self.is_suspended() {
             return Err(anyhow::anyhow!(
-                "Reached max concurrent stream cap of {MAX_CONCURRENT_STREAM_COUNT}; unable to add new stream."
+                "Channel is suspended; unable to add new stream."
             ));
         }
-        let id = self.stream_id_counter.fetch_add(1, Ordering::AcqRel);
+        let max_buffered_bytes = self.max_buffered_bytes.load(Ordering::Acquire);
+        if self.buffered_bytes.load(Ordering::Acquire) >= max_buffered_bytes {
+            return Err(anyhow::anyhow!(
+                "Reached receive buffer cap of {max_buffered_bytes} bytes; unable to add new stream."
+            ));
+        }
+        let max_streams = self.max_streams.load(Ordering::Acquire);
+        if self.streams.len() >= max_streams {
+            return Err(anyhow::anyhow!(
+                "Reached max concurrent stream cap of {max_streams}; unable to add new stream."
+            ));
+        }
+        // ids are never reused, so once they run out the channel takes no more streams
+        let id = self
+            .stream_id_counter
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |id| id.checked_add(1))
+            .map_err(|_| anyhow::anyhow!("Ran out of stream ids; unable to add new stream."))?;
         let stream = Stream { id };
-        let (message_sender, receiver_body) = hyper::Body::channel();
+        let (message_sender, receiver_body) = BodySender::new(
+            self.buffered_bytes.clone(),
+            self.base_channel.dial_id.clone(),
+        );
 
         let base_stream = WebRTCBaseStream {
             stream: stream.clone(),
@@ -130,9 +209,20 @@ impl WebRTCClientChannel {
 
     async fn on_channel_message(&self, msg: DataChannelMessage) -> Result<()> {
         let response = Response::decode(&*msg.data.to_vec())?;
+        // a stream whose reader has fallen behind holds up the channel until the reader catches
+        // up, so that what the stream buffers stays bounded whether or not a cap is set
+        let room = response.stream.as_ref().and_then(|stream| {
+            self.streams
+                .get(&stream.id)
+                .map(|stream| stream.base_stream.message_sender.room())
+        });
+        if let Some(room) = room {
+            room.await;
+        }
         let (active_stream, stream_id) = match response.stream.as_ref() {
             None => {
-                log::error!(
+                dial_log!(
+                    error,
                     "no stream associated with response {:?}: discarding response",
                     response
                 );
@@ -154,16 +244,59 @@ impl WebRTCClientChannel {
         let should_drop_stream = matches!(response.r#type, Some(RespType::Trailers(_)));
 
         let maybe_err = match active_stream {
-            Ok(mut active_stream) => active_stream.on_response(response).await,
+            Ok(mut active_stream) => {
+                let incoming = match &response.r#type {
+                    Some(RespType::Message(message)) => message
+                        .packet_message
+                        .as_ref()
+                        .map_or(0, |packet| packet.data.len()),
+                    _ => 0,
+                };
+                // room for the data is claimed before it's buffered, so that streams receiving
+                // at the same time can't each fit under the cap and together exceed it
+                let max_buffered_bytes = self.max_buffered_bytes.load(Ordering::Acquire);
+                let claimed = self.buffered_bytes.fetch_update(
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                    |buffered| {
+                        buffered
+                            .checked_add(incoming)
+                            .filter(|&buffered| buffered <= max_buffered_bytes)
+                    },
+                );
+                if claimed.is_err() {
+                    drop(active_stream);
+                    self.reset_stream(stream_id).await?;
+                    return Err(anyhow::anyhow!(
+                        "Receive buffer cap of {max_buffered_bytes} bytes exceeded: reset stream {stream_id}"
+                    ));
+                }
+                let buffered = active_stream.base_stream.packet_buffer.len();
+                let maybe_err = active_stream.on_response(response);
+                let now_buffered = active_stream.base_stream.packet_buffer.len();
+                // what was claimed and is no longer held as part of a message has either been
+                // handed to the stream's body, which counts it until it's read, or dropped
+                self.buffered_bytes
+                    .fetch_sub(buffered + incoming - now_buffered, Ordering::AcqRel);
+                maybe_err
+            }
             Err(e) => Err(anyhow::anyhow!("Error acquiring active stream: {e}")),
         };
 
         if should_drop_stream {
-            self.streams.remove(&stream_id);
+            self.remove_stream(stream_id);
         }
         maybe_err
     }
 
+    // Removes the stream, releasing the bytes it had buffered.
+    fn remove_stream(&self, stream_id: u64) -> Option<WebRTCClientStream> {
+        let (_, stream) = self.streams.remove(&stream_id)?;
+        self.buffered_bytes
+            .fetch_sub(stream.base_stream.packet_buffer.len(), Ordering::AcqRel);
+        Some(stream)
+    }
+
     pub(crate) fn resp_body_from_stream(&self, stream_id: u64) -> Result<Body> {
         match self.receiver_bodies.remove(&stream_id) {
             Some(entry) => Ok(entry.1),
@@ -190,6 +323,7 @@ impl WebRTCClientChannel {
         &self,
         stream: Option<Stream>,
         mut data: Vec<u8>,
+        end_of_stream: bool,
     ) -> Result<()> {
         // even if no meaningful data, any actual message will include at least frame header bytes
         let has_message = !data.is_empty();
@@ -236,7 +370,7 @@ impl WebRTCClientChannel {
                 let (to_send, remaining) = data.split_at(split_at);
                 next_message_length -= split_at;
                 let stream = stream.clone();
-                let eos = remaining.is_empty();
+                let eos = end_of_stream && remaining.is_empty();
                 let request = Request {
                     stream,
                     r#type: Some(Type::Message(RequestMessage {
@@ -248,7 +382,7 @@ impl WebRTCClientChannel {
                         // it somehow!
                         eos,
                         packet_message: Some(PacketMessage {
-                            eom: next_message_length == 0 || eos,
+                            eom: next_message_length == 0 || remaining.is_empty(),
                             data: to_send.to_vec(),
                         }),
                     })),
@@ -256,7 +390,11 @@ impl WebRTCClientChannel {
 
                 let request = Message::encode_to_vec(&request);
                 if let Err(e) = self.send(&request).await {
-                    log::error!("error sending message: {e}");
+                    dial_log!(
+                        dial: self.base_channel.dial_id,
+                        error,
+                        "error sending message: {e}"
+                    );
                     return Err(e);
                 }
 
@@ -282,17 +420,953 @@ impl WebRTCClientChannel {
             .map(|_: usize| ())
     }
 
+    /// Resets the stream with the given id, telling the server to stop processing the call
+    /// and closing the stream locally. Streams that have already finished are left alone.
+    pub(crate) async fn reset_stream(&self, stream_id: u64) -> Result<()> {
+        if !self.streams.contains_key(&stream_id) {
+            return Ok(());
+        }
+        self.close_stream_with_recv_error(stream_id, anyhow::anyhow!("stream reset"));
+        let _ = self.receiver_bodies.remove(&stream_id);
+
+        let request = Request {
+            stream: Some(Stream { id: stream_id }),
+            r#type: Some(Type::RstStream(true)),
+        };
+        self.send(&Message::encode_to_vec(&request)).await
+    }
+
+    /// Waits for writes buffered in the data channel to be sent, giving up once the timeout
+    /// elapses. Returns whether the buffer was drained.
+    pub(crate) async fn drain_writes(&self, timeout: Duration) -> bool {
+        let drained = async {
+            while self.base_channel.data_channel.buffered_amount().await > 0 {
+                tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+            }
+        };
+        tokio::time::timeout(timeout, drained).await.is_ok()
+    }
+
     pub(crate) fn close_stream_with_recv_error(&self, stream_id: u64, error: anyhow::Error) {
-        match self.streams.remove(&stream_id) {
-            Some(entry) => entry.1.base_stream.close_with_recv_error(&mut Some(&error)),
+        match self.remove_stream(stream_id) {
+            Some(stream) => stream.base_stream.close_with_recv_error(&mut Some(&error)),
             None => {
-                log::error!("attempted to close stream with id {stream_id}, but it wasn't found!")
+                dial_log!(
+                    dial: self.base_channel.dial_id,
+                    error,
+                    "attempted to close stream with id {stream_id}, but it wasn't found!"
+                )
             }
         }
     }
 
-    /// Returns the current stats report associated with the underlying peer connection.
+    /// Returns the stringified ICE candidate pair selected for the connection, or None if the
+    /// connection hasn't been established yet.
+    pub fn selected_candidate_pair(&self) -> Option<String> {
+        self.base_channel.selected_candidate_pair()
+    }
+
+    /// Returns the current stats report associated with the underlying peer connection. If
+    /// collecting the stats panics, as the webrtc crate has been known to on partially
+    /// initialized connections, an empty report is returned instead.
     pub async fn get_stats(&self) -> webrtc::stats::StatsReport {
-        self.base_channel.peer_connection.get_stats().await
+        let stats = stats_or_empty(self.base_channel.peer_connection.get_stats());
+        in_dial(self.base_channel.dial_id.clone(), stats).await
+    }
+
+    /// Returns a summary of the connection's current stats: the traffic over its data
+    /// channels, the candidate pair selected for it and the candidates of its nominated
+    /// candidate pairs. Unlike [`get_stats`](Self::get_stats), this doesn't tie the caller to
+    /// the webrtc crate's stats types.
+    pub async fn stats_summary(&self) -> StatsSummary {
+        summarize_stats(&self.get_stats().await, self.selected_candidate_pair())
+    }
+
+    /// Returns the fraction, between 0 and 1, of the bytes sent and received over the
+    /// connection so far that went through a TURN relay rather than directly between the
+    /// peers. Returns 0 if no bytes have been exchanged.
+    pub async fn relay_byte_fraction(&self) -> f64 {
+        relay_byte_fraction(&self.get_stats().await)
+    }
+
+    /// Returns an estimate of the one-way latency from this side of the connection to the
+    /// peer, or None if no round trip has been measured yet. The peers' clocks aren't
+    /// synchronized, so this is half the round trip time of the nominated candidate pair: a
+    /// symmetric estimate that overstates one direction and understates the other on an
+    /// asymmetric link. See [`available_bitrates`](Self::available_bitrates) for a hint of how
+    /// asymmetric the link is.
+    pub async fn estimate_one_way_latency(&self) -> Option<Duration> {
+        estimate_one_way_latency(&self.get_stats().await)
+    }
+
+    /// Returns the available outgoing and incoming bitrates, in bits per second, of the
+    /// nominated candidate pair, or None if neither has been estimated yet. An unestimated
+    /// direction is reported as 0.
+    pub async fn available_bitrates(&self) -> Option<(f64, f64)> {
+        available_bitrates(&self.get_stats().await)
+    }
+}
+
+/// A summary of a webRTC connection's stats, as returned by
+/// [`WebRTCClientChannel::stats_summary`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatsSummary {
+    /// The bytes sent across the connection's data channels.
+    pub bytes_sent: u64,
+    /// The bytes received across the connection's data channels.
+    pub bytes_received: u64,
+    /// The messages sent across the connection's data channels.
+    pub messages_sent: u64,
+    /// The messages received across the connection's data channels.
+    pub messages_received: u64,
+    /// The stringified ICE candidate pair selected for the connection, if it's established.
+    pub selected_candidate_pair: Option<String>,
+    /// The local and remote candidates of the nominated candidate pairs.
+    pub nominated_candidates: Vec<CandidateSummary>,
+}
+
+/// An ICE candidate in a [`StatsSummary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateSummary {
+    /// Whether this is a local candidate rather than one of the peer's.
+    pub local: bool,
+    /// The address the candidate is reachable at.
+    pub ip: String,
+    /// The port the candidate is reachable at.
+    pub port: u16,
+    /// The type of the candidate: "host", "srflx", "prflx" or "relay".
+    pub candidate_type: String,
+    /// The network the candidate is on, such as "udp4".
+    pub network_type: String,
+    /// The protocol used to reach the TURN server, for relay candidates.
+    pub relay_protocol: String,
+}
+
+impl CandidateSummary {
+    fn new(local: bool, candidate: &ICECandidateStats) -> Self {
+        Self {
+            local,
+            ip: candidate.ip.clone(),
+            port: candidate.port,
+            candidate_type: candidate.candidate_type.to_string(),
+            network_type: candidate.network_type.to_string(),
+            relay_protocol: candidate.relay_protocol.clone(),
+        }
+    }
+}
+
+// Sums the traffic of every data channel in the report and collects the candidates of its
+// nominated pairs, each candidate once.
+fn summarize_stats(report: &StatsReport, selected_candidate_pair: Option<String>) -> StatsSummary {
+    let mut summary = StatsSummary {
+        selected_candidate_pair,
+        ..Default::default()
+    };
+    let mut nominated = Vec::new();
+    for stats in report.reports.values() {
+        match stats {
+            StatsReportType::DataChannel(channel) => {
+                summary.bytes_sent += channel.bytes_sent as u64;
+                summary.bytes_received += channel.bytes_received as u64;
+                summary.messages_sent += channel.messages_sent as u64;
+                summary.messages_received += channel.messages_received as u64;
+            }
+            StatsReportType::CandidatePair(pair) if pair.nominated => nominated.push(pair),
+            _ => {}
+        }
+    }
+    // the report is a map, so order the pairs to keep the summary stable
+    nominated.sort_by(|a, b| a.id.cmp(&b.id));
+    for pair in nominated {
+        for id in [&pair.local_candidate_id, &pair.remote_candidate_id] {
+            let candidate = match report.reports.get(id) {
+                Some(StatsReportType::LocalCandidate(candidate)) => {
+                    CandidateSummary::new(true, candidate)
+                }
+                Some(StatsReportType::RemoteCandidate(candidate)) => {
+                    CandidateSummary::new(false, candidate)
+                }
+                _ => continue,
+            };
+            if !summary.nominated_candidates.contains(&candidate) {
+                summary.nominated_candidates.push(candidate);
+            }
+        }
+    }
+    summary
+}
+
+async fn stats_or_empty<F>(stats: F) -> StatsReport
+where
+    F: Future<Output = StatsReport>,
+{
+    match AssertUnwindSafe(stats).catch_unwind().await {
+        Ok(report) => report,
+        Err(_) => {
+            dial_log!(
+                warn,
+                "collecting webRTC stats panicked; returning an empty stats report"
+            );
+            StatsReport {
+                reports: Default::default(),
+            }
+        }
+    }
+}
+
+// Sums the byte counters of every candidate pair in the report, counting a pair as relayed if
+// either of its candidates is a relay candidate.
+fn relay_byte_fraction(report: &StatsReport) -> f64 {
+    let is_relay = |id: &str| match report.reports.get(id) {
+        Some(StatsReportType::LocalCandidate(candidate))
+        | Some(StatsReportType::RemoteCandidate(candidate)) => {
+            candidate.candidate_type == CandidateType::Relay
+        }
+        _ => false,
+    };
+    let (mut relayed, mut total) = (0u64, 0u64);
+    for stats in report.reports.values() {
+        if let StatsReportType::CandidatePair(pair) = stats {
+            let bytes = pair.bytes_sent + pair.bytes_received;
+            total += bytes;
+            if is_relay(&pair.local_candidate_id) || is_relay(&pair.remote_candidate_id) {
+                relayed += bytes;
+            }
+        }
+    }
+    if total == 0 {
+        return 0.0;
+    }
+    relayed as f64 / total as f64
+}
+
+// Returns the nominated candidate pair carrying the most bytes, which is the one the
+// connection's traffic goes over.
+fn busiest_nominated_pair(report: &StatsReport) -> Option<&ICECandidatePairStats> {
+    report
+        .reports
+        .values()
+        .filter_map(|stats| match stats {
+            StatsReportType::CandidatePair(pair) if pair.nominated => Some(pair),
+            _ => None,
+        })
+        .max_by_key(|pair| pair.bytes_sent + pair.bytes_received)
+}
+
+// Halves the round trip time of the busiest nominated pair, preferring its latest round trip
+// time over its average one.
+fn estimate_one_way_latency(report: &StatsReport) -> Option<Duration> {
+    let pair = busiest_nominated_pair(report)?;
+    let rtt = if pair.current_round_trip_time > 0.0 {
+        pair.current_round_trip_time
+    } else if pair.responses_received > 0 {
+        pair.total_round_trip_time / pair.responses_received as f64
+    } else {
+        return None;
+    };
+    Some(Duration::from_secs_f64(rtt / 2.0))
+}
+
+fn available_bitrates(report: &StatsReport) -> Option<(f64, f64)> {
+    let pair = busiest_nominated_pair(report)?;
+    let bitrates = (
+        pair.available_outgoing_bitrate,
+        pair.available_incoming_bitrate,
+    );
+    (bitrates.0 > 0.0 || bitrates.1 > 0.0).then_some(bitrates)
+}
+
+/// Returns the number of leading bytes in `data` that make up complete length-prefixed gRPC
+/// messages. Anything past that point is a message whose bytes haven't all arrived yet.
+pub(crate) fn complete_messages_len(data: &[u8]) -> usize {
+    let mut offset = 0;
+    while data.len() - offset >= 5 {
+        let mut length_bytes = [0u8; 4];
+        length_bytes.clone_from_slice(&data[offset + 1..offset + 5]);
+        let message_end = offset + 5 + u32::from_be_bytes(length_bytes) as usize;
+        if message_end > data.len() {
+            break;
+        }
+        offset = message_end;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        available_bitrates, complete_messages_len, estimate_one_way_latency, relay_byte_fraction,
+        stats_or_empty, summarize_stats, CandidateSummary, StatsSummary, WebRTCClientChannel,
+        MAX_CONCURRENT_STREAM_COUNT, MAX_QUEUED_MESSAGES,
+    };
+    use crate::gen::proto::rpc::webrtc::v1::{
+        request::Type, response::Type as RespType, PacketMessage, Request, Response,
+        ResponseHeaders, ResponseMessage, Stream,
+    };
+    use crate::rpc::webrtc::{new_peer_connection_for_client, tests::answer_with_local_peer};
+    use bytes::Bytes;
+    use hyper::body::HttpBody;
+    use prost::Message;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+    use webrtc::{
+        data_channel::{
+            data_channel_init::RTCDataChannelInit, data_channel_message::DataChannelMessage,
+            data_channel_state::RTCDataChannelState, RTCDataChannel,
+        },
+        ice::{
+            agent::agent_stats::CandidatePairStats, candidate::CandidateType,
+            network_type::NetworkType,
+        },
+        peer_connection::{configuration::RTCConfiguration, RTCPeerConnection},
+        stats::{
+            DataChannelStats, ICECandidatePairStats, ICECandidateStats, RTCStatsType, StatsReport,
+            StatsReportType,
+        },
+    };
+
+    fn framed(payload: &[u8]) -> Vec<u8> {
+        let mut message = vec![0u8];
+        message.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        message.extend_from_slice(payload);
+        message
+    }
+
+    #[test]
+    fn complete_messages_len_stops_at_partial_message() {
+        let first = framed(b"hello");
+        let second = framed(b"world");
+        let data = [first.clone(), second].concat();
+        assert_eq!(complete_messages_len(&data), data.len());
+
+        // partial length prefix
+        assert_eq!(complete_messages_len(&data[..first.len() + 3]), first.len());
+        // full length prefix, partial payload
+        assert_eq!(complete_messages_len(&data[..data.len() - 1]), first.len());
+        assert_eq!(complete_messages_len(&[]), 0);
+        assert_eq!(complete_messages_len(&framed(&[])), 5);
+    }
+
+    fn candidate(id: &str, candidate_type: CandidateType) -> (String, StatsReportType) {
+        let stats = ICECandidateStats {
+            timestamp: tokio::time::Instant::now(),
+            stats_type: RTCStatsType::LocalCandidate,
+            id: id.to_string(),
+            candidate_type,
+            deleted: false,
+            ip: "127.0.0.1".to_string(),
+            network_type: NetworkType::Udp4,
+            port: 0,
+            priority: 0,
+            relay_protocol: String::new(),
+            url: String::new(),
+        };
+        (id.to_string(), StatsReportType::LocalCandidate(stats))
+    }
+
+    fn candidate_pair(
+        local: &str,
+        remote: &str,
+        sent: u64,
+        received: u64,
+    ) -> (String, StatsReportType) {
+        let stats = ICECandidatePairStats::from(CandidatePairStats {
+            local_candidate_id: local.to_string(),
+            remote_candidate_id: remote.to_string(),
+            bytes_sent: sent,
+            bytes_received: received,
+            ..Default::default()
+        });
+        (stats.id.clone(), StatsReportType::CandidatePair(stats))
+    }
+
+    #[test]
+    fn stats_summary_totals_data_channels_and_lists_nominated_candidates() {
+        let data_channel = |id: &str, sent: usize, received: usize| {
+            let stats = DataChannelStats {
+                timestamp: tokio::time::Instant::now(),
+                stats_type: RTCStatsType::DataChannel,
+                id: id.to_string(),
+                bytes_received: received,
+                bytes_sent: sent,
+                data_channel_identifier: 0,
+                label: id.to_string(),
+                messages_received: 1,
+                messages_sent: 2,
+                protocol: String::new(),
+                state: RTCDataChannelState::Open,
+            };
+            (id.to_string(), StatsReportType::DataChannel(stats))
+        };
+        let remote = |id: &str| match candidate(id, CandidateType::ServerReflexive) {
+            (id, StatsReportType::LocalCandidate(mut stats)) => {
+                stats.stats_type = RTCStatsType::RemoteCandidate;
+                stats.ip = "203.0.113.7".to_string();
+                (id, StatsReportType::RemoteCandidate(stats))
+            }
+            _ => unreachable!(),
+        };
+        let mut nominated = candidate_pair("host", "remote-srflx", 300, 200);
+        if let StatsReportType::CandidatePair(pair) = &mut nominated.1 {
+            pair.nominated = true;
+        }
+        let report = StatsReport {
+            reports: [
+                data_channel("data", 1000, 400),
+                data_channel("negotiation", 50, 20),
+                candidate("host", CandidateType::Host),
+                candidate("relay", CandidateType::Relay),
+                remote("remote-srflx"),
+                nominated,
+                // candidates only in pairs that weren't nominated are left out
+                candidate_pair("relay", "remote-srflx", 10, 10),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let summary = summarize_stats(&report, Some("pair".to_string()));
+        let candidate_summary = |local, ip: &str, candidate_type: &str| CandidateSummary {
+            local,
+            ip: ip.to_string(),
+            port: 0,
+            candidate_type: candidate_type.to_string(),
+            network_type: "udp4".to_string(),
+            relay_protocol: String::new(),
+        };
+        assert_eq!(
+            summary,
+            StatsSummary {
+                bytes_sent: 1050,
+                bytes_received: 420,
+                messages_sent: 4,
+                messages_received: 2,
+                selected_candidate_pair: Some("pair".to_string()),
+                nominated_candidates: vec![
+                    candidate_summary(true, "127.0.0.1", "host"),
+                    candidate_summary(false, "203.0.113.7", "srflx"),
+                ],
+            }
+        );
+
+        let empty = StatsReport {
+            reports: Default::default(),
+        };
+        assert_eq!(summarize_stats(&empty, None), StatsSummary::default());
+    }
+
+    #[test]
+    fn relay_byte_fraction_counts_pairs_with_a_relay_candidate() {
+        let empty = StatsReport {
+            reports: Default::default(),
+        };
+        assert_eq!(relay_byte_fraction(&empty), 0.0);
+
+        let report = StatsReport {
+            reports: [
+                candidate("host", CandidateType::Host),
+                candidate("relay", CandidateType::Relay),
+                candidate("remote-host", CandidateType::Host),
+                candidate("remote-relay", CandidateType::Relay),
+                candidate_pair("host", "remote-host", 400, 200),
+                candidate_pair("relay", "remote-host", 100, 50),
+                candidate_pair("host", "remote-relay", 150, 100),
+                // a pair whose candidates were never reported is counted as direct
+                candidate_pair("unknown", "remote-unknown", 0, 0),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        assert_eq!(relay_byte_fraction(&report), 0.4);
+    }
+
+    fn round_trip_pair(
+        nominated: bool,
+        bytes: u64,
+        current_rtt: f64,
+        total_rtt: f64,
+        responses: u64,
+        bitrates: (f64, f64),
+    ) -> (String, StatsReportType) {
+        let mut stats = ICECandidatePairStats::from(CandidatePairStats {
+            nominated,
+            bytes_sent: bytes,
+            current_round_trip_time: current_rtt,
+            total_round_trip_time: total_rtt,
+            responses_received: responses,
+            ..Default::default()
+        });
+        stats.id = format!("pair-{bytes}");
+        (
+            stats.available_outgoing_bitrate,
+            stats.available_incoming_bitrate,
+        ) = bitrates;
+        (stats.id.clone(), StatsReportType::CandidatePair(stats))
+    }
+
+    #[test]
+    fn one_way_latency_halves_the_nominated_round_trip() {
+        let report = |pairs: Vec<(String, StatsReportType)>| StatsReport {
+            reports: pairs.into_iter().collect(),
+        };
+        assert_eq!(estimate_one_way_latency(&report(vec![])), None);
+
+        // pairs that weren't nominated are ignored
+        let unnominated = report(vec![round_trip_pair(false, 100, 0.08, 0.0, 0, (1e6, 3e6))]);
+        assert_eq!(estimate_one_way_latency(&unnominated), None);
+        assert_eq!(available_bitrates(&unnominated), None);
+
+        let symmetric = report(vec![round_trip_pair(true, 100, 0.08, 0.0, 0, (0.0, 0.0))]);
+        assert_eq!(
+            estimate_one_way_latency(&symmetric),
+            Some(Duration::from_millis(40))
+        );
+        assert_eq!(available_bitrates(&symmetric), None);
+
+        // with no current round trip time the average one is used, and the busiest nominated
+        // pair wins
+        let average = round_trip_pair(true, 200, 0.0, 0.5, 10, (0.0, 0.0));
+        let quiet = round_trip_pair(true, 10, 0.2, 0.0, 0, (0.0, 0.0));
+        assert_eq!(
+            estimate_one_way_latency(&report(vec![average, quiet])),
+            Some(Duration::from_millis(25))
+        );
+
+        // an asymmetric link is still halved, with its bitrates reported separately
+        let asymmetric = report(vec![round_trip_pair(true, 100, 0.08, 0.0, 0, (1e6, 3e6))]);
+        assert_eq!(
+            estimate_one_way_latency(&asymmetric),
+            Some(Duration::from_millis(40))
+        );
+        assert_eq!(available_bitrates(&asymmetric), Some((1e6, 3e6)));
+
+        // a nominated pair that hasn't completed a round trip yields no estimate
+        let unmeasured = round_trip_pair(true, 100, 0.0, 0.0, 0, (0.0, 0.0));
+        assert_eq!(estimate_one_way_latency(&report(vec![unmeasured])), None);
+    }
+
+    #[tokio::test]
+    async fn panicking_stats_collection_returns_empty_report() {
+        let report = stats_or_empty(async {
+            panic!("stats collection failed");
+        })
+        .await;
+        assert!(report.reports.is_empty());
+
+        let report = stats_or_empty(async {
+            StatsReport {
+                reports: [candidate("host", CandidateType::Host)]
+                    .into_iter()
+                    .collect(),
+            }
+        })
+        .await;
+        assert_eq!(report.reports.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn suspended_channel_rejects_new_streams() {
+        let (peer_connection, data_channel) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let channel = WebRTCClientChannel::new(peer_connection, data_channel).await;
+        assert!(channel.new_stream().is_ok());
+
+        channel.suspend();
+        assert!(channel.is_suspended());
+        assert!(channel.new_stream().is_err());
+
+        channel.resume();
+        assert!(!channel.is_suspended());
+        let stream = channel.new_stream().unwrap();
+        assert_eq!(stream.id, 1);
+        assert_eq!(channel.streams.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn new_streams_are_rejected_past_the_caps() {
+        let (peer_connection, data_channel) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let channel = WebRTCClientChannel::new(peer_connection, data_channel).await;
+        channel.set_max_streams(2);
+        let first = channel.new_stream().unwrap();
+        channel.new_stream().unwrap();
+        let err = channel.new_stream().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Reached max concurrent stream cap of 2; unable to add new stream."
+        );
+
+        // finishing a stream makes room for another
+        channel.close_stream_with_recv_error(first.id, anyhow::anyhow!("done"));
+        channel.new_stream().unwrap();
+        assert_eq!(channel.streams.len(), 2);
+
+        // the last id is handed out, after which there are none left
+        channel.set_max_streams(MAX_CONCURRENT_STREAM_COUNT);
+        channel
+            .stream_id_counter
+            .store(u64::MAX - 1, Ordering::Release);
+        assert_eq!(channel.new_stream().unwrap().id, u64::MAX - 1);
+        let err = channel.new_stream().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Ran out of stream ids; unable to add new stream."
+        );
+        assert_eq!(channel.streams.len(), 3);
+        channel.close().await;
+    }
+
+    #[tokio::test]
+    async fn selected_candidate_pair_is_recorded_once_connected() {
+        let (peer_connection, data_channel) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let channel = WebRTCClientChannel::new(peer_connection.clone(), data_channel).await;
+        assert_eq!(channel.selected_candidate_pair(), None);
+
+        let remote = answer_with_local_peer(&peer_connection).await;
+        let selected = async {
+            loop {
+                if let Some(pair) = channel.selected_candidate_pair() {
+                    return pair;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        let pair = tokio::time::timeout(Duration::from_secs(10), selected)
+            .await
+            .unwrap();
+        assert!(pair.contains("<->"));
+        let summary = channel.stats_summary().await;
+        assert_eq!(summary.selected_candidate_pair, Some(pair));
+        channel.close().await;
+        remote.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn drain_writes_flushes_buffered_data() {
+        let (peer_connection, data_channel) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let remote = answer_with_local_peer(&peer_connection).await;
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let remote_received = received.clone();
+        let remote_channel = remote
+            .create_data_channel(
+                "data",
+                Some(RTCDataChannelInit {
+                    negotiated: Some(0),
+                    ordered: Some(true),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        remote_channel.on_message(Box::new(move |msg: DataChannelMessage| {
+            remote_received.fetch_add(msg.data.len(), Ordering::AcqRel);
+            Box::pin(async {})
+        }));
+
+        let (opened, mut wait_for_open) = tokio::sync::mpsc::channel(1);
+        data_channel.on_open(Box::new(move || {
+            let _ = opened.try_send(());
+            Box::pin(async {})
+        }));
+        let channel = WebRTCClientChannel::new(peer_connection, data_channel.clone()).await;
+        tokio::time::timeout(Duration::from_secs(10), wait_for_open.recv())
+            .await
+            .unwrap();
+
+        let chunk = Bytes::from(vec![0u8; 16 * 1024]);
+        let chunks = 128;
+        for _ in 0..chunks {
+            data_channel.send(&chunk).await.unwrap();
+        }
+        assert!(data_channel.buffered_amount().await > 0);
+
+        assert!(channel.drain_writes(Duration::from_secs(10)).await);
+        assert_eq!(data_channel.buffered_amount().await, 0);
+        channel.close().await;
+
+        let all_received = async {
+            while received.load(Ordering::Acquire) < chunk.len() * chunks {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), all_received)
+            .await
+            .unwrap();
+        remote.close().await.unwrap();
+    }
+
+    // Sends a response on the given stream from the remote end of the data channel.
+    async fn respond(data_channel: &RTCDataChannel, id: u64, r#type: RespType) {
+        let response = Response {
+            stream: Some(Stream { id }),
+            r#type: Some(r#type),
+        };
+        let data = Bytes::from(response.encode_to_vec());
+        data_channel.send(&data).await.unwrap();
+    }
+
+    fn complete_message(len: usize) -> RespType {
+        RespType::Message(ResponseMessage {
+            packet_message: Some(PacketMessage {
+                data: vec![0u8; len],
+                eom: true,
+            }),
+        })
+    }
+
+    fn partial_message(len: usize) -> RespType {
+        RespType::Message(ResponseMessage {
+            packet_message: Some(PacketMessage {
+                data: vec![0u8; len],
+                eom: false,
+            }),
+        })
+    }
+
+    // Connects a client channel to a local peer, returning the peer's end of the data channel
+    // and the ids of the streams the client resets.
+    async fn channel_with_remote() -> (
+        Arc<WebRTCClientChannel>,
+        RTCPeerConnection,
+        Arc<RTCDataChannel>,
+        Arc<Mutex<Vec<u64>>>,
+    ) {
+        let (peer_connection, data_channel) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let remote = answer_with_local_peer(&peer_connection).await;
+        let remote_channel = remote
+            .create_data_channel(
+                "data",
+                Some(RTCDataChannelInit {
+                    negotiated: Some(0),
+                    ordered: Some(true),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        // the streams the client resets
+        let resets = Arc::new(Mutex::new(Vec::new()));
+        let remote_resets = resets.clone();
+        remote_channel.on_message(Box::new(move |msg: DataChannelMessage| {
+            let request = Request::decode(&*msg.data).unwrap();
+            if let Some(Type::RstStream(true)) = request.r#type {
+                remote_resets
+                    .lock()
+                    .unwrap()
+                    .push(request.stream.unwrap().id);
+            }
+            Box::pin(async {})
+        }));
+
+        let (opened, mut wait_for_open) = tokio::sync::mpsc::channel(1);
+        data_channel.on_open(Box::new(move || {
+            let _ = opened.try_send(());
+            Box::pin(async {})
+        }));
+        let channel = WebRTCClientChannel::new(peer_connection, data_channel).await;
+        tokio::time::timeout(Duration::from_secs(10), wait_for_open.recv())
+            .await
+            .unwrap();
+        (channel, remote, remote_channel, resets)
+    }
+
+    #[tokio::test]
+    async fn receive_buffer_cap_bounds_memory_across_streams() {
+        let (channel, remote, remote_channel, resets) = channel_with_remote().await;
+        let buffered = || channel.buffered_bytes.load(Ordering::Acquire);
+
+        let chunk = 4096;
+        let cap = 3 * chunk + chunk / 2;
+        channel.set_max_buffered_bytes(cap);
+        let streams: Vec<u64> = (0..6).map(|_| channel.new_stream().unwrap().id).collect();
+        // every stream gets the start of a message that its consumer never reads
+        for &id in &streams {
+            respond(
+                &remote_channel,
+                id,
+                RespType::Headers(ResponseHeaders::default()),
+            )
+            .await;
+            respond(&remote_channel, id, partial_message(chunk)).await;
+        }
+        let over_cap_reset = async {
+            while resets.lock().unwrap().len() < 3 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), over_cap_reset)
+            .await
+            .unwrap();
+        assert_eq!(*resets.lock().unwrap(), streams[3..]);
+        assert_eq!(buffered(), 3 * chunk);
+        assert_eq!(channel.streams.len(), 3);
+
+        // new streams are refused once the cap is reached
+        let id = channel.new_stream().unwrap().id;
+        respond(
+            &remote_channel,
+            id,
+            RespType::Headers(ResponseHeaders::default()),
+        )
+        .await;
+        respond(&remote_channel, id, partial_message(chunk / 2)).await;
+        let filled = async {
+            while buffered() < cap {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), filled)
+            .await
+            .unwrap();
+        assert!(channel.new_stream().is_err());
+
+        // finishing a stream frees what it buffered
+        channel.reset_stream(streams[0]).await.unwrap();
+        assert_eq!(buffered(), cap - chunk);
+        assert!(channel.new_stream().is_ok());
+
+        channel.close().await;
+        remote.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unread_messages_count_towards_the_receive_buffer_cap() {
+        let (channel, remote, remote_channel, resets) = channel_with_remote().await;
+        let buffered = || channel.buffered_bytes.load(Ordering::Acquire);
+        let settled = |bytes: usize| async move {
+            while buffered() != bytes {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+
+        let chunk = 4096;
+        // each message reaches its reader with a five byte gRPC frame header
+        let unread = chunk + 5;
+        channel.set_max_buffered_bytes(4 * unread + chunk / 2);
+        // a stream whose reader never takes the messages it receives
+        let stalled = channel.new_stream().unwrap().id;
+        let stalled_body = channel.resp_body_from_stream(stalled).unwrap();
+        respond(
+            &remote_channel,
+            stalled,
+            RespType::Headers(ResponseHeaders::default()),
+        )
+        .await;
+        for _ in 0..3 {
+            respond(&remote_channel, stalled, complete_message(chunk)).await;
+        }
+        tokio::time::timeout(Duration::from_secs(10), settled(3 * unread))
+            .await
+            .unwrap();
+
+        // the stalled reader doesn't hold up the other streams, and what they read is freed
+        let live = channel.new_stream().unwrap().id;
+        let mut live_body = channel.resp_body_from_stream(live).unwrap();
+        respond(
+            &remote_channel,
+            live,
+            RespType::Headers(ResponseHeaders::default()),
+        )
+        .await;
+        respond(&remote_channel, live, complete_message(chunk)).await;
+        let read = tokio::time::timeout(Duration::from_secs(10), live_body.data())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.len(), unread);
+        tokio::time::timeout(Duration::from_secs(10), settled(3 * unread))
+            .await
+            .unwrap();
+
+        // the stalled reader's messages pile up until they're more than fits under the cap
+        for _ in 0..2 {
+            respond(&remote_channel, stalled, complete_message(chunk)).await;
+        }
+        let reset = async {
+            while !resets.lock().unwrap().contains(&stalled) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), reset)
+            .await
+            .unwrap();
+        assert!(!channel.streams.contains_key(&stalled));
+
+        // what the reader never took is freed along with its body
+        drop(stalled_body);
+        tokio::time::timeout(Duration::from_secs(10), settled(0))
+            .await
+            .unwrap();
+
+        channel.close().await;
+        remote.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lagging_reader_holds_up_the_channel_when_no_cap_is_set() {
+        let (channel, remote, remote_channel, resets) = channel_with_remote().await;
+        let buffered = || channel.buffered_bytes.load(Ordering::Acquire);
+
+        let chunk = 4096;
+        let unread = chunk + 5;
+        let sent = 4 * MAX_QUEUED_MESSAGES;
+        let id = channel.new_stream().unwrap().id;
+        let mut body = channel.resp_body_from_stream(id).unwrap();
+        respond(
+            &remote_channel,
+            id,
+            RespType::Headers(ResponseHeaders::default()),
+        )
+        .await;
+        for _ in 0..sent {
+            respond(&remote_channel, id, complete_message(chunk)).await;
+        }
+        // the body holds one message and queues a bounded number more, however many are sent
+        let bound = (MAX_QUEUED_MESSAGES + 1) * unread;
+        let filled = async {
+            while buffered() < bound {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), filled)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(buffered(), bound);
+
+        // nothing is dropped: once the reader catches up it gets every message
+        for _ in 0..sent {
+            let read = tokio::time::timeout(Duration::from_secs(10), body.data())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            assert_eq!(read.len(), unread);
+            assert!(buffered() <= bound);
+        }
+        assert!(resets.lock().unwrap().is_empty());
+
+        channel.close().await;
+        remote.close().await.unwrap();
     }
 }
@@ -19,7 +19,7 @@ impl WebRTCClientStream {
         self.headers_received.store(true, Ordering::Release)
     }
 
-    async fn process_message(&mut self, response: ResponseMessage) -> Result<()> {
+    fn process_message(&mut self, response: ResponseMessage) -> Result<()> {
         if let Some(message) = response.packet_message {
             match self.base_stream.process_message(message) {
                 Ok(data) => {
@@ -30,29 +30,25 @@ impl WebRTCClientStream {
                         message_buf.write_u32::<BigEndian>(len)?;
                         message_buf.append(&mut data);
                         let data = Bytes::from(message_buf);
-                        self.base_stream
-                            .message_sender
-                            .send_data(data.clone())
-                            .await?;
+                        self.base_stream.message_sender.send_data(data)?;
                     }
                 }
                 Err(e) => {
-                    log::error!("Error processing message: {e}");
+                    dial_log!(error, "Error processing message: {e}");
                 }
             }
         }
         Ok(())
     }
 
-    async fn process_trailers(&mut self, trailers: ResponseTrailers) {
+    fn process_trailers(&mut self, trailers: ResponseTrailers) {
         let trailers_to_send = trailers_from_proto(trailers.clone());
         if let Err(e) = self
             .base_stream
             .message_sender
             .send_trailers(trailers_to_send)
-            .await
         {
-            log::error!("Error sending trailers to http response: {e}");
+            dial_log!(error, "Error sending trailers to http response: {e}");
         }
 
         self.trailers_received.store(true, Ordering::Release);
@@ -70,14 +66,14 @@ impl WebRTCClientStream {
         };
 
         if let Some(e) = &err {
-            log::debug!("received gRPC error: {e}");
+            dial_log!(debug, "received gRPC error: {e}");
         }
 
         self.base_stream.close_with_recv_error(&mut err.as_ref())
     }
 
     // processes response.
-    pub(crate) async fn on_response(&mut self, response: Response) -> Result<()> {
+    pub(crate) fn on_response(&mut self, response: Response) -> Result<()> {
         match &response.r#type {
             Some(Type::Headers(headers)) => {
                 if self.headers_received.load(Ordering::Acquire) {
@@ -108,10 +104,13 @@ impl WebRTCClientStream {
                     return Err(err);
                 }
 
-                self.process_message(message.to_owned()).await
+                self.process_message(message.to_owned())
             }
 
-            Some(Type::Trailers(trailers)) => Ok(self.process_trailers(trailers.to_owned()).await),
+            Some(Type::Trailers(trailers)) => {
+                self.process_trailers(trailers.to_owned());
+                Ok(())
+            }
             None => Ok(()),
         }
     }
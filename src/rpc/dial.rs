@@ -1,6 +1,7 @@
 use super::{
     client_channel::*,
-    log_prefixes,
+    dial_log::{capture_dial_logs, current_dial_id, in_dial, new_dial_id},
+    log_prefixes, rtt,
     webrtc::{webrtc_action_with_timeout, Options},
 };
 use crate::gen::google;
@@ -22,42 +23,78 @@ use ::http::{
     HeaderValue, Version,
 };
 use ::viam_mdns::{discover, Response};
+use ::webrtc::data_channel::data_channel_state::RTCDataChannelState;
+use ::webrtc::ice::candidate::candidate_base::unmarshal_candidate;
 use ::webrtc::ice_transport::{
     ice_candidate::{RTCIceCandidate, RTCIceCandidateInit},
     ice_connection_state::RTCIceConnectionState,
 };
-use ::webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use ::webrtc::peer_connection::{
+    certificate::RTCCertificate, peer_connection_state::RTCPeerConnectionState, RTCPeerConnection,
+};
+use ::webrtc::sctp_transport::sctp_transport_state::RTCSctpTransportState;
 use anyhow::{Context, Result};
 use core::fmt;
-use futures::stream::FuturesUnordered;
-use futures_util::{pin_mut, stream::StreamExt};
+use futures::{stream::FuturesUnordered, Future, Stream};
+use futures_util::{
+    future::{FutureExt, Shared},
+    pin_mut,
+    stream::StreamExt,
+};
+use hyper::body::HttpBody;
+use hyper::client::connect::dns::{GaiResolver, Name};
 use local_ip_address::list_afinet_netifas;
+use rand::Rng;
+use serde::Serialize;
 use std::{
-    collections::HashMap,
-    net::{IpAddr, Ipv4Addr},
+    any::Any,
+    collections::{
+        hash_map::{Entry, RandomState},
+        HashMap, HashSet,
+    },
+    hash::{BuildHasher, Hash, Hasher},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::Path,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
     },
     task::{Context as TaskContext, Poll},
     time::{Duration, Instant},
 };
-use tokio::sync::{mpsc, watch};
+use tokio::sync::{mpsc, watch, Notify};
 use tonic::codegen::BoxFuture;
-use tonic::transport::{Body, Channel, Uri};
-use tonic::{body::BoxBody, transport::ClientTlsConfig};
-use tower::{Service, ServiceBuilder};
+use tonic::metadata::{Ascii, MetadataKey, MetadataValue};
+use tonic::service::{interceptor::InterceptedService, Interceptor};
+use tonic::transport::{Body, Channel, Endpoint, Uri};
+use tonic::Status;
+use tonic::{
+    body::BoxBody,
+    transport::{Certificate, ClientTlsConfig},
+};
+use tower::{Service, ServiceBuilder, ServiceExt};
 use tower_http::auth::AddAuthorization;
 use tower_http::auth::AddAuthorizationLayer;
 use tower_http::set_header::{SetRequestHeader, SetRequestHeaderLayer};
 
+// The stats of a webRTC channel, summarized for callers that don't depend on the webrtc crate.
+pub use super::client_channel::{CandidateSummary, StatsSummary};
+
 // gRPC status codes
 const STATUS_CODE_OK: i32 = 0;
 const STATUS_CODE_UNKNOWN: i32 = 2;
+const STATUS_CODE_DEADLINE_EXCEEDED: i32 = 4;
 const STATUS_CODE_RESOURCE_EXHAUSTED: i32 = 8;
+const STATUS_CODE_UNAVAILABLE: i32 = 14;
 
 pub const VIAM_MDNS_SERVICE_NAME: &'static str = "_rpc._tcp.local";
 
+// The txt record keys under which a robot advertises its metadata over mDNS.
+const MDNS_ROBOT_ID_TXT_KEY: &str = "robot_id";
+const MDNS_GRPC_TXT_KEY: &str = "grpc";
+const MDNS_WEBRTC_TXT_KEY: &str = "webrtc";
+const MDNS_SECURE_TXT_KEY: &str = "secure";
+
 type SecretType = String;
 
 #[derive(Clone)]
@@ -65,10 +102,32 @@ type SecretType = String;
 /// or a webRTC channel.
 pub enum ViamChannel {
     Direct(Channel),
-    DirectPreAuthorized(AddAuthorization<SetRequestHeader<Channel, HeaderValue>>),
+    DirectPreAuthorized(PreAuthorizedChannel),
     WebRTC(Arc<WebRTCClientChannel>),
 }
 
+/// A direct channel whose calls carry a bearer token and, unless the dial left it out, the
+/// `rpc-host` header addressing them to the robot. How it adds them is an implementation
+/// detail; it makes calls like any other channel.
+#[derive(Clone)]
+pub struct PreAuthorizedChannel(AuthorizedChannel);
+
+type AuthorizedChannel = AddAuthorization<SetRequestHeader<Channel, Option<HeaderValue>>>;
+
+impl Service<http::Request<BoxBody>> for PreAuthorizedChannel {
+    type Response = <AuthorizedChannel as Service<http::Request<BoxBody>>>::Response;
+    type Error = <AuthorizedChannel as Service<http::Request<BoxBody>>>::Error;
+    type Future = <AuthorizedChannel as Service<http::Request<BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        self.0.call(request)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RPCCredentials {
     entity: Option<String>,
@@ -85,6 +144,35 @@ impl RPCCredentials {
 }
 
 impl ViamChannel {
+    /// Wraps an already connected channel so that its calls carry the bearer token and are
+    /// addressed to rpc_host (the robot's address), like the channel a direct dial with
+    /// credentials returns. This lets a caller that caches tokens reconnect without
+    /// authenticating again. The caller is responsible for the token's freshness: once it
+    /// expires, calls are rejected as unauthenticated and a new token must be acquired.
+    pub fn direct_pre_authorized(channel: Channel, rpc_host: &str, token: &str) -> Result<Self> {
+        // AddAuthorizationLayer panics on a token that can't be a header value
+        HeaderValue::from_str(&format!("Bearer {token}"))
+            .context("The token is not a valid header value")?;
+        let rpc_host = HeaderValue::from_str(rpc_host)?;
+        Ok(Self::DirectPreAuthorized(PreAuthorizedChannel(
+            authorize_channel(channel, token, Some(rpc_host)),
+        )))
+    }
+
+    /// Closes the channel. A webRTC channel's peer connection is closed then and there rather
+    /// than by a task spawned when the last clone of the channel is dropped; for direct
+    /// channels, which tonic closes once dropped, this does nothing. Closing a channel that
+    /// is already closed is safe and succeeds.
+    pub async fn close(&self) -> Result<()> {
+        match self {
+            Self::Direct(_) | Self::DirectPreAuthorized(_) => Ok(()),
+            Self::WebRTC(channel) => {
+                channel.close().await;
+                Ok(())
+            }
+        }
+    }
+
     async fn create_resp(
         channel: &mut Arc<WebRTCClientChannel>,
         stream: crate::gen::proto::rpc::webrtc::v1::Stream,
@@ -93,6 +181,7 @@ impl ViamChannel {
     ) -> http::Response<Body> {
         let (parts, body) = request.into_parts();
         let mut status_code = STATUS_CODE_OK;
+        let mut status_message = String::new();
         let stream_id = stream.id;
         let metadata = Some(metadata_from_parts(&parts));
         let headers = RequestHeaders {
@@ -102,40 +191,79 @@ impl ViamChannel {
                 .map(PathAndQuery::to_string)
                 .unwrap_or_default(),
             metadata,
-            timeout: None,
+            timeout: timeout_from_parts(&parts),
         };
 
         if let Err(e) = channel.write_headers(&stream, headers).await {
-            log::error!("error writing headers: {e}");
+            status_message = format!("error writing headers: {e}");
+            dial_log!(error, "{status_message}");
             channel.close_stream_with_recv_error(stream_id, e);
             status_code = STATUS_CODE_UNKNOWN;
         }
 
-        let data = hyper::body::to_bytes(body).await.unwrap().to_vec();
-        if let Err(e) = channel.write_message(Some(stream), data).await {
-            log::error!("error sending message: {e}");
-            channel.close_stream_with_recv_error(stream_id, e);
-            status_code = STATUS_CODE_UNKNOWN;
-        };
+        if status_code == STATUS_CODE_OK {
+            if let Err(e) = Self::write_body(channel, &stream, body).await {
+                status_message = format!("error sending message: {e}");
+                dial_log!(error, "{status_message}");
+                // the server would otherwise keep waiting on the rest of the request
+                if let Err(e) = channel.reset_stream(stream_id).await {
+                    dial_log!(debug, "error resetting stream {stream_id}: {e}");
+                }
+                status_code = STATUS_CODE_UNKNOWN;
+            }
+        }
 
         let body = match channel.resp_body_from_stream(stream_id) {
             Ok(body) => body,
+            // the call has already failed, for the reason given above
+            Err(_) if status_code != STATUS_CODE_OK => Body::empty(),
             Err(e) => {
-                log::error!("error receiving response from stream: {e}");
+                status_message = format!("error receiving response from stream: {e}");
+                dial_log!(error, "{status_message}");
                 channel.close_stream_with_recv_error(stream_id, e);
                 status_code = STATUS_CODE_UNKNOWN;
                 Body::empty()
             }
         };
 
+        // tonic only surfaces the status in the headers for a failed call, so the reason for
+        // the failure has to go alongside it.
         let response = if status_code != STATUS_CODE_OK {
-            response.header("grpc-status", &status_code.to_string())
+            response
+                .header("grpc-status", &status_code.to_string())
+                .header("grpc-message", grpc_message_header_value(&status_message))
         } else {
             response
         };
 
         response.body(body).unwrap()
     }
+
+    // Writes the request body to the stream as its frames arrive rather than collecting the
+    // whole body up front. Complete gRPC messages are only held back until the next frame shows
+    // up, since the last message in the body has to be sent with the end of stream flag set.
+    async fn write_body(
+        channel: &WebRTCClientChannel,
+        stream: &crate::gen::proto::rpc::webrtc::v1::Stream,
+        mut body: BoxBody,
+    ) -> Result<()> {
+        let mut pending: Vec<u8> = Vec::new();
+        while let Some(frame) = body.data().await {
+            let frame = frame.map_err(|e| anyhow::anyhow!("error reading request body: {e}"))?;
+            let complete = complete_messages_len(&pending);
+            if complete > 0 {
+                let remaining = pending.split_off(complete);
+                channel
+                    .write_message(Some(stream.clone()), pending, false)
+                    .await?;
+                pending = remaining;
+            }
+            pending.extend_from_slice(&frame);
+        }
+        channel
+            .write_message(Some(stream.clone()), pending, true)
+            .await
+    }
 }
 
 impl Service<http::Request<BoxBody>> for ViamChannel {
@@ -152,36 +280,399 @@ impl Service<http::Request<BoxBody>> for ViamChannel {
     }
 
     fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        let deadline = request.extensions().get::<CallDeadline>().map(|d| d.0);
         match self {
-            Self::Direct(channel) => Box::pin(channel.call(request)),
-            Self::DirectPreAuthorized(channel) => Box::pin(channel.call(request)),
+            Self::Direct(channel) => with_deadline(channel.call(request), deadline, || {}),
+            Self::DirectPreAuthorized(channel) => {
+                with_deadline(channel.call(request), deadline, || {})
+            }
             Self::WebRTC(channel) => {
                 let mut channel = channel.clone();
-                let fut = async move {
-                    let response = http::response::Response::builder()
-                        // standardized gRPC headers.
-                        .header("content-type", "application/grpc")
-                        .version(Version::HTTP_2);
+                let response = http::response::Response::builder()
+                    // standardized gRPC headers.
+                    .header("content-type", "application/grpc")
+                    .version(Version::HTTP_2);
 
-                    match channel.new_stream() {
-                        Err(e) => {
-                            log::error!("{e}");
-                            let response = response
-                                .header("grpc-status", &STATUS_CODE_RESOURCE_EXHAUSTED.to_string())
-                                .body(Body::default())
-                                .unwrap();
+                // calls are made outside of the dial, so they're tagged with its id by hand
+                let dial_id = channel.base_channel.dial_id.clone();
+                let stream = match channel.new_stream() {
+                    Err(e) => {
+                        dial_log!(dial: dial_id, error, "{e}");
+                        let status_code = if channel.is_suspended() {
+                            STATUS_CODE_UNAVAILABLE
+                        } else {
+                            STATUS_CODE_RESOURCE_EXHAUSTED
+                        };
+                        let response = response
+                            .header("grpc-status", &status_code.to_string())
+                            .body(Body::default())
+                            .unwrap();
 
-                            Ok(response)
-                        }
-                        Ok(stream) => {
-                            Ok(Self::create_resp(&mut channel, stream, request, response).await)
-                        }
+                        return Box::pin(futures::future::ready(Ok(response)));
+                    }
+                    Ok(stream) => stream,
+                };
+
+                // if the call runs past its deadline, reset the stream so the server stops
+                // working on it and we stop buffering its responses.
+                let stream_id = stream.id;
+                let abort_channel = Arc::downgrade(&channel);
+                let abort_dial_id = dial_id.clone();
+                let abort_stream = move || {
+                    if let Some(channel) = abort_channel.upgrade() {
+                        tokio::spawn(in_dial(abort_dial_id, async move {
+                            if let Err(e) = channel.reset_stream(stream_id).await {
+                                dial_log!(debug, "error resetting stream {stream_id}: {e}");
+                            }
+                        }));
                     }
                 };
-                Box::pin(fut)
+
+                let fut = in_dial(dial_id, async move {
+                    Ok(Self::create_resp(&mut channel, stream, request, response).await)
+                });
+                with_deadline(fut, deadline, abort_stream)
+            }
+        }
+    }
+}
+
+// The point in time by which a call must complete, set on a request by a TimedChannel.
+#[derive(Clone, Copy, Debug)]
+struct CallDeadline(tokio::time::Instant);
+
+// Wraps a call's response future so that the call fails with DEADLINE_EXCEEDED if either the
+// response or the rest of its body doesn't arrive before the deadline, running `on_expire` if
+// it doesn't. Calls without a deadline are left as is.
+fn with_deadline<F>(
+    fut: F,
+    deadline: Option<tokio::time::Instant>,
+    on_expire: impl FnOnce() + Send + 'static,
+) -> BoxFuture<http::Response<Body>, tonic::transport::Error>
+where
+    F: Future<Output = Result<http::Response<Body>, tonic::transport::Error>> + Send + 'static,
+{
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return Box::pin(fut),
+    };
+    Box::pin(async move {
+        let deadline_exceeded = || {
+            http::response::Response::builder()
+                .header("content-type", "application/grpc")
+                .header("grpc-status", &STATUS_CODE_DEADLINE_EXCEEDED.to_string())
+                .header("grpc-message", "Deadline exceeded")
+                .version(Version::HTTP_2)
+                .body(Body::default())
+                .unwrap()
+        };
+        let response = match tokio::time::timeout_at(deadline, fut).await {
+            Ok(Ok(response)) => response,
+            // tonic enforces the grpc-timeout header itself on direct channels, failing the
+            // call with a transport error of its own once it passes.
+            Ok(Err(_)) if tokio::time::Instant::now() >= deadline => return Ok(deadline_exceeded()),
+            Ok(Err(e)) => return Err(e),
+            Err(_) => {
+                on_expire();
+                return Ok(deadline_exceeded());
+            }
+        };
+
+        let (parts, mut body) = response.into_parts();
+        let (mut sender, deadline_body) = Body::channel();
+        tokio::spawn(async move {
+            let forward = async {
+                while let Some(data) = body.data().await {
+                    sender.send_data(data?).await?;
+                }
+                if let Some(trailers) = body.trailers().await? {
+                    sender.send_trailers(trailers).await?;
+                }
+                Ok::<(), hyper::Error>(())
+            };
+            let forwarded = tokio::time::timeout_at(deadline, forward).await;
+            if let Ok(Err(_)) = forwarded {
+                sender.abort();
+            } else if forwarded.is_err() {
+                let mut trailers = http::HeaderMap::new();
+                trailers.insert(
+                    "grpc-status",
+                    HeaderValue::from(STATUS_CODE_DEADLINE_EXCEEDED),
+                );
+                trailers.insert(
+                    "grpc-message",
+                    HeaderValue::from_static("Deadline exceeded"),
+                );
+                let _ = sender.send_trailers(trailers).await;
+                on_expire();
             }
+        });
+        Ok(http::Response::from_parts(parts, deadline_body))
+    })
+}
+
+/// A ViamChannel that gives every call made through it a deadline. The deadline is sent to
+/// the server as a grpc-timeout header and also enforced locally: a call that runs past it
+/// fails with DEADLINE_EXCEEDED and, over webRTC, its stream is reset. A call that already
+/// carries a shorter grpc-timeout keeps it.
+#[derive(Clone)]
+pub struct TimedChannel {
+    channel: ViamChannel,
+    timeout: Duration,
+}
+
+impl TimedChannel {
+    pub fn new(channel: ViamChannel, timeout: Duration) -> Self {
+        Self { channel, timeout }
+    }
+}
+
+impl Service<http::Request<BoxBody>> for TimedChannel {
+    type Response = http::Response<Body>;
+    type Error = tonic::transport::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.channel.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<BoxBody>) -> Self::Future {
+        let timeout = match request
+            .headers()
+            .get(GRPC_TIMEOUT_HEADER)
+            .and_then(parse_grpc_timeout)
+        {
+            Some(requested) => requested.min(self.timeout),
+            None => self.timeout,
+        };
+        request
+            .headers_mut()
+            .insert(GRPC_TIMEOUT_HEADER, grpc_timeout_header_value(timeout));
+        request
+            .extensions_mut()
+            .insert(CallDeadline(tokio::time::Instant::now() + timeout));
+        self.channel.call(request)
+    }
+}
+
+impl ViamChannel {
+    /// Attaches a value of the caller's choosing to the channel, such as the id of the robot
+    /// it's connected to, so that code juggling many channels needn't keep a map alongside
+    /// them. ViamChannel's variants have no room for it, so the channel comes back wrapped in
+    /// a ContextChannel, which makes calls exactly as the channel would.
+    pub fn with_context<C: Any + Send + Sync>(self, context: C) -> ContextChannel {
+        ContextChannel {
+            channel: self,
+            context: Arc::new(context),
+        }
+    }
+}
+
+/// A ViamChannel carrying a context attached with [`ViamChannel::with_context`]. The context
+/// is only ever read back by the caller; calls go through to the channel untouched.
+#[derive(Clone)]
+pub struct ContextChannel {
+    channel: ViamChannel,
+    context: Arc<dyn Any + Send + Sync>,
+}
+
+impl ContextChannel {
+    /// Returns the attached context if it is a `T`.
+    pub fn context<T: Any>(&self) -> Option<&T> {
+        self.context.downcast_ref()
+    }
+
+    pub fn channel(&self) -> &ViamChannel {
+        &self.channel
+    }
+
+    pub fn into_channel(self) -> ViamChannel {
+        self.channel
+    }
+}
+
+impl Service<http::Request<BoxBody>> for ContextChannel {
+    type Response = http::Response<Body>;
+    type Error = tonic::transport::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.channel.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        self.channel.call(request)
+    }
+}
+
+/// A credentialed ViamChannel that remembers the options it was dialed with, made with
+/// [`DialBuilder::connect_reconnectable`], so that it can be dialed again without rebuilding
+/// them.
+#[derive(Clone)]
+pub struct ReconnectableChannel {
+    channel: ViamChannel,
+    builder: DialBuilder<WithCredentials>,
+    on_reconnect: Option<Arc<dyn Fn(u32) + Send + Sync>>,
+    backoff: ReconnectBackoff,
+    // How many reconnect attempts have been made since the last one that succeeded, shared
+    // with the channel's clones.
+    attempts: Arc<AtomicU32>,
+}
+
+impl ReconnectableChannel {
+    /// Calls the given hook with the attempt number, counting from 1, right before each
+    /// reconnect attempt dials, so that stateful clients can, for example, re-subscribe to
+    /// their streams. The count starts over once a reconnect succeeds.
+    pub fn on_reconnect(mut self, hook: impl Fn(u32) + Send + Sync + 'static) -> Self {
+        self.on_reconnect = Some(Arc::new(hook));
+        self
+    }
+
+    /// Makes reconnect wait initial before the second of a run of failing attempts and twice
+    /// as long before each one after it, up to max. Up to half of each wait is randomly taken
+    /// off, so that clients that lost their connections together don't reconnect in
+    /// lockstep. Defaults to an initial wait of 100ms capped at 10s.
+    pub fn reconnect_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = ReconnectBackoff { initial, max };
+        self
+    }
+
+    /// Dials again with the same options, authenticating and negotiating the connection
+    /// anew, and returns the fresh channel. This is meant for recovering from a lost
+    /// connection or an expired token, and can be done as often as needed. If the dial fails,
+    /// the next reconnect backs off before dialing; see
+    /// [`ReconnectableChannel::reconnect_backoff`].
+    pub async fn reconnect(&self) -> Result<ViamChannel> {
+        let attempt = self.attempts.fetch_add(1, Ordering::AcqRel) + 1;
+        let wait = self.backoff.before_attempt(attempt);
+        if !wait.is_zero() {
+            log::debug!("Backing off for {wait:?} before reconnect attempt {attempt}");
+            tokio::time::sleep(wait).await;
+        }
+        if let Some(on_reconnect) = &self.on_reconnect {
+            on_reconnect(attempt);
+        }
+        let channel = self.builder.clone().connect().await?;
+        self.attempts.store(0, Ordering::Release);
+        Ok(channel)
+    }
+
+    /// Reconnects as [`ReconnectableChannel::reconnect`] does and swaps the fresh channel in
+    /// for the current one, which is kept if the dial fails. Copies of the replaced channel
+    /// taken with [`ReconnectableChannel::channel`] keep using the old connection.
+    pub async fn reconnect_in_place(&mut self) -> Result<()> {
+        self.channel = self.reconnect().await?;
+        Ok(())
+    }
+
+    /// The channel as currently connected.
+    pub fn channel(&self) -> &ViamChannel {
+        &self.channel
+    }
+
+    /// Unwraps the channel, forgetting the options it was dialed with.
+    pub fn into_channel(self) -> ViamChannel {
+        self.channel
+    }
+}
+
+// How long a ReconnectableChannel waits before each attempt of a run of failing reconnects.
+#[derive(Debug, Clone, Copy)]
+struct ReconnectBackoff {
+    initial: Duration,
+    max: Duration,
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ReconnectBackoff {
+    // The first attempt goes ahead right away; the ones after it wait between half and all of
+    // a backoff that doubles with each attempt, up to max.
+    fn before_attempt(&self, attempt: u32) -> Duration {
+        if attempt <= 1 {
+            return Duration::ZERO;
+        }
+        let doublings = 2u32.saturating_pow(attempt - 2);
+        let backoff = self.initial.saturating_mul(doublings).min(self.max);
+        backoff / 2 + rand::thread_rng().gen_range(Duration::ZERO..=backoff / 2)
+    }
+}
+
+impl Service<http::Request<BoxBody>> for ReconnectableChannel {
+    type Response = http::Response<Body>;
+    type Error = tonic::transport::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.channel.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        self.channel.call(request)
+    }
+}
+
+const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+// grpc-timeout values are limited to eight digits followed by a unit.
+const GRPC_TIMEOUT_MAX_VALUE: u128 = 99_999_999;
+
+// Parses a grpc-timeout header value, returning None if it's malformed.
+fn parse_grpc_timeout(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?;
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().ok()?;
+    let timeout = match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    };
+    Some(timeout)
+}
+
+// Percent-encodes a status message for the grpc-message header, as the gRPC spec requires for
+// anything outside of printable ASCII.
+fn grpc_message_header_value(message: &str) -> HeaderValue {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        match byte {
+            b'%' => encoded.push_str("%25"),
+            0x20..=0x7e => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
         }
     }
+    HeaderValue::from_str(&encoded).unwrap()
+}
+
+// Formats a timeout as a grpc-timeout header value, using the finest unit that fits.
+fn grpc_timeout_header_value(timeout: Duration) -> HeaderValue {
+    let secs = u128::from(timeout.as_secs());
+    let value = [
+        (timeout.as_nanos(), 'n'),
+        (timeout.as_micros(), 'u'),
+        (timeout.as_millis(), 'm'),
+        (secs, 'S'),
+        (secs / 60, 'M'),
+        (secs / (60 * 60), 'H'),
+    ]
+    .into_iter()
+    .find(|(amount, _)| *amount <= GRPC_TIMEOUT_MAX_VALUE)
+    .map(|(amount, unit)| format!("{amount}{unit}"))
+    .unwrap_or_else(|| format!("{GRPC_TIMEOUT_MAX_VALUE}H"));
+    HeaderValue::from_str(&value).unwrap()
 }
 
 /// Options for modifying the connection parameters
@@ -191,9 +682,175 @@ pub struct DialOptions {
     webrtc_options: Option<Options>,
     uri: Option<Parts>,
     disable_mdns: bool,
+    expected_robot_id: Option<String>,
     allow_downgrade: bool,
     insecure: bool,
+    // Whether credentials may be sent over an insecure connection, where they're unencrypted.
+    allow_insecure_credentials: bool,
+    lazy_connect: bool,
+    attempts: AttemptOptions,
+    tls_sni: Option<String>,
+    // A CA trusted alongside the system's roots when validating the robot's certificate.
+    tls_ca_certificate: Option<Certificate>,
+    endpoint: Option<Endpoint>,
+    mdns_query: MdnsQueryOptions,
+    skip_webrtc_on_local: bool,
+    mdns_interface_policy: MdnsInterfacePolicy,
+    // The interval between HTTP/2 keepalive pings and how long to wait for each to be
+    // acknowledged. `None` leaves tonic's default of sending none.
+    http2_keepalive: Option<(Duration, Duration)>,
+    tcp: TcpSettings,
+    omit_rpc_host_header: bool,
+    address_family: AddressFamily,
+}
+
+// The TCP settings of the direct channels a dial opens. They're set on the channels' endpoints
+// and applied again by the connector those are connected with, which tonic doesn't configure.
+#[derive(Debug, Clone, Copy, Default)]
+struct TcpSettings {
+    // The idle time before TCP keepalive probes are sent. `None` sends none.
+    keepalive: Option<Duration>,
+    // How long opening the TCP connection may take. `None` leaves it to the OS.
+    connect_timeout: Option<Duration>,
+}
+
+impl TcpSettings {
+    fn endpoint(self, uri: Uri) -> Endpoint {
+        let endpoint = Channel::builder(uri).tcp_keepalive(self.keepalive);
+        match self.connect_timeout {
+            Some(timeout) => endpoint.connect_timeout(timeout),
+            None => endpoint,
+        }
+    }
+}
+
+// How many times connect tries to establish a connection, how long it gives each attempt
+// and all of them together, and how long it waits between attempts.
+#[derive(Debug, Clone, Copy)]
+struct AttemptOptions {
+    max_attempts: u32,
+    per_attempt_timeout: Option<Duration>,
+    overall_timeout: Option<Duration>,
+    initial_backoff: Option<Duration>,
+}
+
+impl Default for AttemptOptions {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            per_attempt_timeout: None,
+            overall_timeout: None,
+            initial_backoff: None,
+        }
+    }
+}
+
+/// How a connection was established, as returned by a DialBuilder's `connect_with_report`:
+/// how long each of its phases took and who it authenticated as. Phases that didn't happen on
+/// the way to the returned connection are `None`, so the phases that did happen add up to
+/// roughly `total`.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectReport {
+    /// Looking up the robot's address over mDNS.
+    pub mdns: Option<Duration>,
+    /// Opening the TCP connection to the robot.
+    pub tcp_connect: Option<Duration>,
+    /// The TLS handshake (for secure connections) and the HTTP/2 handshake that follows it.
+    pub handshake: Option<Duration>,
+    /// Acquiring an auth token with the dial's credentials.
+    pub auth: Option<Duration>,
+    /// Negotiating a webRTC connection through the signaling server, whether or not it
+    /// succeeded.
+    pub webrtc: Option<Duration>,
+    /// The connection attempt as a whole.
+    pub total: Duration,
+    /// The entity the connection authenticated as: the one given with the dial's credentials,
+    /// or the robot's host when none was given. `None` when dialing without credentials.
+    pub authenticated_entity: Option<String>,
+    /// The id the dial's log lines are prefixed with, as `[dial <id>]`, which tells them apart
+    /// from those of other dials running at the same time.
+    pub dial_id: String,
+}
+
+// How long connect spends looking for the robot's address over mDNS.
+const MDNS_URI_TIMEOUT: Duration = Duration::from_millis(1500);
+// How often mDNS queries are repeated on each interface, before any jitter is added.
+const MDNS_QUERY_INTERVAL: Duration = Duration::from_millis(250);
+
+// How many mDNS queries are sent on each interface, and up to how much longer than
+// MDNS_QUERY_INTERVAL to randomly make the interval between them.
+#[derive(Debug, Clone, Copy)]
+struct MdnsQueryOptions {
+    repeats: u32,
+    jitter: Duration,
+}
+
+impl Default for MdnsQueryOptions {
+    fn default() -> Self {
+        Self {
+            repeats: 5,
+            jitter: Duration::ZERO,
+        }
+    }
+}
+
+impl MdnsQueryOptions {
+    fn interval(&self) -> Duration {
+        MDNS_QUERY_INTERVAL + rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+    }
+}
+
+// How long an mDNS lookup that has found the robot on one interface keeps waiting for others
+// to answer, and how long the connect race between the addresses found may take. Both fit
+// within MDNS_URI_TIMEOUT.
+const MDNS_SELECTION_WINDOW: Duration = Duration::from_millis(100);
+
+/// How to choose between the addresses found for a robot when it answers mDNS queries on
+/// more than one network interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MdnsInterfacePolicy {
+    /// Uses the address found on whichever interface answers first.
+    #[default]
+    FirstWins,
+    /// Uses the address found on the named interface (e.g. "eth0"), waiting briefly for it to
+    /// answer once another interface has, and otherwise the first address found.
+    PreferInterface(String),
+    /// Waits briefly for every interface to answer, then uses whichever of the addresses
+    /// found accepts a TCP connection first.
+    LowestLatency,
+}
+
+/// Which IP address families direct connections may use when the robot's host resolves to
+/// addresses of both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressFamily {
+    /// Only IPv4 addresses are attempted.
+    V4,
+    /// Only IPv6 addresses are attempted.
+    V6,
+    /// Addresses of either family are attempted, in the order they resolve in.
+    #[default]
+    Dual,
+}
+
+impl AddressFamily {
+    fn allows(self, ip: IpAddr) -> bool {
+        match self {
+            Self::V4 => ip.is_ipv4(),
+            Self::V6 => ip.is_ipv6(),
+            Self::Dual => true,
+        }
+    }
+}
+
+// The address a robot was found at on one network interface.
+#[derive(Debug, Clone)]
+struct MdnsAnswer {
+    interface: String,
+    addr: String,
+    info: RobotMdnsInfo,
 }
+
 #[derive(Clone)]
 pub struct WantsCredentials(());
 #[derive(Clone)]
@@ -223,6 +880,159 @@ impl<T> fmt::Debug for DialBuilder<T> {
 }
 
 impl DialOptions {
+    // Returns the pairs of options that were both set but contradict each other, along with
+    // why, so that connect can refuse them rather than quietly ignoring one.
+    fn conflicts(&self) -> Vec<(&'static str, &'static str, &'static str)> {
+        let mut conflicts = Vec::new();
+        if let Some(webrtc) = self.webrtc_options.as_ref().filter(|o| o.disable_webrtc) {
+            let webrtc_only = [
+                ("signaling_server", webrtc.signaling_server.is_some()),
+                ("signaling_header", !webrtc.signaling_headers.is_empty()),
+                (
+                    "additional_local_candidate",
+                    !webrtc.additional_local_candidates.is_empty(),
+                ),
+                ("dtls_certificate", webrtc.dtls_certificate.is_some()),
+                ("sdp_base64_urlsafe", webrtc.sdp_base64_urlsafe),
+                ("post_open_probe", webrtc.post_open_probe.is_some()),
+                ("webrtc_negotiation_retries", webrtc.negotiation_retries > 0),
+                ("max_receive_buffer", webrtc.max_receive_buffer.is_some()),
+                (
+                    "max_concurrent_streams",
+                    webrtc.max_concurrent_streams.is_some(),
+                ),
+                ("with_ice_servers", !webrtc.ice_servers.is_empty()),
+                ("on_dial_event", webrtc.dial_event_hook.is_set()),
+                (
+                    "ice_disconnected_timeout",
+                    webrtc.ice_disconnected_timeout.is_some(),
+                ),
+                ("ice_failed_timeout", webrtc.ice_failed_timeout.is_some()),
+                ("skip_webrtc_on_local", self.skip_webrtc_on_local),
+            ];
+            for (option, _) in webrtc_only.into_iter().filter(|(_, set)| *set) {
+                conflicts.push(("disable_webrtc", option, "it only applies to webRTC"));
+            }
+        }
+        if self.disable_mdns {
+            if self.expected_robot_id.is_some() {
+                conflicts.push((
+                    "disable_mdns",
+                    "expected_robot_id",
+                    "it only applies to mDNS",
+                ));
+            }
+            if self.skip_webrtc_on_local {
+                conflicts.push((
+                    "disable_mdns",
+                    "skip_webrtc_on_local",
+                    "it only applies to connections made via mDNS",
+                ));
+            }
+            if self.mdns_interface_policy != MdnsInterfacePolicy::FirstWins {
+                conflicts.push((
+                    "disable_mdns",
+                    "mdns_interface_policy",
+                    "it only applies to mDNS",
+                ));
+            }
+        }
+        if self.lazy_connect && self.allow_downgrade {
+            conflicts.push((
+                "lazy_connect",
+                "allow_downgrade",
+                "lazily connected channels can't be downgraded",
+            ));
+        }
+        if self.endpoint.is_some() {
+            if self.tls_sni.is_some() {
+                conflicts.push((
+                    "from_endpoint",
+                    "tls_sni",
+                    "the endpoint's own TLS settings are used",
+                ));
+            }
+            if self.tls_ca_certificate.is_some() {
+                conflicts.push((
+                    "from_endpoint",
+                    "tls_ca_certificate",
+                    "the endpoint's own TLS settings are used",
+                ));
+            }
+            if self.allow_downgrade {
+                conflicts.push((
+                    "from_endpoint",
+                    "allow_downgrade",
+                    "the endpoint is connected to as is",
+                ));
+            }
+            if self.http2_keepalive.is_some() {
+                conflicts.push((
+                    "from_endpoint",
+                    "http2_keepalive",
+                    "the endpoint's own keepalive settings are used",
+                ));
+            }
+            if self.tcp.keepalive.is_some() {
+                conflicts.push((
+                    "from_endpoint",
+                    "tcp_keepalive",
+                    "the endpoint's own keepalive settings are used",
+                ));
+            }
+            if self.tcp.connect_timeout.is_some() {
+                conflicts.push((
+                    "from_endpoint",
+                    "tcp_connect_timeout",
+                    "the endpoint's own timeouts are used",
+                ));
+            }
+        }
+        conflicts
+    }
+
+    // Fails, listing every conflict, if any of the options contradict each other, if
+    // credentials would be sent unencrypted without allow_insecure_credentials, or if the
+    // signaling server isn't a valid uri.
+    fn validate(&self) -> Result<()> {
+        let plaintext = self.insecure
+            || self
+                .endpoint
+                .as_ref()
+                .is_some_and(|endpoint| endpoint.uri().scheme() == Some(&Scheme::HTTP));
+        if self.credentials.is_some() && plaintext && !self.allow_insecure_credentials {
+            return Err(anyhow::anyhow!(
+                "Refusing to dial insecurely with credentials, which would be sent unencrypted; \
+                 call allow_insecure_credentials to send them anyway"
+            ));
+        }
+        if let Some(signaling_server) = self
+            .webrtc_options
+            .as_ref()
+            .and_then(|options| options.signaling_server.as_deref())
+        {
+            signaling_server_uri(signaling_server)?;
+        }
+        let conflicts = self.conflicts();
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        let conflicts: Vec<String> = conflicts
+            .into_iter()
+            .map(|(a, b, why)| format!("{a} and {b} ({why})"))
+            .collect();
+        Err(anyhow::anyhow!(
+            "Conflicting dial options: {}",
+            conflicts.join("; ")
+        ))
+    }
+
+    // Whether a connection that fails over https may be retried over plain http, which is
+    // never done with credentials unless they may be sent unencrypted.
+    fn may_downgrade(&self) -> bool {
+        self.allow_downgrade && (self.credentials.is_none() || self.allow_insecure_credentials)
+    }
+
     /// Creates a new DialBuilder
     pub fn builder() -> DialBuilder<WantsUri> {
         DialBuilder {
@@ -232,8 +1042,22 @@ impl DialOptions {
                 uri: None,
                 allow_downgrade: false,
                 disable_mdns: false,
+                expected_robot_id: None,
                 insecure: false,
+                allow_insecure_credentials: false,
                 webrtc_options: None,
+                lazy_connect: false,
+                attempts: AttemptOptions::default(),
+                tls_sni: None,
+                tls_ca_certificate: None,
+                endpoint: None,
+                mdns_query: MdnsQueryOptions::default(),
+                skip_webrtc_on_local: false,
+                mdns_interface_policy: MdnsInterfacePolicy::default(),
+                http2_keepalive: None,
+                tcp: TcpSettings::default(),
+                omit_rpc_host_header: false,
+                address_family: AddressFamily::Dual,
             },
         }
     }
@@ -250,13 +1074,62 @@ impl DialBuilder<WantsUri> {
                 uri: Some(uri_parts),
                 allow_downgrade: false,
                 disable_mdns: false,
+                expected_robot_id: None,
                 insecure: false,
+                allow_insecure_credentials: false,
                 webrtc_options: None,
+                lazy_connect: false,
+                attempts: AttemptOptions::default(),
+                tls_sni: None,
+                tls_ca_certificate: None,
+                endpoint: None,
+                mdns_query: MdnsQueryOptions::default(),
+                skip_webrtc_on_local: false,
+                mdns_interface_policy: MdnsInterfacePolicy::default(),
+                http2_keepalive: None,
+                tcp: TcpSettings::default(),
+                omit_rpc_host_header: false,
+                address_family: AddressFamily::Dual,
             },
         }
     }
 }
 impl DialBuilder<WantsCredentials> {
+    /// Creates a DialBuilder that connects to the robot through an already configured tonic
+    /// endpoint (with its own TLS settings, timeouts and so on) rather than a channel of its
+    /// own making. The endpoint is used as is for the direct connection and, unless a separate
+    /// signaling server is configured, for webRTC signaling. mDNS is disabled, since it would
+    /// connect to the robot without going through the endpoint.
+    pub fn from_endpoint(endpoint: Endpoint) -> Self {
+        let mut uri = endpoint.uri().clone().into_parts();
+        uri.path_and_query = Some(PathAndQuery::from_static(""));
+        DialBuilder {
+            state: WantsCredentials(()),
+            config: DialOptions {
+                credentials: None,
+                uri: Some(uri),
+                allow_downgrade: false,
+                disable_mdns: true,
+                expected_robot_id: None,
+                insecure: false,
+                allow_insecure_credentials: false,
+                webrtc_options: None,
+                lazy_connect: false,
+                attempts: AttemptOptions::default(),
+                tls_sni: None,
+                tls_ca_certificate: None,
+                endpoint: Some(endpoint),
+                mdns_query: MdnsQueryOptions::default(),
+                skip_webrtc_on_local: false,
+                mdns_interface_policy: MdnsInterfacePolicy::default(),
+                http2_keepalive: None,
+                tcp: TcpSettings::default(),
+                omit_rpc_host_header: false,
+                address_family: AddressFamily::Dual,
+            },
+        }
+    }
+
     /// Tells connecting logic to not expect/require credentials
     pub fn without_credentials(self) -> DialBuilder<WithoutCredentials> {
         DialBuilder {
@@ -265,9 +1138,23 @@ impl DialBuilder<WantsCredentials> {
                 credentials: None,
                 uri: self.config.uri,
                 allow_downgrade: false,
-                disable_mdns: false,
+                disable_mdns: self.config.disable_mdns,
+                expected_robot_id: None,
                 insecure: false,
+                allow_insecure_credentials: false,
                 webrtc_options: None,
+                lazy_connect: false,
+                attempts: AttemptOptions::default(),
+                tls_sni: None,
+                tls_ca_certificate: None,
+                endpoint: self.config.endpoint,
+                mdns_query: MdnsQueryOptions::default(),
+                skip_webrtc_on_local: false,
+                mdns_interface_policy: MdnsInterfacePolicy::default(),
+                http2_keepalive: None,
+                tcp: TcpSettings::default(),
+                omit_rpc_host_header: false,
+                address_family: AddressFamily::Dual,
             },
         }
     }
@@ -279,9 +1166,23 @@ impl DialBuilder<WantsCredentials> {
                 credentials: Some(creds),
                 uri: self.config.uri,
                 allow_downgrade: false,
-                disable_mdns: false,
+                disable_mdns: self.config.disable_mdns,
+                expected_robot_id: None,
                 insecure: false,
+                allow_insecure_credentials: false,
                 webrtc_options: None,
+                lazy_connect: false,
+                attempts: AttemptOptions::default(),
+                tls_sni: None,
+                tls_ca_certificate: None,
+                endpoint: self.config.endpoint,
+                mdns_query: MdnsQueryOptions::default(),
+                skip_webrtc_on_local: false,
+                mdns_interface_policy: MdnsInterfacePolicy::default(),
+                http2_keepalive: None,
+                tcp: TcpSettings::default(),
+                omit_rpc_host_header: false,
+                address_family: AddressFamily::Dual,
             },
         }
     }
@@ -304,22 +1205,517 @@ impl<T: AuthMethod> DialBuilder<T> {
         self
     }
 
-    /// Overrides any default connection behavior, forcing direct connection. Note that
-    /// the connection itself will fail if it is between a client and server on separate
-    /// networks and not over webRTC
-    pub fn disable_webrtc(mut self) -> Self {
-        let webrtc_options = Options::default().disable_webrtc();
-        self.config.webrtc_options = Some(webrtc_options);
+    /// Only accepts mDNS responses whose txt records advertise the given robot id, so that
+    /// a similarly named robot on the same network isn't mistaken for the one being dialed.
+    pub fn expected_robot_id(mut self, robot_id: &str) -> Self {
+        self.config.expected_robot_id = Some(robot_id.to_string());
         self
     }
 
-    async fn get_addr_from_interface(
-        iface: (&str, Vec<&IpAddr>),
-        candidates: &Vec<String>,
-    ) -> Option<String> {
-        let addresses: Vec<Ipv4Addr> = iface
-            .1
-            .iter()
+    /// Sets how many mDNS queries are sent on each network interface while looking for the
+    /// robot, so that a robot whose first answers are lost on a congested network is still
+    /// found. Responses are listened for until an interval after the last query. Defaults to
+    /// 5. Note that the mDNS lookup as a whole is still bounded to 1.5 seconds.
+    pub fn mdns_query_repeats(mut self, repeats: u32) -> Self {
+        self.config.mdns_query.repeats = repeats.max(1);
+        self
+    }
+
+    /// Randomly lengthens the interval between mDNS queries, normally 250ms, by up to the
+    /// given jitter, so that clients started together don't keep querying in lockstep. The
+    /// interval is drawn once per interface for each lookup.
+    pub fn mdns_query_jitter(mut self, jitter: Duration) -> Self {
+        self.config.mdns_query.jitter = jitter;
+        self
+    }
+
+    /// Sets how to choose between the addresses found for the robot when it answers mDNS
+    /// queries on more than one network interface, as it may on a host attached to several
+    /// subnets. Defaults to [`MdnsInterfacePolicy::FirstWins`].
+    pub fn mdns_interface_policy(mut self, policy: MdnsInterfacePolicy) -> Self {
+        self.config.mdns_interface_policy = policy;
+        self
+    }
+
+    /// Skips webRTC when the robot was reached directly at an address found over mDNS, since
+    /// the local network connection is already about as fast as webRTC would be and
+    /// negotiating it only delays the dial. Unlike disabling webRTC, connections made through
+    /// the robot's uri still attempt webRTC.
+    pub fn skip_webrtc_on_local(mut self) -> Self {
+        self.config.skip_webrtc_on_local = true;
+        self
+    }
+
+    /// Defers connecting a direct channel until its first call rather than connecting while
+    /// dialing. This only takes effect for direct connections to the uri itself: mDNS
+    /// candidates are still connected to eagerly, and webRTC and authenticating with
+    /// credentials use the channel, and so connect it, while dialing. Lazily connected
+    /// channels can't be downgraded to HTTP.
+    pub fn lazy_connect(mut self) -> Self {
+        self.config.lazy_connect = true;
+        self
+    }
+
+    /// Presents the given server name during the TLS handshake and validates the robot's
+    /// certificate against it, rather than the host being connected to. This allows
+    /// connecting to a robot by IP address while validating its named certificate. The
+    /// `rpc-host` header sent with calls is unaffected.
+    pub fn tls_sni(mut self, server_name: &str) -> Self {
+        self.config.tls_sni = Some(server_name.to_string());
+        self
+    }
+
+    /// Trusts the PEM encoded CA certificate, alongside the system's roots, when validating
+    /// the robot's certificate, e.g. for a robot whose certificate was issued by a private CA.
+    pub fn tls_ca_certificate(mut self, pem: impl AsRef<[u8]>) -> Self {
+        self.config.tls_ca_certificate = Some(Certificate::from_pem(pem));
+        self
+    }
+
+    /// Sends HTTP/2 keepalive pings at the given interval on the direct channels to the robot,
+    /// over mDNS or not, and to an explicitly configured signaling server, closing a
+    /// connection whose ping isn't acknowledged within the timeout. This keeps long-lived
+    /// connections from silently dying behind NATs and firewalls that drop idle flows. By
+    /// default no pings are sent.
+    pub fn http2_keepalive(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.config.http2_keepalive = Some((interval, timeout));
+        self
+    }
+
+    /// Enables TCP keepalive on the direct channels to the robot and to an explicitly
+    /// configured signaling server, sending probes once a connection has been idle for the
+    /// given time. By default no probes are sent.
+    pub fn tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.config.tcp.keepalive = Some(idle);
+        self
+    }
+
+    /// Bounds how long opening the TCP connection of each direct channel may take, failing
+    /// that channel rather than waiting on the OS's own timeout. By default there's no bound
+    /// beyond the dial's other timeouts.
+    pub fn tcp_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.tcp.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Leaves out the `rpc-host` header, which addresses calls to the robot when they go
+    /// through a proxy or the signaling server, from the calls made over direct channels and
+    /// from signaling. This is for custom servers that reject or misroute calls carrying an
+    /// unexpected `rpc-host` header. By default the header is sent.
+    pub fn without_rpc_host_header(mut self) -> Self {
+        self.config.omit_rpc_host_header = true;
+        self
+    }
+
+    /// Limits the direct connections to the robot, over mDNS or not, and to an explicitly
+    /// configured signaling server to addresses of the given family. This avoids waiting out
+    /// connection timeouts on hosts where one of the IPv4 and IPv6 stacks is broken. By default
+    /// addresses of both families are attempted.
+    pub fn address_family(mut self, family: AddressFamily) -> Self {
+        self.config.address_family = family;
+        self
+    }
+
+    /// Returns the key a [`DialCoalescer`] shares this dial's connection under: the uri it
+    /// connects to along with a fingerprint of its credentials, if any. Fails if the uri is
+    /// incomplete, as there's then no telling which robot the dial is to.
+    pub fn dial_key(&self) -> Result<DialKey> {
+        let uri = self
+            .duplicate_uri()
+            .and_then(|parts| Uri::from_parts(parts).ok())
+            .context("The uri to dial is incomplete")?;
+        Ok(DialKey::new(
+            &uri.to_string(),
+            self.config.credentials.as_ref(),
+        ))
+    }
+
+    /// Describes the configuration connect will use, one setting per line, with defaults and
+    /// values inferred from the uri (such as the signaling server of a cloud robot) filled
+    /// in. Credential secrets are left out.
+    pub fn describe(&self) -> String {
+        let mut uri = self
+            .duplicate_uri()
+            .and_then(|parts| Uri::from_parts(parts).ok());
+        if self.config.insecure {
+            uri = uri.and_then(|uri| {
+                let mut parts = uri.into_parts();
+                parts.scheme = Some(Scheme::HTTP);
+                Uri::from_parts(parts).ok()
+            });
+        }
+        let host = uri
+            .as_ref()
+            .and_then(Uri::authority)
+            .map(Authority::to_string)
+            .unwrap_or_default();
+        let or_none = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+        let timeout = |timeout: Option<Duration>| or_none(timeout.map(|t| format!("{t:?}")));
+
+        let mut lines = vec![
+            format!("uri: {}", or_none(uri.as_ref().map(Uri::to_string))),
+            format!(
+                "credentials: {}",
+                or_none(self.config.credentials.as_ref().map(|creds| format!(
+                    "{} for entity {}",
+                    creds.credentials.r#type,
+                    creds.entity.as_deref().unwrap_or(&host)
+                )))
+            ),
+            format!("insecure: {}", self.config.insecure),
+            format!(
+                "allow insecure credentials: {}",
+                self.config.allow_insecure_credentials
+            ),
+            format!("allow downgrade: {}", self.config.allow_downgrade),
+            format!(
+                "tls server name: {}",
+                self.config.tls_sni.as_deref().unwrap_or(&host)
+            ),
+            format!(
+                "tls CA certificate: {}",
+                match self.config.tls_ca_certificate {
+                    Some(_) => "supplied",
+                    None => "system roots only",
+                }
+            ),
+            format!("lazy connect: {}", self.config.lazy_connect),
+            format!(
+                "HTTP/2 keepalive: {}",
+                or_none(
+                    self.config
+                        .http2_keepalive
+                        .map(|(interval, timeout)| format!(
+                            "every {interval:?}, timing out after {timeout:?}"
+                        ))
+                )
+            ),
+            format!(
+                "TCP keepalive: {}",
+                or_none(
+                    self.config
+                        .tcp
+                        .keepalive
+                        .map(|idle| format!("after {idle:?} idle"))
+                )
+            ),
+            format!(
+                "TCP connect timeout: {}",
+                or_none(self.config.tcp.connect_timeout.map(|t| format!("{t:?}")))
+            ),
+            format!("rpc-host header: {}", !self.config.omit_rpc_host_header),
+            format!("address family: {:?}", self.config.address_family),
+            format!("supplied endpoint: {}", self.config.endpoint.is_some()),
+        ];
+        if self.config.disable_mdns {
+            lines.push("mDNS: disabled".to_string());
+        } else {
+            let query = &self.config.mdns_query;
+            lines.push(format!(
+                "mDNS: enabled (lookup timeout {MDNS_URI_TIMEOUT:?}, {} queries per interface \
+                 every {MDNS_QUERY_INTERVAL:?} plus up to {:?} of jitter, expected robot id {}, \
+                 interface policy {:?})",
+                query.repeats,
+                query.jitter,
+                or_none(self.config.expected_robot_id.clone()),
+                self.config.mdns_interface_policy
+            ));
+        }
+        let attempts = &self.config.attempts;
+        lines.push(format!(
+            "attempts: {} (per attempt timeout {}, overall timeout {}, initial backoff {})",
+            attempts.max_attempts,
+            timeout(attempts.per_attempt_timeout),
+            timeout(attempts.overall_timeout),
+            timeout(attempts.initial_backoff)
+        ));
+
+        let webrtc_options = self.config.webrtc_options.as_ref();
+        if webrtc_options.is_some_and(|options| options.disable_webrtc) {
+            lines.push("webRTC: disabled".to_string());
+            return lines.join("\n");
+        }
+        lines.push(if self.config.skip_webrtc_on_local {
+            "webRTC: enabled (skipped when connected via mDNS)".to_string()
+        } else {
+            "webRTC: enabled".to_string()
+        });
+        let signaling_server = match webrtc_options.and_then(|o| o.signaling_server.as_ref()) {
+            Some(signaling_server) => signaling_server.to_string(),
+            None => match &uri {
+                Some(uri) => {
+                    let inferred = infer_remote_uri_from_authority(uri.clone());
+                    if &inferred == uri {
+                        format!("{inferred} (the dialed uri)")
+                    } else {
+                        format!("{inferred} (inferred from the uri)")
+                    }
+                }
+                None => "none".to_string(),
+            },
+        };
+        lines.push(format!("  signaling server: {signaling_server}"));
+        let trickle_ice = !webrtc_options.is_some_and(|options| options.disable_trickle_ice);
+        lines.push(format!("  trickle ICE: {trickle_ice}"));
+        let header_names: Vec<&str> = webrtc_options
+            .map(|options| {
+                options
+                    .signaling_headers
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect()
+            })
+            .unwrap_or_default();
+        lines.push(format!("  signaling headers: {header_names:?}"));
+        let additional_candidates = webrtc_options
+            .map(|options| options.additional_local_candidates.len())
+            .unwrap_or_default();
+        lines.push(format!(
+            "  additional local candidates: {additional_candidates}"
+        ));
+        let ice_servers: Vec<&str> = webrtc_options
+            .iter()
+            .flat_map(|options| &options.ice_servers)
+            .flat_map(|server| &server.urls)
+            .map(String::as_str)
+            .collect();
+        lines.push(format!(
+            "  supplied ICE servers: {}",
+            or_none((!ice_servers.is_empty()).then(|| ice_servers.join(", ")))
+        ));
+        let sdp_base64_urlsafe = webrtc_options.is_some_and(|options| options.sdp_base64_urlsafe);
+        lines.push(format!("  url-safe sdp encoding: {sdp_base64_urlsafe}"));
+        let retries = webrtc_options.map_or(0, |options| options.negotiation_retries);
+        lines.push(format!("  negotiation retries: {retries}"));
+        lines.push(format!(
+            "  receive buffer cap: {}",
+            or_none(
+                webrtc_options
+                    .and_then(|options| options.max_receive_buffer)
+                    .map(|bytes| format!("{bytes} bytes"))
+            )
+        ));
+        lines.push(format!(
+            "  concurrent stream cap: {}",
+            webrtc_options
+                .and_then(|options| options.max_concurrent_streams)
+                .unwrap_or(MAX_CONCURRENT_STREAM_COUNT)
+        ));
+        let event_hook = webrtc_options.is_some_and(|options| options.dial_event_hook.is_set());
+        lines.push(format!("  dial event hook: {event_hook}"));
+        lines.push(format!(
+            "  post-open probe: {}",
+            or_none(
+                webrtc_options
+                    .and_then(|options| options.post_open_probe)
+                    .map(|settle| format!("settling for {settle:?}"))
+            )
+        ));
+        let ice_timeout = |timeout: Option<Duration>| {
+            timeout.map_or_else(|| "default".to_string(), |t| format!("{t:?}"))
+        };
+        lines.push(format!(
+            "  ICE disconnected timeout: {}",
+            ice_timeout(webrtc_options.and_then(|o| o.ice_disconnected_timeout))
+        ));
+        lines.push(format!(
+            "  ICE failed timeout: {}",
+            ice_timeout(webrtc_options.and_then(|o| o.ice_failed_timeout))
+        ));
+        lines.join("\n")
+    }
+
+    /// Makes connect try to establish a connection up to max_attempts times before giving up,
+    /// starting a new attempt as soon as the previous one fails or times out. Defaults to a
+    /// single attempt.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.config.attempts.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Makes connect try to establish a connection up to max_attempts times, waiting
+    /// initial_backoff after the first failed attempt and twice as long after each one after
+    /// it. Attempts that fail because the robot rejected the dial's credentials aren't
+    /// retried, since retrying them can't succeed.
+    pub fn with_retries(mut self, max_attempts: u32, initial_backoff: Duration) -> Self {
+        self.config.attempts.max_attempts = max_attempts.max(1);
+        self.config.attempts.initial_backoff = Some(initial_backoff);
+        self
+    }
+
+    /// Bounds how long each connection attempt may take, so that an attempt hanging on a
+    /// partially failed network doesn't use up the time left for the ones after it.
+    pub fn per_attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.config.attempts.per_attempt_timeout = Some(timeout);
+        self
+    }
+
+    /// Bounds how long connect may take across all of its attempts.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.attempts.overall_timeout = Some(timeout);
+        self
+    }
+
+    /// Overrides any default connection behavior, forcing direct connection. Note that
+    /// the connection itself will fail if it is between a client and server on separate
+    /// networks and not over webRTC
+    pub fn disable_webrtc(mut self) -> Self {
+        // keep any other webRTC settings so that connect can report them as conflicting
+        let webrtc_options = self.take_webrtc_options().disable_webrtc();
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Replaces the options webRTC connections are made with, e.g. to gather candidates
+    /// through one's own TURN servers with [`Options::with_ice_servers`]. Any webRTC options
+    /// set on the builder before this are discarded, while ones set after it are added to the
+    /// given options.
+    pub fn webrtc_options(mut self, options: Options) -> Self {
+        self.config.webrtc_options = Some(options);
+        self
+    }
+
+    /// Uses the provided certificate for the webRTC DTLS handshake rather than generating a
+    /// new one for every connection. This allows peers to pin the certificate's fingerprint.
+    pub fn dtls_certificate(mut self, certificate: RTCCertificate) -> Self {
+        let webrtc_options = self.take_webrtc_options().dtls_certificate(certificate);
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Runs webRTC signaling against the given server instead of over the channel opened to
+    /// the dialed uri. When dialing with credentials, the signaling server is sent the access
+    /// token obtained from the dialed uri. If webRTC can't be established, the connection falls
+    /// back to the dialed uri as usual. Fails the connect if the signaling server isn't a valid uri.
+    pub fn signaling_server(mut self, uri: &str) -> Self {
+        let webrtc_options = self.take_webrtc_options().signaling_server(uri.to_string());
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Adds a header to the webRTC signaling RPCs only, e.g. one that a gateway in front of
+    /// the signaling server routes on. Calls made over the resulting connection don't carry
+    /// it. Invalid header names or values fail the webRTC attempt.
+    pub fn signaling_header(mut self, name: &str, value: &str) -> Self {
+        let webrtc_options = self
+            .take_webrtc_options()
+            .signaling_header(name.to_string(), value.to_string());
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Probes a webRTC connection once its data channel opens and before returning it: the
+    /// dial waits for the peer connection to report connected and for the SCTP association
+    /// under the data channel to be established, failing the webRTC attempt if they aren't
+    /// within the webRTC timeout, and then waits for the settle delay. This trades a little
+    /// connection time for not losing the first message sent on a connection that opened
+    /// before it was fully usable.
+    pub fn post_open_probe(mut self, settle: Duration) -> Self {
+        let webrtc_options = self.take_webrtc_options().post_open_probe(settle);
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Retries a failed webRTC negotiation up to the given number of times before falling back
+    /// to a direct connection. Each retry closes the failed peer connection and starts the
+    /// whole offer, answer and ICE exchange over on a new one, which often succeeds where the
+    /// first attempt lost a race gathering candidates or failed ICE on a flaky network.
+    /// Defaults to no retries.
+    pub fn webrtc_negotiation_retries(mut self, retries: u32) -> Self {
+        let webrtc_options = self.take_webrtc_options().negotiation_retries(retries);
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Caps how many bytes of response messages a webRTC connection holds across all of its
+    /// streams, whether partially received or waiting to be read, bounding the memory that
+    /// many concurrent server streams can take up together. While the cap is reached, new
+    /// calls are refused, and a stream receiving more than fits under the cap is reset.
+    /// Defaults to no cap, in which case a stream whose reader falls a few messages behind
+    /// holds up the connection until the reader catches up.
+    pub fn max_receive_buffer(mut self, bytes: usize) -> Self {
+        let webrtc_options = self.take_webrtc_options().max_receive_buffer(bytes);
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Caps how many calls a webRTC connection has in flight at once, bounding the memory
+    /// taken up by streams that are never finished. While the cap is reached, new calls are
+    /// refused with RESOURCE_EXHAUSTED. Defaults to 256.
+    pub fn max_concurrent_streams(mut self, count: usize) -> Self {
+        let webrtc_options = self.take_webrtc_options().max_concurrent_streams(count);
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Calls the given hook with each step of the webRTC signaling exchange as it happens:
+    /// the offer going out, the answer coming back and every ICE candidate sent or received.
+    /// This is meant for test harnesses that need to observe signaling; the hook is called
+    /// on the dial's tasks, so it should return quickly.
+    pub fn on_dial_event(mut self, hook: impl Fn(DialEvent) + Send + Sync + 'static) -> Self {
+        let webrtc_options = self
+            .take_webrtc_options()
+            .dial_event_hook(DialEventHook(Some(Arc::new(hook))));
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Exchanges webRTC session descriptions with the signaling server in the url-safe base64
+    /// alphabet rather than the standard one, for signaling peers that expect it.
+    pub fn sdp_base64_urlsafe(mut self) -> Self {
+        let webrtc_options = self.take_webrtc_options().sdp_base64_urlsafe();
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Advertises an extra local ICE candidate, in candidate string form (e.g. "candidate:1 1
+    /// udp 2130706431 203.0.113.7 50000 typ host"), to the remote peer alongside the gathered
+    /// ones, such as one for a manually configured port forward that gathering can't discover.
+    /// The candidate is only sent once gathering finishes with trickle ICE enabled; candidates
+    /// that don't parse are logged and skipped.
+    pub fn additional_local_candidate(mut self, candidate: &str) -> Self {
+        let webrtc_options = self
+            .take_webrtc_options()
+            .additional_local_candidate(candidate.to_string());
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Sets how long a webRTC connection can go without network activity before it's
+    /// considered disconnected. Defaults to the webrtc crate's 5 seconds; raising it helps
+    /// on high latency links such as satellite or cellular ones.
+    pub fn ice_disconnected_timeout(mut self, timeout: Duration) -> Self {
+        let webrtc_options = self.take_webrtc_options().ice_disconnected_timeout(timeout);
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    /// Sets how long a webRTC connection can stay disconnected before it's considered failed.
+    /// Defaults to the webrtc crate's 25 seconds.
+    pub fn ice_failed_timeout(mut self, timeout: Duration) -> Self {
+        let webrtc_options = self.take_webrtc_options().ice_failed_timeout(timeout);
+        self.config.webrtc_options = Some(webrtc_options);
+        self
+    }
+
+    // Returns any webRTC options already set on the builder, otherwise the options a
+    // connection would be made with by default.
+    fn take_webrtc_options(&mut self) -> Options {
+        self.config.webrtc_options.take().unwrap_or_default()
+    }
+
+    async fn get_addr_from_interface(
+        iface: (&str, Vec<&IpAddr>),
+        candidates: &Vec<String>,
+        expected_robot_id: Option<&str>,
+        query: MdnsQueryOptions,
+    ) -> Option<(String, RobotMdnsInfo)> {
+        // viam_mdns can only send queries from IPv4 addresses, so an interface's IPv6
+        // addresses aren't queried from; robots answering with an IPv6 address are still
+        // found through the interface's IPv4 addresses.
+        let addresses: Vec<Ipv4Addr> = iface
+            .1
+            .iter()
             .filter_map(|ip| match ip {
                 IpAddr::V4(v4) => Some(*v4),
                 IpAddr::V6(_) => None,
@@ -329,15 +1725,18 @@ impl<T: AuthMethod> DialBuilder<T> {
         let mut resp: Option<Response> = None;
         for ipv4 in addresses {
             for candidate in candidates {
-                let discovery = discover::interface_with_loopback(
-                    VIAM_MDNS_SERVICE_NAME,
-                    Duration::from_millis(250),
-                    ipv4,
-                )
-                .ok()?;
+                let interval = query.interval();
+                let discovery =
+                    discover::interface_with_loopback(VIAM_MDNS_SERVICE_NAME, interval, ipv4)
+                        .ok()?;
                 let stream = discovery.listen();
                 pin_mut!(stream);
-                while let Some(Ok(response)) = stream.next().await {
+                // the first query goes out an interval after listening starts, and answers to
+                // the last one are given an interval to arrive
+                let listen_until = tokio::time::Instant::now() + interval * (query.repeats + 1);
+                while let Ok(Some(Ok(response))) =
+                    tokio::time::timeout_at(listen_until, stream.next()).await
+                {
                     if let Some(hostname) = response.hostname() {
                         // Machine uris come in local ("my-cool-robot.abcdefg.local.viam.cloud")
                         // and non-local ("my-cool-robot.abcdefg.viam.cloud") forms. Sometimes
@@ -347,7 +1746,9 @@ impl<T: AuthMethod> DialBuilder<T> {
                         // candidates based on the actual "my-cool-robot" name without being opinionated
                         // on whether the candidate is locally named or not.
                         let local_agnostic_candidate = candidate.as_str().split("viam").next()?;
-                        if hostname.contains(local_agnostic_candidate) {
+                        if hostname.contains(local_agnostic_candidate)
+                            && txt_records_match_robot_id(response.txt_records(), expected_robot_id)
+                        {
                             resp = Some(response);
                             break;
                         }
@@ -360,25 +1761,15 @@ impl<T: AuthMethod> DialBuilder<T> {
         }
 
         let resp = resp?;
-        let mut has_grpc = false;
-        let mut has_webrtc = false;
-        for field in resp.txt_records() {
-            has_grpc = has_grpc || field.contains("grpc");
-            has_webrtc = has_webrtc || field.contains("webrtc");
-        }
+        let txt_records: Vec<&str> = resp.txt_records().collect();
+        let info = parse_txt_records(&txt_records);
 
-        let ip_addr = match resp.ip_addr() {
-            Some(std::net::IpAddr::V4(ip_v4)) => Some(ip_v4),
-            Some(std::net::IpAddr::V6(_)) | None => None,
-        };
-
-        if !(has_grpc || has_webrtc) || ip_addr.is_none() {
+        if !info.has_transport() {
             return None;
         }
-        let mut local_addr = ip_addr?.to_string();
-        local_addr.push(':');
-        local_addr.push_str(&resp.port()?.to_string());
-        Some(local_addr)
+        // formatted as a socket address so that IPv6 addresses are bracketed
+        let local_addr = SocketAddr::new(resp.ip_addr()?, resp.port()?).to_string();
+        Some((local_addr, info))
     }
 
     fn duplicate_uri(&self) -> Option<Parts> {
@@ -389,7 +1780,13 @@ impl<T: AuthMethod> DialBuilder<T> {
     }
 
     async fn get_mdns_uri(&self) -> Option<Parts> {
-        log::debug!("{}", log_prefixes::MDNS_QUERY_ATTEMPT);
+        self.get_mdns_uri_and_info().await.map(|(uri, _)| uri)
+    }
+
+    // Looks the robot up over mDNS, returning the uri to reach it at along with the metadata
+    // it advertised.
+    async fn get_mdns_uri_and_info(&self) -> Option<(Parts, RobotMdnsInfo)> {
+        dial_log!(debug, "{}", log_prefixes::MDNS_QUERY_ATTEMPT);
         if self.config.disable_mdns {
             return None;
         }
@@ -407,26 +1804,77 @@ impl<T: AuthMethod> DialBuilder<T> {
                 map
             });
 
+        let lookup_start = tokio::time::Instant::now();
         let mut iface_futures = FuturesUnordered::new();
         for iface in ifaces {
-            iface_futures.push(Self::get_addr_from_interface(iface, &candidates));
+            let interface = iface.0.to_string();
+            let lookup = Self::get_addr_from_interface(
+                iface,
+                &candidates,
+                self.config.expected_robot_id.as_deref(),
+                self.config.mdns_query,
+            );
+            iface_futures.push(async move {
+                let (addr, info) = lookup.await?;
+                Some(MdnsAnswer {
+                    interface,
+                    addr,
+                    info,
+                })
+            });
         }
 
-        let mut local_addr: Option<String> = None;
-        while let Some(maybe_addr) = iface_futures.next().await {
-            if maybe_addr.is_some() {
-                local_addr = maybe_addr;
+        let policy = &self.config.mdns_interface_policy;
+        // leave room for the selection itself within the lookup's timeout
+        let wait_until = lookup_start + MDNS_URI_TIMEOUT.saturating_sub(MDNS_SELECTION_WINDOW * 2);
+        let mut answers: Vec<MdnsAnswer> = Vec::new();
+        let mut grace_until = None;
+        loop {
+            let next = match grace_until {
+                None => iface_futures.next().await,
+                Some(until) => match tokio::time::timeout_at(until, iface_futures.next()).await {
+                    Ok(next) => next,
+                    Err(_) => break,
+                },
+            };
+            let answer = match next {
+                None => break,
+                Some(None) => continue,
+                Some(Some(answer)) => answer,
+            };
+            dial_log!(
+                debug,
+                "Robot answered mDNS on interface {} at {}",
+                answer.interface,
+                answer.addr
+            );
+            let done = match policy {
+                MdnsInterfacePolicy::FirstWins => true,
+                MdnsInterfacePolicy::PreferInterface(name) => answer.interface == *name,
+                MdnsInterfacePolicy::LowestLatency => false,
+            };
+            answers.push(answer);
+            if done {
                 break;
             }
+            grace_until.get_or_insert_with(|| {
+                (tokio::time::Instant::now() + MDNS_SELECTION_WINDOW).min(wait_until)
+            });
         }
-        let local_addr = match local_addr {
+
+        let (local_addr, info) = match select_mdns_answer(policy, answers).await {
             None => {
-                log::debug!("Unable to connect via mDNS");
+                dial_log!(debug, "Unable to connect via mDNS");
                 return None;
             }
-            Some(addr) => {
-                log::debug!("{}: {addr}", log_prefixes::MDNS_ADDRESS_FOUND);
-                addr
+            Some(answer) => {
+                dial_log!(
+                    debug,
+                    "{}: {}",
+                    log_prefixes::MDNS_ADDRESS_FOUND,
+                    answer.addr
+                );
+                (answer.addr, answer.info)
             }
         };
 
@@ -434,22 +1882,102 @@ impl<T: AuthMethod> DialBuilder<T> {
         uri.authority = Some(auth);
         uri.scheme = Some(Scheme::HTTP);
 
-        Some(uri)
+        Some((uri, info))
+    }
+
+    // Opens the channel to the robot's uri, through the endpoint the DialBuilder was created
+    // from if there is one.
+    async fn robot_channel(
+        &self,
+        domain: &str,
+        uri: Uri,
+        report: &Mutex<ConnectReport>,
+    ) -> Result<Channel> {
+        let endpoint = match &self.config.endpoint {
+            Some(endpoint) => endpoint,
+            None => {
+                let channel = Self::create_channel(
+                    self.config.may_downgrade(),
+                    domain,
+                    uri,
+                    false,
+                    self.config.lazy_connect,
+                    self.config.tls_sni.as_deref(),
+                    self.config.tls_ca_certificate.as_ref(),
+                    self.config.http2_keepalive,
+                    self.config.tcp,
+                    self.config.address_family,
+                    Some(report),
+                )
+                .await;
+                if self.config.allow_downgrade && !self.config.may_downgrade() {
+                    return channel.context(
+                        "Not downgrading to plain http, as the credentials would be sent \
+                         unencrypted; call allow_insecure_credentials to allow it",
+                    );
+                }
+                return channel;
+            }
+        };
+        if self.config.lazy_connect {
+            return Ok(endpoint.connect_lazy());
+        }
+        endpoint
+            .connect()
+            .await
+            .with_context(|| format!("Connecting to {:?}", endpoint.uri()))
+    }
+
+    // The rpc-host header to address calls to domain with, or None if it's to be left out.
+    fn rpc_host_header(&self, domain: &str) -> Result<Option<HeaderValue>> {
+        if self.config.omit_rpc_host_header {
+            return Ok(None);
+        }
+        Ok(Some(HeaderValue::from_str(domain)?))
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn create_channel(
         allow_downgrade: bool,
         domain: &str,
         uri: Uri,
         for_mdns: bool,
+        lazy: bool,
+        sni: Option<&str>,
+        ca_certificate: Option<&Certificate>,
+        keepalive: Option<(Duration, Duration)>,
+        tcp: TcpSettings,
+        family: AddressFamily,
+        report: Option<&Mutex<ConnectReport>>,
     ) -> Result<Channel> {
-        let mut chan = Channel::builder(uri.clone());
-        if for_mdns {
-            let tls_config = ClientTlsConfig::new().domain_name(domain);
+        let builder = |uri: Uri| match keepalive {
+            Some((interval, timeout)) => tcp
+                .endpoint(uri)
+                .http2_keep_alive_interval(interval)
+                .keep_alive_timeout(timeout),
+            None => tcp.endpoint(uri),
+        };
+        let mut chan = builder(uri.clone());
+        let https = uri.scheme() == Some(&Scheme::HTTPS);
+        if for_mdns || (https && (sni.is_some() || ca_certificate.is_some())) {
+            let mut tls_config = ClientTlsConfig::new();
+            if for_mdns {
+                tls_config = tls_config.domain_name(sni.unwrap_or(domain));
+            } else if let Some(sni) = sni {
+                tls_config = tls_config.domain_name(sni);
+            }
+            if let Some(ca_certificate) = ca_certificate {
+                tls_config = tls_config.ca_certificate(ca_certificate.clone());
+            }
             chan = chan.tls_config(tls_config)?;
         }
+        if lazy {
+            return Ok(chan.connect_lazy());
+        }
+        let start = Instant::now();
+        let tcp_connect = Arc::new(Mutex::new(None));
         let chan = match chan
-            .connect()
+            .connect_with_connector(timed_connector(tcp_connect.clone(), tcp, family))
             .await
             .with_context(|| format!("Connecting to {:?}", uri.clone()))
         {
@@ -459,17 +1987,219 @@ impl<T: AuthMethod> DialBuilder<T> {
                     let mut uri_parts = uri.clone().into_parts();
                     uri_parts.scheme = Some(Scheme::HTTP);
                     let uri = Uri::from_parts(uri_parts)?;
-                    Channel::builder(uri).connect().await?
+                    builder(uri)
+                        .connect_with_connector(timed_connector(tcp_connect.clone(), tcp, family))
+                        .await?
                 } else {
                     return Err(anyhow::anyhow!(e));
                 }
             }
         };
+        if let Some(report) = report {
+            let total = start.elapsed();
+            let tcp_connect = tcp_connect.lock().unwrap().unwrap_or_default();
+            let mut report = report.lock().unwrap();
+            report.tcp_connect = Some(tcp_connect);
+            report.handshake = Some(total.saturating_sub(tcp_connect));
+        }
         Ok(chan)
     }
 }
 
-impl DialBuilder<WithoutCredentials> {
+/// Connection settings that can be loaded from a configuration file (e.g. JSON or TOML)
+/// rather than set on a DialBuilder programmatically. Only the uri is required; everything
+/// else defaults to what a fresh DialBuilder would do. Timeouts are given in seconds.
+/// Deserializing it needs the `serde` feature.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct DialConfig {
+    pub uri: String,
+    pub credentials: Option<DialConfigCredentials>,
+    pub insecure: bool,
+    #[cfg_attr(feature = "serde", serde(alias = "danger_allow_insecure_credentials"))]
+    pub allow_insecure_credentials: bool,
+    pub allow_downgrade: bool,
+    pub disable_mdns: bool,
+    pub disable_webrtc: bool,
+    pub lazy_connect: bool,
+    pub expected_robot_id: Option<String>,
+    pub tls_sni: Option<String>,
+    pub signaling_server: Option<String>,
+    pub max_attempts: Option<u32>,
+    pub per_attempt_timeout_secs: Option<f64>,
+    pub connect_timeout_secs: Option<f64>,
+}
+
+/// The credentials of a [`DialConfig`], as passed to [`RPCCredentials::new`].
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct DialConfigCredentials {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub entity: Option<String>,
+    pub r#type: SecretType,
+    pub payload: String,
+}
+
+/// A DialBuilder made from a [`DialConfig`], which has credentials or not depending on the
+/// configuration.
+pub enum ConfiguredDialBuilder {
+    WithCredentials(DialBuilder<WithCredentials>),
+    WithoutCredentials(DialBuilder<WithoutCredentials>),
+}
+
+impl From<DialBuilder<WithCredentials>> for ConfiguredDialBuilder {
+    fn from(builder: DialBuilder<WithCredentials>) -> Self {
+        Self::WithCredentials(builder)
+    }
+}
+
+impl From<DialBuilder<WithoutCredentials>> for ConfiguredDialBuilder {
+    fn from(builder: DialBuilder<WithoutCredentials>) -> Self {
+        Self::WithoutCredentials(builder)
+    }
+}
+
+impl ConfiguredDialBuilder {
+    /// Reports the steps of the builder's signaling exchange; see [`DialBuilder::on_dial_event`]
+    pub fn on_dial_event(self, hook: impl Fn(DialEvent) + Send + Sync + 'static) -> Self {
+        match self {
+            Self::WithCredentials(builder) => Self::WithCredentials(builder.on_dial_event(hook)),
+            Self::WithoutCredentials(builder) => {
+                Self::WithoutCredentials(builder.on_dial_event(hook))
+            }
+        }
+    }
+
+    fn config(&self) -> &DialOptions {
+        match self {
+            Self::WithCredentials(builder) => &builder.config,
+            Self::WithoutCredentials(builder) => &builder.config,
+        }
+    }
+
+    // The authority of the uri the builder dials, if it has one.
+    fn uri(&self) -> Option<String> {
+        let uri = self.config().uri.as_ref()?;
+        uri.authority.as_ref().map(ToString::to_string)
+    }
+
+    fn webrtc_disabled(&self) -> bool {
+        let webrtc_options = self.config().webrtc_options.as_ref();
+        webrtc_options.is_some_and(|options| options.disable_webrtc)
+    }
+
+    /// Connects with the configured builder; see [`DialBuilder::connect`]
+    pub async fn connect(self) -> Result<ViamChannel> {
+        match self {
+            Self::WithCredentials(builder) => builder.connect().await,
+            Self::WithoutCredentials(builder) => builder.connect().await,
+        }
+    }
+
+    /// Connects with the configured builder; see [`DialBuilder::connect_with_report`]
+    pub async fn connect_with_report(self) -> Result<(ViamChannel, ConnectReport)> {
+        match self {
+            Self::WithCredentials(builder) => builder.connect_with_report().await,
+            Self::WithoutCredentials(builder) => builder.connect_with_report().await,
+        }
+    }
+}
+
+impl DialConfig {
+    /// Creates the DialBuilder the configuration describes, with credentials if it has any.
+    /// Fails if the uri or signaling server isn't a valid uri or a timeout isn't a valid
+    /// number of seconds.
+    pub fn into_builder(self) -> Result<ConfiguredDialBuilder> {
+        self.uri
+            .parse::<Uri>()
+            .with_context(|| format!("Invalid uri {:?}", self.uri))?;
+        if let Some(signaling_server) = &self.signaling_server {
+            signaling_server_uri(signaling_server)?;
+        }
+        let seconds = |secs: Option<f64>, name: &str| {
+            secs.map(|secs| {
+                Duration::try_from_secs_f64(secs)
+                    .with_context(|| format!("Invalid {name} of {secs} seconds"))
+            })
+            .transpose()
+        };
+        let per_attempt_timeout = seconds(self.per_attempt_timeout_secs, "per attempt timeout")?;
+        let connect_timeout = seconds(self.connect_timeout_secs, "connect timeout")?;
+
+        let builder = DialOptions::builder().uri(&self.uri);
+        Ok(match self.credentials.clone() {
+            Some(creds) => {
+                let creds = RPCCredentials::new(creds.entity, creds.r#type, creds.payload);
+                let mut builder = builder.with_credentials(creds);
+                if self.allow_insecure_credentials {
+                    builder = builder.allow_insecure_credentials();
+                }
+                ConfiguredDialBuilder::WithCredentials(self.configure(
+                    builder,
+                    per_attempt_timeout,
+                    connect_timeout,
+                ))
+            }
+            None => ConfiguredDialBuilder::WithoutCredentials(self.configure(
+                builder.without_credentials(),
+                per_attempt_timeout,
+                connect_timeout,
+            )),
+        })
+    }
+
+    fn configure<T: AuthMethod>(
+        self,
+        mut builder: DialBuilder<T>,
+        per_attempt_timeout: Option<Duration>,
+        connect_timeout: Option<Duration>,
+    ) -> DialBuilder<T> {
+        if self.insecure {
+            builder = builder.insecure();
+        }
+        if self.allow_downgrade {
+            builder = builder.allow_downgrade();
+        }
+        if self.disable_mdns {
+            builder = builder.disable_mdns();
+        }
+        if self.disable_webrtc {
+            builder = builder.disable_webrtc();
+        }
+        if self.lazy_connect {
+            builder = builder.lazy_connect();
+        }
+        if let Some(robot_id) = &self.expected_robot_id {
+            builder = builder.expected_robot_id(robot_id);
+        }
+        if let Some(server_name) = &self.tls_sni {
+            builder = builder.tls_sni(server_name);
+        }
+        if let Some(signaling_server) = &self.signaling_server {
+            builder = builder.signaling_server(signaling_server);
+        }
+        if let Some(max_attempts) = self.max_attempts {
+            builder = builder.max_attempts(max_attempts);
+        }
+        if let Some(timeout) = per_attempt_timeout {
+            builder = builder.per_attempt_timeout(timeout);
+        }
+        if let Some(timeout) = connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        builder
+    }
+}
+
+impl Clone for DialBuilder<WithoutCredentials> {
     fn clone(&self) -> Self {
         DialBuilder {
             state: WithoutCredentials(()),
@@ -478,19 +2208,36 @@ impl DialBuilder<WithoutCredentials> {
                 webrtc_options: self.config.webrtc_options.clone(),
                 uri: self.duplicate_uri(),
                 disable_mdns: self.config.disable_mdns,
+                expected_robot_id: self.config.expected_robot_id.clone(),
                 allow_downgrade: self.config.allow_downgrade,
                 insecure: self.config.insecure,
+                allow_insecure_credentials: self.config.allow_insecure_credentials,
+                lazy_connect: self.config.lazy_connect,
+                attempts: self.config.attempts,
+                tls_sni: self.config.tls_sni.clone(),
+                tls_ca_certificate: self.config.tls_ca_certificate.clone(),
+                endpoint: self.config.endpoint.clone(),
+                mdns_query: self.config.mdns_query,
+                skip_webrtc_on_local: self.config.skip_webrtc_on_local,
+                mdns_interface_policy: self.config.mdns_interface_policy.clone(),
+                http2_keepalive: self.config.http2_keepalive,
+                tcp: self.config.tcp,
+                omit_rpc_host_header: self.config.omit_rpc_host_header,
+                address_family: self.config.address_family,
             },
         }
     }
+}
 
+impl DialBuilder<WithoutCredentials> {
     /// attempts to establish a connection without credentials to the DialBuilder's given uri
     async fn connect_inner(
-        self,
+        mut self,
         mdns_uri: Option<Parts>,
         mut original_uri_parts: Parts,
+        report: Arc<Mutex<ConnectReport>>,
     ) -> Result<ViamChannel> {
-        let webrtc_options = self.config.webrtc_options;
+        let webrtc_options = self.config.webrtc_options.take();
         let disable_webrtc = match &webrtc_options {
             Some(options) => options.disable_webrtc,
             None => false,
@@ -506,82 +2253,169 @@ impl DialBuilder<WithoutCredentials> {
         let mdns_uri = mdns_uri.and_then(|p| Uri::from_parts(p).ok());
         let attempting_mdns = mdns_uri.is_some();
         if attempting_mdns {
-            log::debug!("Attempting to connect via mDNS");
+            dial_log!(debug, "Attempting to connect via mDNS");
         } else {
-            log::debug!("Attempting to connect");
+            dial_log!(debug, "Attempting to connect");
         }
 
         let channel = match mdns_uri {
-            Some(uri) => Self::create_channel(self.config.allow_downgrade, domain, uri, true).await,
+            Some(uri) => {
+                Self::create_channel(
+                    self.config.allow_downgrade,
+                    domain,
+                    uri,
+                    true,
+                    false,
+                    self.config.tls_sni.as_deref(),
+                    self.config.tls_ca_certificate.as_ref(),
+                    self.config.http2_keepalive,
+                    self.config.tcp,
+                    self.config.address_family,
+                    Some(&report),
+                )
+                .await
+            }
             // not actually an error necessarily, but we want to ensure that a channel is still
             // created with the default uri
             None => Err(anyhow::anyhow!("")),
         };
 
-        let channel = match channel {
+        let (channel, via_mdns) = match channel {
             Ok(c) => {
-                log::debug!("Connected via mDNS");
-                c
+                dial_log!(debug, "Connected via mDNS");
+                (c, true)
             }
             Err(e) => {
                 if attempting_mdns {
-                    log::debug!(
+                    dial_log!(
+                        debug,
                         "Unable to connect via mDNS; falling back to robot URI. Error: {e}"
                     );
                 }
-                Self::create_channel(self.config.allow_downgrade, domain, uri.clone(), false)
-                    .await?
+                (
+                    self.robot_channel(domain, uri.clone(), &report).await?,
+                    false,
+                )
             }
         };
         // TODO (RSDK-517) make maybe_connect_via_webrtc take a more generic type so we don't
         // need to add these dummy layers.
-        let intercepted_channel = ServiceBuilder::new()
-            .layer(AddAuthorizationLayer::basic(
-                "fake username",
-                "fake password",
-            ))
-            .layer(SetRequestHeaderLayer::overriding(
-                HeaderName::from_static("rpc-host"),
-                HeaderValue::from_str(domain)?,
-            ))
-            .service(channel.clone());
+        let rpc_host = self.rpc_host_header(domain)?;
+        let intercept = |channel: Channel| {
+            ServiceBuilder::new()
+                .layer(AddAuthorizationLayer::basic(
+                    "fake username",
+                    "fake password",
+                ))
+                .layer(SetRequestHeaderLayer::overriding(
+                    HeaderName::from_static("rpc-host"),
+                    rpc_host.clone(),
+                ))
+                .service(channel)
+        };
 
-        if disable_webrtc {
-            log::debug!("{}", log_prefixes::DIALED_GRPC);
+        if disable_webrtc || (via_mdns && self.config.skip_webrtc_on_local) {
+            dial_log!(debug, "{}", log_prefixes::DIALED_GRPC);
             Ok(ViamChannel::Direct(channel.clone()))
         } else {
-            match maybe_connect_via_webrtc(uri, intercepted_channel.clone(), webrtc_options).await {
+            let webrtc_start = Instant::now();
+            let intercepted_channel = match signaling_server_channel(
+                webrtc_options.as_ref(),
+                self.config.allow_downgrade,
+                self.config.insecure,
+                self.config.http2_keepalive,
+                self.config.tcp,
+                self.config.address_family,
+            )
+            .await
+            {
+                Ok(Some(signaling_channel)) => intercept(signaling_channel),
+                Ok(None) => intercept(channel.clone()),
+                Err(e) => {
+                    dial_log!(
+                        error,
+                        "error connecting to signaling server: {e}. Attempting to connect directly"
+                    );
+                    report.lock().unwrap().webrtc = Some(webrtc_start.elapsed());
+                    dial_log!(debug, "{}", log_prefixes::DIALED_GRPC);
+                    return Ok(ViamChannel::Direct(channel.clone()));
+                }
+            };
+            let webrtc_channel =
+                maybe_connect_via_webrtc(intercepted_channel, webrtc_options).await;
+            report.lock().unwrap().webrtc = Some(webrtc_start.elapsed());
+            match webrtc_channel {
                 Ok(webrtc_channel) => Ok(ViamChannel::WebRTC(webrtc_channel)),
                 Err(e) => {
-                    log::error!("error connecting via webrtc: {e}. Attempting to connect directly");
-                    log::debug!("{}", log_prefixes::DIALED_GRPC);
+                    dial_log!(
+                        error,
+                        "error connecting via webrtc: {e}. Attempting to connect directly"
+                    );
+                    dial_log!(debug, "{}", log_prefixes::DIALED_GRPC);
                     Ok(ViamChannel::Direct(channel.clone()))
                 }
             }
         }
     }
 
-    async fn connect_mdns(self, original_uri: Parts) -> Result<ViamChannel> {
-        let mdns_uri =
-            webrtc::action_with_timeout(self.get_mdns_uri(), Duration::from_millis(1500))
-                .await
-                .ok()
-                .flatten()
-                .ok_or(anyhow::anyhow!(
-                    "Unable to establish connection via mDNS; uri not found"
-                ))?;
+    async fn connect_mdns(
+        self,
+        original_uri: Parts,
+        report: Arc<Mutex<ConnectReport>>,
+    ) -> Result<ViamChannel> {
+        let mdns_start = Instant::now();
+        let mdns_uri = webrtc::action_with_timeout(self.get_mdns_uri(), MDNS_URI_TIMEOUT)
+            .await
+            .ok()
+            .flatten()
+            .ok_or(anyhow::anyhow!(
+                "Unable to establish connection via mDNS; uri not found"
+            ))?;
+        report.lock().unwrap().mdns = Some(mdns_start.elapsed());
 
-        self.connect_inner(Some(mdns_uri), original_uri).await
+        self.connect_inner(Some(mdns_uri), original_uri, report)
+            .await
     }
 
+    /// attempts to establish a connection without credentials to the DialBuilder's given uri.
+    /// The uri and any address found for it over mDNS are dialed concurrently and the first
+    /// connection established wins, so an mDNS lookup that finds nothing doesn't hold up
+    /// connecting to the uri. Fails without dialing if any of the options set contradict each
+    /// other, such as a signaling server along with disable_webrtc.
     pub async fn connect(self) -> Result<ViamChannel> {
-        log::debug!("{}", log_prefixes::DIAL_ATTEMPT);
+        Ok(self.connect_with_report().await?.0)
+    }
+
+    /// Like `connect`, but also reports how long each phase of the successful connection
+    /// attempt took.
+    pub async fn connect_with_report(self) -> Result<(ViamChannel, ConnectReport)> {
+        self.config.validate()?;
+        let attempts = self.config.attempts;
+        let dial_id = new_dial_id();
+        let connecting = connect_with_attempts(attempts, || self.clone().connect_once());
+        let (channel, mut report) = in_dial(Some(dial_id.clone()), connecting)
+            .await
+            .with_context(|| format!("Dial {dial_id} failed"))?;
+        report.dial_id = dial_id;
+        Ok((channel, report))
+    }
+
+    async fn connect_once(self) -> Result<(ViamChannel, ConnectReport)> {
+        let start = Instant::now();
+        dial_log!(debug, "{}", log_prefixes::DIAL_ATTEMPT);
         let original_uri = self.duplicate_uri().ok_or(anyhow::anyhow!(
             "Attempting to connect but there was no uri"
         ))?;
         let original_uri2 = duplicate_uri(&original_uri).ok_or(anyhow::anyhow!(
             "Attempting to connect but there was no uri"
         ))?;
+        let with_mdns_report = Arc::new(Mutex::new(ConnectReport::default()));
+        let without_mdns_report = Arc::new(Mutex::new(ConnectReport::default()));
+        let finish = |chan, report: &Mutex<ConnectReport>| {
+            let mut report = report.lock().unwrap().clone();
+            report.total = start.elapsed();
+            Ok((chan, report))
+        };
         // We want to short circuit and return the first `Ok` result from our connection
         // attempts, which `tokio::select!` does great. Buuuuut, we don't want to
         // abandon the `Err` results, and we want to provide comprehensive logging for
@@ -589,8 +2423,8 @@ impl DialBuilder<WithoutCredentials> {
         // the same future multiple times, while the loop lets us immediately return on the
         // first `Ok` result while still seeing and logging any error results.
         tokio::pin! {
-            let with_mdns = self.clone().connect_mdns(original_uri);
-            let without_mdns = self.connect_inner(None, original_uri2);
+            let with_mdns = self.clone().connect_mdns(original_uri, with_mdns_report.clone());
+            let without_mdns = self.connect_inner(None, original_uri2, without_mdns_report.clone());
         }
         let mut with_mdns_err: Option<anyhow::Error> = None;
         let mut without_mdns_err: Option<anyhow::Error> = None;
@@ -598,18 +2432,18 @@ impl DialBuilder<WithoutCredentials> {
             tokio::select! {
                 with_mdns = &mut with_mdns, if with_mdns_err.is_none() => {
                     match with_mdns {
-                        Ok(chan) => return Ok(chan),
+                        Ok(chan) => return finish(chan, &with_mdns_report),
                         Err(e) => {
-                            log::debug!("Error connecting with mdns: {e}");
+                            dial_log!(debug, "Error connecting with mdns: {e}");
                             with_mdns_err = Some(e);
                         }
                     }
                 }
                 without_mdns = &mut without_mdns, if without_mdns_err.is_none() => {
                     match without_mdns {
-                        Ok(chan) => return Ok(chan),
+                        Ok(chan) => return finish(chan, &without_mdns_report),
                         Err(e) => {
-                            log::debug!("Error connecting without mdns: {e}");
+                            dial_log!(debug, "Error connecting without mdns: {e}");
                             without_mdns_err = Some(e);
                         }
                     }
@@ -624,6 +2458,163 @@ impl DialBuilder<WithoutCredentials> {
     }
 }
 
+// Runs connection attempts until one of them succeeds, the attempts run out or the robot
+// rejects the dial's credentials, bounding each attempt and all of them together by the
+// configured timeouts and backing off between attempts if configured to.
+async fn connect_with_attempts<T, F, Fut>(options: AttemptOptions, mut attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let attempts = async {
+        let mut attempt_number = 1;
+        let mut backoff = options.initial_backoff;
+        loop {
+            let result = match options.per_attempt_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, attempt())
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(anyhow::anyhow!(
+                            "connection attempt timed out after {timeout:?}"
+                        ))
+                    }),
+                None => attempt().await,
+            };
+            match result {
+                Ok(connected) => return Ok(connected),
+                Err(e) if is_auth_rejection(&e) => return Err(e),
+                Err(e) if attempt_number >= options.max_attempts => {
+                    if options.max_attempts == 1 {
+                        return Err(e);
+                    }
+                    return Err(
+                        e.context(format!("Unable to connect after {attempt_number} attempts"))
+                    );
+                }
+                Err(e) => {
+                    dial_log!(
+                        debug,
+                        "Connection attempt {attempt_number} of {} failed: {e}",
+                        options.max_attempts
+                    );
+                    if let Some(wait) = backoff {
+                        dial_log!(debug, "Backing off for {wait:?} before the next attempt");
+                        tokio::time::sleep(wait).await;
+                        backoff = Some(wait.saturating_mul(2));
+                    }
+                    attempt_number += 1;
+                }
+            }
+        }
+    };
+    match options.overall_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, attempts)
+            .await
+            .map_err(|_| anyhow::anyhow!("unable to connect within {timeout:?}"))?,
+        None => attempts.await,
+    }
+}
+
+// The connector tonic would otherwise build for a channel, with the channel's TCP settings,
+// except that it records how long the most recent TCP connection took to open so the rest of
+// the channel's setup can be told apart.
+fn timed_connector(
+    elapsed: Arc<Mutex<Option<Duration>>>,
+    tcp: TcpSettings,
+    family: AddressFamily,
+) -> impl Service<
+    Uri,
+    Response = tokio::net::TcpStream,
+    Error = tower::BoxError,
+    Future = BoxFuture<tokio::net::TcpStream, tower::BoxError>,
+> + Clone {
+    timed_connector_with_resolver(elapsed, tcp, family, GaiResolver::new())
+}
+
+// Like timed_connector, but resolving hosts with resolver. Only the resolved addresses of the
+// family are attempted, and a host that is an IP address of another family is refused.
+fn timed_connector_with_resolver<R>(
+    elapsed: Arc<Mutex<Option<Duration>>>,
+    tcp: TcpSettings,
+    family: AddressFamily,
+    resolver: R,
+) -> impl Service<
+    Uri,
+    Response = tokio::net::TcpStream,
+    Error = tower::BoxError,
+    Future = BoxFuture<tokio::net::TcpStream, tower::BoxError>,
+> + Clone
+where
+    R: Service<Name> + Clone + Send + Sync + 'static,
+    R::Response: Iterator<Item = SocketAddr>,
+    R::Error: Into<tower::BoxError>,
+    R::Future: Send,
+{
+    let resolver = tower::service_fn(move |name: Name| {
+        let resolving = resolver.clone().oneshot(name.clone());
+        async move {
+            let addrs: Vec<SocketAddr> = resolving
+                .await
+                .map_err(Into::into)?
+                .filter(|addr| family.allows(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{name} has no {family:?} addresses").into());
+            }
+            Ok::<_, tower::BoxError>(addrs.into_iter())
+        }
+    });
+    let mut http = hyper::client::HttpConnector::new_with_resolver(resolver);
+    http.enforce_http(false);
+    http.set_nodelay(true);
+    http.set_keepalive(tcp.keepalive);
+    http.set_connect_timeout(tcp.connect_timeout);
+    tower::service_fn(move |uri: Uri| {
+        let mut http = http.clone();
+        let elapsed = elapsed.clone();
+        Box::pin(async move {
+            // IP addresses aren't resolved, so they're checked here instead
+            let ip = uri.host().and_then(host_ip);
+            if let Some(ip) = ip.filter(|ip| !family.allows(*ip)) {
+                return Err(format!("{ip} is not a {family:?} address").into());
+            }
+            let start = Instant::now();
+            let stream = http.call(uri).await.map_err(tower::BoxError::from)?;
+            *elapsed.lock().unwrap() = Some(start.elapsed());
+            Ok(stream)
+        }) as BoxFuture<_, _>
+    })
+}
+
+// Returns whether the error comes from the robot rejecting the dial's credentials, as opposed
+// to failing to reach the robot at all.
+fn is_auth_rejection(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        cause.downcast_ref::<Status>().is_some_and(|status| {
+            matches!(
+                status.code(),
+                tonic::Code::Unauthenticated | tonic::Code::PermissionDenied
+            )
+        })
+    })
+}
+
+// Adds the bearer token and, unless it's None, the rpc-host header to the calls made through
+// channel.
+fn authorize_channel(
+    channel: Channel,
+    token: &str,
+    rpc_host: Option<HeaderValue>,
+) -> AuthorizedChannel {
+    ServiceBuilder::new()
+        .layer(AddAuthorizationLayer::bearer(token))
+        .layer(SetRequestHeaderLayer::overriding(
+            HeaderName::from_static("rpc-host"),
+            rpc_host,
+        ))
+        .service(channel)
+}
+
 async fn get_auth_token(
     channel: &mut Channel,
     creds: Credentials,
@@ -639,7 +2630,7 @@ async fn get_auth_token(
     Ok(rsp.into_inner().access_token)
 }
 
-impl DialBuilder<WithCredentials> {
+impl Clone for DialBuilder<WithCredentials> {
     fn clone(&self) -> Self {
         DialBuilder {
             state: WithCredentials(()),
@@ -648,20 +2639,37 @@ impl DialBuilder<WithCredentials> {
                 webrtc_options: self.config.webrtc_options.clone(),
                 uri: self.duplicate_uri(),
                 disable_mdns: self.config.disable_mdns,
+                expected_robot_id: self.config.expected_robot_id.clone(),
                 allow_downgrade: self.config.allow_downgrade,
                 insecure: self.config.insecure,
+                allow_insecure_credentials: self.config.allow_insecure_credentials,
+                lazy_connect: self.config.lazy_connect,
+                attempts: self.config.attempts,
+                tls_sni: self.config.tls_sni.clone(),
+                tls_ca_certificate: self.config.tls_ca_certificate.clone(),
+                endpoint: self.config.endpoint.clone(),
+                mdns_query: self.config.mdns_query,
+                skip_webrtc_on_local: self.config.skip_webrtc_on_local,
+                mdns_interface_policy: self.config.mdns_interface_policy.clone(),
+                http2_keepalive: self.config.http2_keepalive,
+                tcp: self.config.tcp,
+                omit_rpc_host_header: self.config.omit_rpc_host_header,
+                address_family: self.config.address_family,
             },
         }
     }
+}
 
+impl DialBuilder<WithCredentials> {
     async fn connect_inner(
-        self,
+        mut self,
         mdns_uri: Option<Parts>,
         mut original_uri_parts: Parts,
+        report: Arc<Mutex<ConnectReport>>,
     ) -> Result<ViamChannel> {
         let is_insecure = self.config.insecure;
 
-        let webrtc_options = self.config.webrtc_options;
+        let webrtc_options = self.config.webrtc_options.take();
         let disable_webrtc = match &webrtc_options {
             Some(options) => options.disable_webrtc,
             None => false,
@@ -679,96 +2687,202 @@ impl DialBuilder<WithCredentials> {
         let mdns_uri = mdns_uri.and_then(|p| Uri::from_parts(p).ok());
         let attempting_mdns = mdns_uri.is_some();
 
-        let allow_downgrade = self.config.allow_downgrade;
+        let allow_downgrade = self.config.may_downgrade();
         if attempting_mdns {
-            log::debug!("Attempting to connect via mDNS");
+            dial_log!(debug, "Attempting to connect via mDNS");
         } else {
-            log::debug!("Attempting to connect");
+            dial_log!(debug, "Attempting to connect");
         }
         let channel = match mdns_uri {
-            Some(uri) => Self::create_channel(allow_downgrade, &domain, uri, true).await,
+            Some(uri) => {
+                Self::create_channel(
+                    allow_downgrade,
+                    &domain,
+                    uri,
+                    true,
+                    false,
+                    self.config.tls_sni.as_deref(),
+                    self.config.tls_ca_certificate.as_ref(),
+                    self.config.http2_keepalive,
+                    self.config.tcp,
+                    self.config.address_family,
+                    Some(&report),
+                )
+                .await
+            }
             // not actually an error necessarily, but we want to ensure that a channel is still
             // created with the default uri
             None => Err(anyhow::anyhow!("")),
         };
-        let real_channel = match channel {
+        let (real_channel, via_mdns) = match channel {
             Ok(c) => {
-                log::debug!("Connected via mDNS");
-                c
+                dial_log!(debug, "Connected via mDNS");
+                (c, true)
             }
             Err(e) => {
                 if attempting_mdns {
-                    log::debug!(
+                    dial_log!(
+                        debug,
                         "Unable to connect via mDNS; falling back to robot URI. Error: {e}"
                     );
                 }
-                Self::create_channel(allow_downgrade, &domain, uri_for_auth, false).await?
+                (
+                    self.robot_channel(&domain, uri_for_auth, &report).await?,
+                    false,
+                )
             }
         };
 
-        log::debug!("{}", log_prefixes::ACQUIRING_AUTH_TOKEN);
+        let rpc_host = self.rpc_host_header(&domain)?;
+        dial_log!(debug, "{}", log_prefixes::ACQUIRING_AUTH_TOKEN);
+        let auth_start = Instant::now();
+        let entity = self
+            .config
+            .credentials
+            .as_ref()
+            .unwrap()
+            .entity
+            .clone()
+            .unwrap_or_else(|| domain.clone());
         let token = get_auth_token(
             &mut real_channel.clone(),
-            self.config
-                .credentials
-                .as_ref()
-                .unwrap()
-                .credentials
-                .clone(),
-            self.config
-                .credentials
-                .unwrap()
-                .entity
-                .unwrap_or_else(|| domain.clone()),
+            self.config.credentials.unwrap().credentials,
+            entity.clone(),
         )
         .await?;
-        log::debug!("{}", log_prefixes::ACQUIRED_AUTH_TOKEN);
+        {
+            let mut report = report.lock().unwrap();
+            report.auth = Some(auth_start.elapsed());
+            report.authenticated_entity = Some(entity);
+        }
+        dial_log!(debug, "{}", log_prefixes::ACQUIRED_AUTH_TOKEN);
 
-        let channel = ServiceBuilder::new()
-            .layer(AddAuthorizationLayer::bearer(&token))
-            .layer(SetRequestHeaderLayer::overriding(
-                HeaderName::from_static("rpc-host"),
-                HeaderValue::from_str(domain.as_str())?,
-            ))
-            .service(real_channel);
+        let authorize = |channel: Channel| authorize_channel(channel, &token, rpc_host.clone());
+        let channel = authorize(real_channel);
 
-        if disable_webrtc {
-            log::debug!("Connected via gRPC");
-            Ok(ViamChannel::DirectPreAuthorized(channel))
+        if disable_webrtc || (via_mdns && self.config.skip_webrtc_on_local) {
+            dial_log!(debug, "Connected via gRPC");
+            Ok(ViamChannel::DirectPreAuthorized(PreAuthorizedChannel(
+                channel,
+            )))
         } else {
-            match maybe_connect_via_webrtc(original_uri, channel.clone(), webrtc_options).await {
+            let webrtc_start = Instant::now();
+            let signaling_channel = match signaling_server_channel(
+                webrtc_options.as_ref(),
+                allow_downgrade,
+                is_insecure,
+                self.config.http2_keepalive,
+                self.config.tcp,
+                self.config.address_family,
+            )
+            .await
+            {
+                Ok(Some(signaling_channel)) => authorize(signaling_channel),
+                Ok(None) => channel.clone(),
+                Err(e) => {
+                    dial_log!(error, "Unable to connect to signaling server: [{e}]. Attempting direct connection.");
+                    report.lock().unwrap().webrtc = Some(webrtc_start.elapsed());
+                    dial_log!(debug, "Connected via gRPC");
+                    return Ok(ViamChannel::DirectPreAuthorized(PreAuthorizedChannel(
+                        channel,
+                    )));
+                }
+            };
+            let webrtc_channel = maybe_connect_via_webrtc(signaling_channel, webrtc_options).await;
+            report.lock().unwrap().webrtc = Some(webrtc_start.elapsed());
+            match webrtc_channel {
                 Ok(webrtc_channel) => Ok(ViamChannel::WebRTC(webrtc_channel)),
                 Err(e) => {
-                    log::error!(
-                    "Unable to establish webrtc connection due to error: [{e}]. Attempting direct connection."
+                    dial_log!(error, "Unable to establish webrtc connection due to error: [{e}]. Attempting direct connection."
                 );
-                    log::debug!("Connected via gRPC");
-                    Ok(ViamChannel::DirectPreAuthorized(channel))
+                    dial_log!(debug, "Connected via gRPC");
+                    Ok(ViamChannel::DirectPreAuthorized(PreAuthorizedChannel(
+                        channel,
+                    )))
                 }
             }
         }
     }
 
-    async fn connect_mdns(self, original_uri: Parts) -> Result<ViamChannel> {
+    async fn connect_mdns(
+        self,
+        original_uri: Parts,
+        report: Arc<Mutex<ConnectReport>>,
+    ) -> Result<ViamChannel> {
         // NOTE(benjirewis): Use a duration of 1500ms for getting the mDNS URI. I've anecdotally
         // seen times as great as 922ms to fetch a non-loopback mDNS URI. With an
         // interface_with_loopback query interval of 250ms, 1500ms here should give us time for ~6
         // queries.
-        let mdns_uri =
-            webrtc::action_with_timeout(self.get_mdns_uri(), Duration::from_millis(1500))
-                .await
-                .ok()
-                .flatten()
-                .ok_or(anyhow::anyhow!(
-                    "Unable to establish connection via mDNS; uri not found"
-                ))?;
+        let mdns_start = Instant::now();
+        let mdns_uri = webrtc::action_with_timeout(self.get_mdns_uri(), MDNS_URI_TIMEOUT)
+            .await
+            .ok()
+            .flatten()
+            .ok_or(anyhow::anyhow!(
+                "Unable to establish connection via mDNS; uri not found"
+            ))?;
+        report.lock().unwrap().mdns = Some(mdns_start.elapsed());
+
+        self.connect_inner(Some(mdns_uri), original_uri, report)
+            .await
+    }
+
+    /// Allows the credentials to be sent over a connection without TLS, where anyone on the
+    /// network path can read them. Without this, connect refuses to dial with credentials
+    /// and `insecure` (or an endpoint using plain http), and `allow_downgrade` doesn't fall
+    /// back to plain http. Only meant for local testing or robots reached over a trusted
+    /// network, such as one on the same host.
+    pub fn allow_insecure_credentials(mut self) -> Self {
+        self.config.allow_insecure_credentials = true;
+        self
+    }
 
-        self.connect_inner(Some(mdns_uri), original_uri).await
+    /// The same as [`allow_insecure_credentials`](Self::allow_insecure_credentials), under a
+    /// name that makes the risk plain at the call site.
+    pub fn danger_allow_insecure_credentials(self) -> Self {
+        self.allow_insecure_credentials()
     }
 
-    /// attempts to establish a connection with credentials to the DialBuilder's given uri
+    /// attempts to establish a connection with credentials to the DialBuilder's given uri.
+    /// The uri and any address found for it over mDNS are dialed concurrently and the first
+    /// connection established wins, so an mDNS lookup that finds nothing doesn't hold up
+    /// connecting to the uri. Fails without dialing if any of the options set contradict each
+    /// other, such as a signaling server along with disable_webrtc.
     pub async fn connect(self) -> Result<ViamChannel> {
-        log::debug!("{}", log_prefixes::DIAL_ATTEMPT);
+        Ok(self.connect_with_report().await?.0)
+    }
+
+    /// Like `connect`, but also reports how long each phase of the successful connection
+    /// attempt took.
+    pub async fn connect_with_report(self) -> Result<(ViamChannel, ConnectReport)> {
+        self.config.validate()?;
+        let attempts = self.config.attempts;
+        let dial_id = new_dial_id();
+        let connecting = connect_with_attempts(attempts, || self.clone().connect_once());
+        let (channel, mut report) = in_dial(Some(dial_id.clone()), connecting)
+            .await
+            .with_context(|| format!("Dial {dial_id} failed"))?;
+        report.dial_id = dial_id;
+        Ok((channel, report))
+    }
+
+    /// Like `connect`, but returns a channel that remembers these options, so that it can be
+    /// reconnected with [`ReconnectableChannel::reconnect`].
+    pub async fn connect_reconnectable(self) -> Result<ReconnectableChannel> {
+        let builder = self.clone();
+        let channel = self.connect().await?;
+        Ok(ReconnectableChannel {
+            channel,
+            builder,
+            on_reconnect: None,
+            backoff: ReconnectBackoff::default(),
+            attempts: Arc::new(AtomicU32::new(0)),
+        })
+    }
+
+    async fn connect_once(self) -> Result<(ViamChannel, ConnectReport)> {
+        let start = Instant::now();
+        dial_log!(debug, "{}", log_prefixes::DIAL_ATTEMPT);
         let original_uri = self.duplicate_uri().ok_or(anyhow::anyhow!(
             "Attempting to connect but there was no uri"
         ))?;
@@ -776,6 +2890,13 @@ impl DialBuilder<WithCredentials> {
             "Attempting to connect but there was no uri"
         ))?;
 
+        let with_mdns_report = Arc::new(Mutex::new(ConnectReport::default()));
+        let without_mdns_report = Arc::new(Mutex::new(ConnectReport::default()));
+        let finish = |chan, report: &Mutex<ConnectReport>| {
+            let mut report = report.lock().unwrap().clone();
+            report.total = start.elapsed();
+            Ok((chan, report))
+        };
         // We want to short circuit and return the first `Ok` result from our connection
         // attempts, which `tokio::select!` does great. Buuuuut, we don't want to
         // abandon the `Err` results, and we want to provide comprehensive logging for
@@ -783,8 +2904,8 @@ impl DialBuilder<WithCredentials> {
         // the same future multiple times, while the loop lets us immediately return on the
         // first `Ok` result while still seeing and logging any error results.
         tokio::pin! {
-            let with_mdns = self.clone().connect_mdns(original_uri);
-            let without_mdns = self.connect_inner(None, original_uri2);
+            let with_mdns = self.clone().connect_mdns(original_uri, with_mdns_report.clone());
+            let without_mdns = self.connect_inner(None, original_uri2, without_mdns_report.clone());
         }
         let mut with_mdns_err: Option<anyhow::Error> = None;
         let mut without_mdns_err: Option<anyhow::Error> = None;
@@ -792,24 +2913,30 @@ impl DialBuilder<WithCredentials> {
             tokio::select! {
                 with_mdns = &mut with_mdns, if with_mdns_err.is_none() => {
                     match with_mdns {
-                        Ok(chan) => return Ok(chan),
+                        Ok(chan) => return finish(chan, &with_mdns_report),
                         Err(e) => {
-                            log::debug!("Error connecting with mdns: {e}");
+                            dial_log!(debug, "Error connecting with mdns: {e}");
                             with_mdns_err = Some(e);
                         }
                     }
                 }
                 without_mdns = &mut without_mdns, if without_mdns_err.is_none() => {
                     match without_mdns {
-                        Ok(chan) => return Ok(chan),
+                        Ok(chan) => return finish(chan, &without_mdns_report),
                         Err(e) => {
-                            log::debug!("Error connecting without mdns: {e}");
+                            dial_log!(debug, "Error connecting without mdns: {e}");
                             without_mdns_err = Some(e);
                         }
                     }
                 }
             }
         }
+        // keep the robot's rejection of the credentials intact so that it isn't retried
+        for err in [&mut without_mdns_err, &mut with_mdns_err] {
+            if let Some(e) = err.take_if(|e| is_auth_rejection(e)) {
+                return Err(e.context("Unable to connect: the robot rejected the credentials"));
+            }
+        }
         Err(anyhow::anyhow!(
             "Unable to connect with or without mdns.
                     with_mdns err: {with_mdns_err:?}
@@ -818,10 +2945,70 @@ impl DialBuilder<WithCredentials> {
     }
 }
 
-async fn send_done_or_error_update(
-    update: CallUpdateRequest,
-    channel: AddAuthorization<SetRequestHeader<Channel, HeaderValue>>,
-) {
+/// A step of a webRTC dial's signaling exchange, as reported to the hook set with a
+/// DialBuilder's `on_dial_event`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialEvent {
+    /// The offer, whose SDP is given, was sent to the signaling server.
+    SignalingOfferSent { sdp: String },
+    /// The robot's answer, whose SDP is given, came back from the signaling server.
+    SignalingAnswerReceived { sdp: String },
+    /// A local ICE candidate, gathered or additional, was sent to the robot.
+    CandidateSent { candidate: String },
+    /// One of the robot's ICE candidates was received and added to the peer connection.
+    CandidateReceived { candidate: String },
+}
+
+// Calls the hook set with on_dial_event, if there is one.
+#[derive(Clone, Default)]
+pub(crate) struct DialEventHook(Option<Arc<dyn Fn(DialEvent) + Send + Sync>>);
+
+impl DialEventHook {
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    // Takes the event lazily so that nothing is copied for it when there's no hook.
+    fn emit(&self, event: impl FnOnce() -> DialEvent) {
+        if let Some(hook) = &self.0 {
+            hook(event());
+        }
+    }
+}
+
+// The channel signaling RPCs are sent over, which adds any signaling specific headers.
+type SignalingChannel = InterceptedService<AuthorizedChannel, SignalingHeaders>;
+
+// Adds the configured signaling headers to every signaling request.
+#[derive(Clone)]
+struct SignalingHeaders(Arc<Vec<(MetadataKey<Ascii>, MetadataValue<Ascii>)>>);
+
+impl SignalingHeaders {
+    fn new(headers: &[(String, String)]) -> Result<Self> {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| {
+                let key = MetadataKey::from_bytes(name.as_bytes())
+                    .with_context(|| format!("Invalid signaling header name {name:?}"))?;
+                let value = MetadataValue::try_from(value.as_str())
+                    .with_context(|| format!("Invalid value for signaling header {name:?}"))?;
+                Ok((key, value))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self(Arc::new(headers)))
+    }
+}
+
+impl Interceptor for SignalingHeaders {
+    fn call(&mut self, mut request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
+        for (key, value) in self.0.iter() {
+            request.metadata_mut().insert(key.clone(), value.clone());
+        }
+        Ok(request)
+    }
+}
+
+async fn send_done_or_error_update(update: CallUpdateRequest, channel: SignalingChannel) {
     let mut signaling_client = SignalingServiceClient::new(channel.clone());
 
     if let Err(e) = signaling_client
@@ -830,7 +3017,7 @@ async fn send_done_or_error_update(
         .map_err(anyhow::Error::from)
         .map(|_| ())
     {
-        log::error!("Error sending done or error update: {e}")
+        dial_log!(error, "Error sending done or error update: {e}")
     }
 }
 
@@ -838,7 +3025,7 @@ async fn send_error_once(
     sent_error: Arc<AtomicBool>,
     uuid: &String,
     err: &anyhow::Error,
-    channel: AddAuthorization<SetRequestHeader<Channel, HeaderValue>>,
+    channel: SignalingChannel,
 ) {
     if sent_error.load(Ordering::Acquire) {
         return;
@@ -858,11 +3045,7 @@ async fn send_error_once(
     send_done_or_error_update(update_request, channel).await
 }
 
-async fn send_done_once(
-    sent_done: Arc<AtomicBool>,
-    uuid: &String,
-    channel: AddAuthorization<SetRequestHeader<Channel, HeaderValue>>,
-) {
+async fn send_done_once(sent_done: Arc<AtomicBool>, uuid: &String, channel: SignalingChannel) {
     if sent_done.load(Ordering::Acquire) {
         return;
     }
@@ -896,12 +3079,89 @@ impl fmt::Display for CallerUpdateStats {
     }
 }
 
+// Parses the signaling server given to the builder into the https uri it's dialed at.
+fn signaling_server_uri(signaling_server: &str) -> Result<Uri> {
+    let mut parts = signaling_server
+        .parse::<Uri>()
+        .with_context(|| format!("Invalid signaling server {signaling_server:?}"))?
+        .into_parts();
+    parts.scheme = Some(Scheme::HTTPS);
+    parts.path_and_query = Some(PathAndQuery::from_static(""));
+    Uri::from_parts(parts).with_context(|| format!("Invalid signaling server {signaling_server:?}"))
+}
+
+// Opens a channel to the signaling server explicitly configured in the webRTC options, if any.
+async fn signaling_server_channel(
+    webrtc_options: Option<&Options>,
+    allow_downgrade: bool,
+    insecure: bool,
+    keepalive: Option<(Duration, Duration)>,
+    tcp: TcpSettings,
+    family: AddressFamily,
+) -> Result<Option<Channel>> {
+    let signaling_server = match webrtc_options.and_then(|o| o.signaling_server.as_deref()) {
+        Some(signaling_server) => signaling_server_uri(signaling_server)?,
+        None => return Ok(None),
+    };
+    let mut parts = signaling_server.into_parts();
+    if insecure {
+        parts.scheme = Some(Scheme::HTTP);
+    }
+    let uri = Uri::from_parts(parts)?;
+    let domain = uri
+        .authority()
+        .map(Authority::to_string)
+        .unwrap_or_default();
+    dial_log!(debug, "Signaling through {uri}");
+    let channel = DialBuilder::<WithoutCredentials>::create_channel(
+        allow_downgrade,
+        &domain,
+        uri,
+        false,
+        false,
+        None,
+        None,
+        keepalive,
+        tcp,
+        family,
+        None,
+    )
+    .await?;
+    Ok(Some(channel))
+}
+
 async fn maybe_connect_via_webrtc(
-    uri: Uri,
-    channel: AddAuthorization<SetRequestHeader<Channel, HeaderValue>>,
+    channel: AuthorizedChannel,
     webrtc_options: Option<Options>,
 ) -> Result<Arc<WebRTCClientChannel>> {
-    let webrtc_options = webrtc_options.unwrap_or_else(|| Options::infer_from_uri(uri.clone()));
+    let webrtc_options = webrtc_options.unwrap_or_default();
+    let retries = webrtc_options.negotiation_retries;
+    let mut retry = 0;
+    loop {
+        match negotiate_webrtc(channel.clone(), webrtc_options.clone()).await {
+            Err(e) if retry < retries => {
+                retry += 1;
+                dial_log!(
+                    debug,
+                    "webRTC negotiation failed: {e}. Retrying on a new peer connection \
+                     ({retry} of {retries})"
+                );
+            }
+            result => return result,
+        }
+    }
+}
+
+// Makes a single attempt at negotiating a webRTC connection over the signaling channel. The
+// peer connection is closed if the attempt fails.
+async fn negotiate_webrtc(
+    channel: AuthorizedChannel,
+    webrtc_options: Options,
+) -> Result<Arc<WebRTCClientChannel>> {
+    let channel = InterceptedService::new(
+        channel,
+        SignalingHeaders::new(&webrtc_options.signaling_headers)?,
+    );
     let mut signaling_client = SignalingServiceClient::new(channel.clone());
     let response = match signaling_client
         .optional_web_rtc_config(OptionalWebRtcConfigRequest::default())
@@ -918,10 +3178,23 @@ async fn maybe_connect_via_webrtc(
     };
 
     let optional_config = response.into_inner().config;
-    let config = webrtc::extend_webrtc_config(webrtc_options.config, optional_config);
+    let config = webrtc::extend_webrtc_config(
+        webrtc_options.config,
+        optional_config,
+        &webrtc_options.ice_servers,
+    );
+    let post_open_probe = webrtc_options.post_open_probe;
+    let dial_event_hook = webrtc_options.dial_event_hook.clone();
 
-    let (peer_connection, data_channel) =
-        webrtc::new_peer_connection_for_client(config, webrtc_options.disable_trickle_ice).await?;
+    let (peer_connection, data_channel) = webrtc::new_peer_connection_for_client(
+        config,
+        webrtc_options.disable_trickle_ice,
+        webrtc_options.dtls_certificate.clone(),
+        webrtc_options.ice_disconnected_timeout,
+        webrtc_options.ice_failed_timeout,
+    )
+    .await?;
+    let mut peer_connection_guard = PeerConnectionGuard(Some(peer_connection.clone()));
 
     let sent_done_or_error = Arc::new(AtomicBool::new(false));
     let uuid_lock = Arc::new(RwLock::new("".to_string()));
@@ -943,6 +3216,8 @@ async fn maybe_connect_via_webrtc(
     let ice_done = Arc::new(tokio::sync::Notify::new());
     let ice_done2 = ice_done.clone();
     let caller_update_stats = Arc::new(Mutex::new(CallerUpdateStats::default()));
+    // the peer connection's callbacks and the signaling task run outside of the dial's task
+    let dial_id = current_dial_id();
 
     if !webrtc_options.disable_trickle_ice {
         let offer = peer_connection.create_offer(None).await?;
@@ -953,18 +3228,23 @@ async fn maybe_connect_via_webrtc(
         let exchange_done = exchange_done.clone();
 
         let on_local_ice_candidate_failure = is_open_s.clone();
+        let additional_local_candidates =
+            Arc::new(webrtc_options.additional_local_candidates.clone());
+        let dial_event_hook = dial_event_hook.clone();
 
         let caller_update_stats = caller_update_stats.clone();
         let caller_update_stats2 = caller_update_stats.clone();
+        let state_change_dial_id = dial_id.clone();
+        let candidate_dial_id = dial_id.clone();
         peer_connection.on_ice_connection_state_change(Box::new(
             move |state: RTCIceConnectionState| {
                 let caller_update_stats = caller_update_stats.clone();
-                Box::pin(async move {
+                Box::pin(in_dial(state_change_dial_id.clone(), async move {
                     if state == RTCIceConnectionState::Completed {
                         let caller_update_stats_inner = caller_update_stats.lock().unwrap();
-                        log::debug!("{}", caller_update_stats_inner);
+                        dial_log!(debug, "{}", caller_update_stats_inner);
                     }
-                })
+                }))
             },
         ));
         peer_connection.on_ice_candidate(Box::new(
@@ -979,7 +3259,9 @@ async fn maybe_connect_via_webrtc(
                 let on_local_ice_candidate_failure = on_local_ice_candidate_failure.clone();
                 let mut remote_description_set_r = remote_description_set_r.clone();
                 let caller_update_stats = caller_update_stats2.clone();
-                Box::pin(async move {
+                let additional_local_candidates = additional_local_candidates.clone();
+                let dial_event_hook = dial_event_hook.clone();
+                Box::pin(in_dial(candidate_dial_id.clone(), async move {
                     // If the value in the watch channel has not been set yet, we wait until it does.
                     // Afterwards Some(()) should be visible to all watcher and any watcher waiting  will
                     // return
@@ -993,8 +3275,7 @@ async fn maybe_connect_via_webrtc(
                                 )));
                             }
                             Err(_) => {
-                                log::info!(
-                                    "timed out on_ice_candidate; remote description was never set"
+                                dial_log!(info, "timed out on_ice_candidate; remote description was never set"
                                 );
                                 let _ = on_local_ice_candidate_failure.try_send(Some(Box::new(
                                     anyhow::anyhow!("timed out waiting for remote description"),
@@ -1017,8 +3298,7 @@ async fn maybe_connect_via_webrtc(
                     // not actually causing performance failures it's probably not worth the effort
                     // at this time.
                     if uuid.is_empty() {
-                        log::debug!(
-                            "UUID never updated. This is likely because we never received a response \
+                        dial_log!(debug, "UUID never updated. This is likely because we never received a response \
                             from the signaling client. This happens occasionally with parallel dialing \
                             and isn't concerning provided connection still occurs."
                         );
@@ -1027,30 +3307,35 @@ async fn maybe_connect_via_webrtc(
                     let mut signaling_client = SignalingServiceClient::new(channel.clone());
                     match ice_candidate {
                         Some(ice_candidate) => {
-                            log::debug!("Gathered local candidate of {ice_candidate}");
+                            dial_log!(debug, "Gathered local candidate of {ice_candidate}");
                             if sent_done_or_error.load(Ordering::Acquire) {
                                 return;
                             }
                             let proto_candidate = ice_candidate_to_proto(ice_candidate).await;
                             match proto_candidate {
                                 Ok(proto_candidate) => {
+                                    let candidate = proto_candidate.candidate.clone();
                                     let update_request = CallUpdateRequest {
                                         uuid: uuid.clone(),
                                         update: Some(Update::Candidate(proto_candidate)),
                                     };
                                     let call_update_start = Instant::now();
-                                    if let Err(e) = webrtc_action_with_timeout(
+                                    match webrtc_action_with_timeout(
                                         signaling_client.call_update(update_request),
                                     )
                                     .await
                                     .and_then(|resp| resp.map_err(anyhow::Error::from))
                                     {
-                                        log::error!("Error sending ice candidate: {e}");
-                                        let _ = on_local_ice_candidate_failure.try_send(Some(
-                                            Box::new(anyhow::anyhow!(
-                                                "Error sending ice candidate: {e}"
-                                            )),
-                                        ));
+                                        Ok(_) => dial_event_hook
+                                            .emit(|| DialEvent::CandidateSent { candidate }),
+                                        Err(e) => {
+                                            dial_log!(error, "Error sending ice candidate: {e}");
+                                            let _ = on_local_ice_candidate_failure.try_send(
+                                                Some(Box::new(anyhow::anyhow!(
+                                                    "Error sending ice candidate: {e}"
+                                                ))),
+                                            );
+                                        }
                                     }
                                     let mut caller_update_stats_inner =
                                         caller_update_stats.lock().unwrap();
@@ -1064,16 +3349,25 @@ async fn maybe_connect_via_webrtc(
                                     caller_update_stats_inner.total_duration +=
                                         call_update_duration;
                                 }
-                                Err(e) => log::error!("Error parsing ice candidate: {e}"),
+                                Err(e) => dial_log!(error, "Error parsing ice candidate: {e}"),
                             }
                         }
                         None => {
                             // will only be executed once when gathering is finished
+                            if !sent_done_or_error.load(Ordering::Acquire) {
+                                send_additional_candidates(
+                                    &additional_local_candidates,
+                                    &uuid,
+                                    channel.clone(),
+                                    &dial_event_hook,
+                                )
+                                .await;
+                            }
                             ice_done.notify_one();
                             send_done_once(sent_done_or_error, &uuid, channel.clone()).await;
                         }
                     }
-                })
+                }))
             },
         ));
 
@@ -1083,27 +3377,38 @@ async fn maybe_connect_via_webrtc(
     let local_description = peer_connection.local_description().await.unwrap();
 
     // Local SD will be multi-line, so use two log messages to indicate start, SD and end.
-    log::debug!(
+    dial_log!(
+        debug,
         "{}\n{}",
         log_prefixes::START_LOCAL_SESSION_DESCRIPTION,
         local_description.sdp
     );
-    log::debug!("{}", log_prefixes::END_LOCAL_SESSION_DESCRIPTION);
+    dial_log!(debug, "{}", log_prefixes::END_LOCAL_SESSION_DESCRIPTION);
 
-    let sdp = encode_sdp(local_description)?;
+    let sdp_base64_urlsafe = webrtc_options.sdp_base64_urlsafe;
+    let sdp = webrtc::encode_sdp_with_alphabet(&local_description, sdp_base64_urlsafe)?;
     let call_request = CallRequest {
         sdp,
         disable_trickle: webrtc_options.disable_trickle_ice,
     };
 
     let client_channel = WebRTCClientChannel::new(peer_connection, data_channel).await;
+    if let Some(bytes) = webrtc_options.max_receive_buffer {
+        client_channel.set_max_buffered_bytes(bytes);
+    }
+    if let Some(count) = webrtc_options.max_concurrent_streams {
+        client_channel.set_max_streams(count);
+    }
     let client_channel_for_ice_gathering_thread = Arc::downgrade(&client_channel);
     let mut signaling_client = SignalingServiceClient::new(channel.clone());
     let mut call_client = signaling_client.call(call_request).await?.into_inner();
+    dial_event_hook.emit(|| DialEvent::SignalingOfferSent {
+        sdp: local_description.sdp.clone(),
+    });
 
     let channel2 = channel.clone();
     let sent_done_or_error2 = sent_done_or_error.clone();
-    tokio::spawn(async move {
+    tokio::spawn(in_dial(dial_id, async move {
         let uuid = uuid_for_ice_gathering_thread;
         let client_channel = client_channel_for_ice_gathering_thread;
         let init_received = AtomicBool::new(false);
@@ -1126,7 +3431,7 @@ async fn maybe_connect_via_webrtc(
                     }
                 },
                 Err(e) => {
-                    log::error!("Error processing call response: {e}");
+                    dial_log!(error, "Error processing call response: {e}");
                     let _ = is_open_s.try_send(Some(Box::new(e)));
                     break;
                 }
@@ -1147,7 +3452,11 @@ async fn maybe_connect_via_webrtc(
                         uuid_s.clone_from(&response.uuid);
                     }
 
-                    let answer = match decode_sdp(init.sdp) {
+                    let answer = webrtc::decode_sdp_with_alphabet(&init.sdp, sdp_base64_urlsafe);
+                    let answer = match answer.and_then(|answer| {
+                        webrtc::validate_sdp(&answer)?;
+                        Ok(answer)
+                    }) {
                         Ok(a) => a,
                         Err(e) => {
                             send_error_once(
@@ -1161,6 +3470,9 @@ async fn maybe_connect_via_webrtc(
                             break;
                         }
                     };
+                    dial_event_hook.emit(|| DialEvent::SignalingAnswerReceived {
+                        sdp: answer.sdp.clone(),
+                    });
                     {
                         let cc = match client_channel.upgrade() {
                             Some(cc) => cc,
@@ -1220,7 +3532,8 @@ async fn maybe_connect_via_webrtc(
                                     break;
                                 }
                             };
-                            log::debug!("Received remote ICE candidate of {candidate:#?}");
+                            dial_log!(debug, "Received remote ICE candidate of {candidate:#?}");
+                            let candidate_string = candidate.candidate.clone();
                             if let Err(e) = client_channel
                                 .base_channel
                                 .peer_connection
@@ -1233,14 +3546,17 @@ async fn maybe_connect_via_webrtc(
                                 let _ = is_open_s.try_send(Some(Box::new(e)));
                                 break;
                             }
+                            dial_event_hook.emit(|| DialEvent::CandidateReceived {
+                                candidate: candidate_string,
+                            });
                         }
-                        Err(e) => log::error!("Error parsing ice candidate: {e}"),
+                        Err(e) => dial_log!(error, "Error parsing ice candidate: {e}"),
                     }
                 }
                 None => continue,
             }
         }
-    });
+    }));
 
     // TODO (GOUT-11): create separate authorization if external_auth_addr and/or creds.Type is `Some`
 
@@ -1256,87 +3572,2496 @@ async fn maybe_connect_via_webrtc(
             return Err(anyhow::anyhow!("Timed out opening data channel."));
         }
     }
+    if let Some(settle) = post_open_probe {
+        probe_open_channel(&client_channel, settle).await?;
+    }
 
     exchange_done.store(true, Ordering::Release);
     let uuid = uuid_lock.read().unwrap().to_string();
     send_done_once(sent_done_or_error, &uuid, channel.clone()).await;
+    peer_connection_guard.0 = None;
     Ok(client_channel)
 }
 
-async fn ice_candidate_to_proto(ice_candidate: RTCIceCandidate) -> Result<IceCandidate> {
-    let ice_candidate = ice_candidate.to_json()?;
-    Ok(IceCandidate {
-        candidate: ice_candidate.candidate,
-        sdp_mid: ice_candidate.sdp_mid,
-        sdpm_line_index: ice_candidate.sdp_mline_index.map(u32::from),
-        username_fragment: ice_candidate.username_fragment,
-    })
+// Waits for a webRTC connection whose data channel has opened to be fully established, and
+// then for it to settle, so that the first message sent on it isn't lost.
+async fn probe_open_channel(channel: &WebRTCClientChannel, settle: Duration) -> Result<()> {
+    let peer_connection = &channel.base_channel.peer_connection;
+    let data_channel = &channel.base_channel.data_channel;
+    let established = async {
+        while peer_connection.connection_state() != RTCPeerConnectionState::Connected
+            || peer_connection.sctp().state() != RTCSctpTransportState::Connected
+            || data_channel.ready_state() != RTCDataChannelState::Open
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    webrtc_action_with_timeout(established)
+        .await
+        .context("Timed out waiting for the opened data channel to be established")?;
+    tokio::time::sleep(settle).await;
+    Ok(())
 }
 
-fn ice_candidate_from_proto(proto: Option<IceCandidate>) -> Result<RTCIceCandidateInit> {
-    match proto {
-        Some(proto) => {
-            let proto_sdpm: usize = proto.sdpm_line_index().try_into()?;
-            let sdp_mline_index: Option<u16> = proto_sdpm.try_into().ok();
+// Closes a peer connection that is dropped before negotiation completes, either because
+// negotiation failed or because the dial it belongs to was cancelled.
+struct PeerConnectionGuard(Option<Arc<RTCPeerConnection>>);
 
-            Ok(RTCIceCandidateInit {
-                candidate: proto.candidate.clone(),
-                sdp_mid: Some(proto.sdp_mid().to_string()),
-                sdp_mline_index,
-                username_fragment: Some(proto.username_fragment().to_string()),
-            })
+impl Drop for PeerConnectionGuard {
+    fn drop(&mut self) {
+        let peer_connection = match self.0.take() {
+            Some(pc) => pc,
+            None => return,
+        };
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                if let Err(e) = peer_connection.close().await {
+                    dial_log!(debug, "Error closing abandoned peer connection: {e}");
+                }
+            });
         }
-        None => Err(anyhow::anyhow!("No ice candidate provided")),
     }
 }
 
-fn decode_sdp(sdp: String) -> Result<RTCSessionDescription> {
-    let sdp = String::from_utf8(base64::decode(sdp)?)?;
-    Ok(serde_json::from_str::<RTCSessionDescription>(&sdp)?)
+/// A DialGroup runs dials on behalf of a caller and keeps track of the ones still in flight,
+/// so that all of them can be aborted at once (e.g. when a tool dialing many robots shuts down).
+#[derive(Debug, Default)]
+pub struct DialGroup {
+    cancelled: Notify,
 }
 
-fn encode_sdp(sdp: RTCSessionDescription) -> Result<String> {
-    let sdp = serde_json::to_vec(&sdp)?;
-    Ok(base64::encode(sdp))
+impl DialGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the given dial (typically the future returned by a DialBuilder's `connect`) as a
+    /// member of this group. If `cancel_all` is called before the dial completes, the dial is
+    /// dropped, closing any partially established peer connection, and an error is returned.
+    pub async fn dial<F>(&self, dial: F) -> Result<ViamChannel>
+    where
+        F: Future<Output = Result<ViamChannel>>,
+    {
+        let cancelled = self.cancelled.notified();
+        tokio::pin! {
+            let cancelled = cancelled;
+            let dial = dial;
+        }
+        // Register interest before polling the dial so a cancellation issued at any point
+        // after this dial started is observed.
+        cancelled.as_mut().enable();
+
+        tokio::select! {
+            res = &mut dial => res,
+            _ = &mut cancelled => Err(anyhow::anyhow!("Dial was cancelled")),
+        }
+    }
+
+    /// Aborts every dial currently running through this group. Dials started afterwards are
+    /// unaffected.
+    pub fn cancel_all(&self) {
+        dial_log!(debug, "Cancelling all in-flight dials");
+        self.cancelled.notify_waiters();
+    }
 }
 
-fn infer_remote_uri_from_authority(uri: Uri) -> Uri {
-    let authority = uri.authority().map(Authority::as_str).unwrap_or_default();
-    let is_local_connection = authority.contains(".local.viam.cloud")
-        || authority.contains("localhost")
-        || authority.contains("0.0.0.0");
+/// Identifies what a dial connects to, and as whom, so that a [`DialCoalescer`] can tell
+/// dials that would open the same connection apart from ones that wouldn't. Credentials are
+/// only kept as a fingerprint that is keyed per process and left out of debug output.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct DialKey {
+    uri: String,
+    credentials: Option<u64>,
+}
 
-    if !is_local_connection {
-        if let Some((new_uri, _)) = Options::infer_signaling_server_address(&uri) {
-            return Uri::from_parts(uri_parts_with_defaults(&new_uri)).unwrap_or(uri);
+impl fmt::Debug for DialKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DialKey")
+            .field("uri", &self.uri)
+            .field(
+                "credentials",
+                &format_args!(
+                    "{}",
+                    match self.credentials {
+                        Some(_) => "<Supplied>",
+                        None => "<None>",
+                    }
+                ),
+            )
+            .finish()
+    }
+}
+
+impl DialKey {
+    /// Creates the key for a dial to the given uri with the given credentials, if any. Dials
+    /// share a key only if both their uris and their credentials are the same.
+    pub fn new(uri: &str, credentials: Option<&RPCCredentials>) -> Self {
+        // Randomly keyed once per process, so a fingerprint can't be checked against guessed
+        // secrets outside of it.
+        static FINGERPRINT_STATE: OnceLock<RandomState> = OnceLock::new();
+        let credentials = credentials.map(|creds| {
+            let mut hasher = FINGERPRINT_STATE
+                .get_or_init(RandomState::new)
+                .build_hasher();
+            creds.entity.hash(&mut hasher);
+            creds.credentials.r#type.hash(&mut hasher);
+            creds.credentials.payload.hash(&mut hasher);
+            hasher.finish()
+        });
+        Self {
+            uri: uri.to_string(),
+            credentials,
         }
     }
-    uri
 }
 
-fn duplicate_uri(parts: &Parts) -> Option<Parts> {
-    let uri = Uri::builder()
-        .authority(parts.authority.clone()?)
-        .path_and_query(parts.path_and_query.clone()?)
-        .scheme(parts.scheme.clone()?);
-    Some(uri.build().ok()?.into_parts())
+type SharedDial = Shared<BoxFuture<ViamChannel, Arc<anyhow::Error>>>;
+
+// A dial in flight through a DialCoalescer, along with how many callers are waiting on it.
+#[derive(Debug)]
+struct InFlightDial {
+    dial: SharedDial,
+    waiters: usize,
 }
 
-fn uri_parts_with_defaults(uri: &str) -> Parts {
-    let mut uri_parts = uri.parse::<Uri>().unwrap().into_parts();
-    uri_parts.scheme = Some(Scheme::HTTPS);
-    uri_parts.path_and_query = Some(PathAndQuery::from_static(""));
-    uri_parts
+// A caller's interest in an in-flight dial. The dial is forgotten once it completes or once
+// its last waiter goes away, whichever comes first, dropping it in the latter case.
+struct DialWaiter<'a> {
+    in_flight: &'a Mutex<HashMap<DialKey, InFlightDial>>,
+    key: DialKey,
+    dial: SharedDial,
+    completed: bool,
 }
 
-fn metadata_from_parts(parts: &http::request::Parts) -> Metadata {
-    let mut md = HashMap::new();
-    for (k, v) in parts.headers.iter() {
-        let k = k.to_string();
-        let v = Strings {
-            values: vec![HeaderValue::to_str(v).unwrap().to_string()],
+impl Drop for DialWaiter<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(entry) = in_flight.get_mut(&self.key) {
+            if entry.dial.ptr_eq(&self.dial) {
+                entry.waiters -= 1;
+                if self.completed || entry.waiters == 0 {
+                    in_flight.remove(&self.key);
+                }
+            }
+        }
+    }
+}
+
+/// A DialCoalescer lets independent parts of an application dial the same robot at once
+/// without each paying for its own handshake. While a dial for a key is in flight, further
+/// dials for that key wait on it and get a clone of its channel instead of connecting again.
+/// Once it completes, the next dial for the key connects anew.
+#[derive(Debug, Default)]
+pub struct DialCoalescer {
+    in_flight: Mutex<HashMap<DialKey, InFlightDial>>,
+}
+
+impl DialCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the given dial (typically the future returned by a DialBuilder's `connect`) for
+    /// the given key (typically the DialBuilder's `dial_key`), unless a dial for the same key
+    /// is already in flight, in which case the given dial is dropped without being polled and
+    /// the in-flight one's result is returned. A failed dial's error is shared as its message.
+    /// If every caller waiting on a dial is dropped, the dial is dropped too.
+    pub async fn dial<F>(&self, key: DialKey, dial: F) -> Result<ViamChannel>
+    where
+        F: Future<Output = Result<ViamChannel>> + Send + 'static,
+    {
+        let mut waiter = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            let entry = match in_flight.entry(key.clone()) {
+                Entry::Occupied(entry) => {
+                    dial_log!(debug, "Joining in-flight dial to {}", key.uri);
+                    entry.into_mut()
+                }
+                Entry::Vacant(entry) => {
+                    let dial: BoxFuture<ViamChannel, Arc<anyhow::Error>> =
+                        Box::pin(async move { dial.await.map_err(Arc::new) });
+                    entry.insert(InFlightDial {
+                        dial: dial.shared(),
+                        waiters: 0,
+                    })
+                }
+            };
+            entry.waiters += 1;
+            DialWaiter {
+                in_flight: &self.in_flight,
+                key,
+                dial: entry.dial.clone(),
+                completed: false,
+            }
         };
-        md.insert(k, v);
+
+        let res = waiter.dial.clone().await;
+        waiter.completed = true;
+        res.map_err(|e| anyhow::anyhow!("{e:#}"))
+    }
+}
+
+/// Everything captured by [`capture_diagnostics`] about a single dial attempt.
+#[derive(Debug, Serialize)]
+pub struct DiagnosticBundle {
+    /// The uri the options dial; empty if they have none.
+    pub uri: String,
+    pub environment: DiagnosticEnvironment,
+    pub dial: DiagnosticDial,
+    pub signaling: DiagnosticSignaling,
+    /// Average round-trip time in milliseconds of echo requests sent over the established
+    /// connection, if one was established and any of the requests got across it.
+    pub rtt_ms: Option<f64>,
+    /// The peer connection's stats report, if the connection is over webRTC.
+    pub stats: Option<::webrtc::stats::StatsReport>,
+    /// Every line the dial and its connection logged, at any level, in the order they were
+    /// logged.
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticEnvironment {
+    pub os: String,
+    pub arch: String,
+    pub rust_utils_version: String,
+    pub captured_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticDial {
+    /// One of "grpc", "grpc-preauthorized" or "webrtc"; `None` if the dial failed.
+    pub transport: Option<String>,
+    pub duration_ms: f64,
+    pub error: Option<String>,
+}
+
+/// The webRTC signaling exchange of the dial, as reported by its [`DialEvent`]s. Empty if the
+/// dial never got as far as signaling.
+#[derive(Debug, Default, Serialize)]
+pub struct DiagnosticSignaling {
+    /// The SDP of the last offer sent.
+    pub offer_sdp: Option<String>,
+    /// The SDP of the last answer received.
+    pub answer_sdp: Option<String>,
+    pub local_candidates: Vec<String>,
+    pub remote_candidates: Vec<String>,
+}
+
+impl DiagnosticSignaling {
+    fn record(&mut self, event: DialEvent) {
+        match event {
+            DialEvent::SignalingOfferSent { sdp } => self.offer_sdp = Some(sdp),
+            DialEvent::SignalingAnswerReceived { sdp } => self.answer_sdp = Some(sdp),
+            DialEvent::CandidateSent { candidate } => self.local_candidates.push(candidate),
+            DialEvent::CandidateReceived { candidate } => self.remote_candidates.push(candidate),
+        }
+    }
+}
+
+const DIAGNOSTIC_RTT_PINGS: u32 = 10;
+
+/// Dials with the given options (a DialBuilder with or without credentials) and full
+/// instrumentation, and writes a JSON bundle describing the attempt to `path`, overwriting any
+/// existing file. The bundle holds the signaling exchange, the dial's log lines and, if the
+/// dial succeeds, the connection's round-trip time and stats; it's written whether or not the
+/// dial succeeds. The log lines are recorded whatever logger is installed, if any. The
+/// connection is closed once it's been measured.
+pub async fn capture_diagnostics(
+    opts: impl Into<ConfiguredDialBuilder>,
+    path: impl AsRef<Path>,
+) -> Result<DiagnosticBundle> {
+    let environment = DiagnosticEnvironment {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        rust_utils_version: env!("CARGO_PKG_VERSION").to_string(),
+        captured_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let builder = opts.into();
+    let mut bundle = DiagnosticBundle {
+        uri: builder.uri().unwrap_or_default(),
+        environment,
+        dial: DiagnosticDial {
+            transport: None,
+            duration_ms: 0.0,
+            error: None,
+        },
+        signaling: DiagnosticSignaling::default(),
+        rtt_ms: None,
+        stats: None,
+        logs: Vec::new(),
+    };
+
+    let signaling = Arc::new(Mutex::new(DiagnosticSignaling::default()));
+    let recorded = signaling.clone();
+    // a direct dial has no signaling to record, and rejects the hook
+    let builder = if builder.webrtc_disabled() {
+        builder
+    } else {
+        builder.on_dial_event(move |event| recorded.lock().unwrap().record(event))
+    };
+    // the dial's future is large enough to overflow the stack of a debug build unboxed
+    let ((), logs) = capture_dial_logs(Box::pin(async {
+        let start = Instant::now();
+        let dial_result = builder.connect().await;
+        bundle.dial.duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        match dial_result {
+            Err(e) => bundle.dial.error = Some(format!("{e:#}")),
+            Ok(channel) => {
+                let transport = match &channel {
+                    ViamChannel::Direct(_) => "grpc",
+                    ViamChannel::DirectPreAuthorized(_) => "grpc-preauthorized",
+                    ViamChannel::WebRTC(_) => "webrtc",
+                };
+                bundle.dial.transport = Some(transport.to_string());
+                // a connection none of the pings get across is still worth a bundle
+                bundle.rtt_ms = rtt::measure_rtt(channel.clone(), DIAGNOSTIC_RTT_PINGS)
+                    .await
+                    .ok()
+                    .map(|rtt| rtt.avg.as_secs_f64() * 1000.0);
+                if let ViamChannel::WebRTC(channel) = &channel {
+                    bundle.stats = Some(channel.get_stats().await);
+                    channel.close().await;
+                }
+            }
+        }
+    }))
+    .await;
+    bundle.logs = logs;
+    bundle.signaling = std::mem::take(&mut *signaling.lock().unwrap());
+
+    let file = std::fs::File::create(path.as_ref()).with_context(|| {
+        format!(
+            "error creating diagnostics file {}",
+            path.as_ref().display()
+        )
+    })?;
+    serde_json::to_writer_pretty(file, &bundle)?;
+    Ok(bundle)
+}
+
+async fn ice_candidate_to_proto(ice_candidate: RTCIceCandidate) -> Result<IceCandidate> {
+    let ice_candidate = ice_candidate.to_json()?;
+    Ok(IceCandidate {
+        candidate: ice_candidate.candidate,
+        sdp_mid: ice_candidate.sdp_mid,
+        sdpm_line_index: ice_candidate.sdp_mline_index.map(u32::from),
+        username_fragment: ice_candidate.username_fragment,
+    })
+}
+
+// Sends manually configured local candidates to the signaling server as though they'd been
+// gathered, skipping any that don't parse.
+async fn send_additional_candidates(
+    candidates: &[String],
+    uuid: &str,
+    channel: SignalingChannel,
+    dial_event_hook: &DialEventHook,
+) {
+    let mut signaling_client = SignalingServiceClient::new(channel);
+    for candidate in candidates {
+        let proto_candidate = match additional_candidate_to_proto(candidate) {
+            Ok(proto_candidate) => proto_candidate,
+            Err(e) => {
+                dial_log!(
+                    error,
+                    "Invalid additional local candidate {candidate:?}: {e}"
+                );
+                continue;
+            }
+        };
+        dial_log!(debug, "Sending additional local candidate of {candidate}");
+        let sent = proto_candidate.candidate.clone();
+        let update_request = CallUpdateRequest {
+            uuid: uuid.to_string(),
+            update: Some(Update::Candidate(proto_candidate)),
+        };
+        match webrtc_action_with_timeout(signaling_client.call_update(update_request))
+            .await
+            .and_then(|resp| resp.map_err(anyhow::Error::from))
+        {
+            Ok(_) => dial_event_hook.emit(|| DialEvent::CandidateSent { candidate: sent }),
+            Err(e) => dial_log!(error, "Error sending additional local candidate: {e}"),
+        }
+    }
+}
+
+fn additional_candidate_to_proto(candidate: &str) -> Result<IceCandidate> {
+    let candidate = candidate.trim();
+    let candidate = candidate.strip_prefix("candidate:").unwrap_or(candidate);
+    unmarshal_candidate(candidate)?;
+    // the same media description gathered candidates are reported against
+    Ok(IceCandidate {
+        candidate: format!("candidate:{candidate}"),
+        sdp_mid: Some(String::new()),
+        sdpm_line_index: Some(0),
+        username_fragment: None,
+    })
+}
+
+fn ice_candidate_from_proto(proto: Option<IceCandidate>) -> Result<RTCIceCandidateInit> {
+    match proto {
+        Some(proto) => {
+            let proto_sdpm: usize = proto.sdpm_line_index().try_into()?;
+            let sdp_mline_index: Option<u16> = proto_sdpm.try_into().ok();
+
+            Ok(RTCIceCandidateInit {
+                candidate: proto.candidate.clone(),
+                sdp_mid: Some(proto.sdp_mid().to_string()),
+                sdp_mline_index,
+                username_fragment: Some(proto.username_fragment().to_string()),
+            })
+        }
+        None => Err(anyhow::anyhow!("No ice candidate provided")),
+    }
+}
+
+fn infer_remote_uri_from_authority(uri: Uri) -> Uri {
+    let authority = uri.authority().map(Authority::as_str).unwrap_or_default();
+    let is_local_connection = authority.contains(".local.viam.cloud")
+        || authority.contains("localhost")
+        || authority.contains("0.0.0.0")
+        || uri.host().is_some_and(is_loopback_host);
+
+    if !is_local_connection {
+        if let Some((new_uri, _)) = Options::infer_signaling_server_address(&uri) {
+            return Uri::from_parts(uri_parts_with_defaults(&new_uri)).unwrap_or(uri);
+        }
+    }
+    uri
+}
+
+// Returns the IP address host is, if it is one, IPv6 addresses being accepted with or without
+// the brackets they're written in within uris.
+fn host_ip(host: &str) -> Option<IpAddr> {
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    host.parse().ok()
+}
+
+// Returns whether host is a loopback or unspecified IP address.
+fn is_loopback_host(host: &str) -> bool {
+    host_ip(host).is_some_and(|ip| ip.is_loopback() || ip.is_unspecified())
+}
+
+// Chooses which of the addresses a robot was found at over mDNS to use, per the policy. The
+// answers are in the order they arrived in.
+async fn select_mdns_answer(
+    policy: &MdnsInterfacePolicy,
+    mut answers: Vec<MdnsAnswer>,
+) -> Option<MdnsAnswer> {
+    let index = match policy {
+        MdnsInterfacePolicy::FirstWins => 0,
+        MdnsInterfacePolicy::PreferInterface(name) => answers
+            .iter()
+            .position(|answer| answer.interface == *name)
+            .unwrap_or(0),
+        MdnsInterfacePolicy::LowestLatency if answers.len() > 1 => {
+            let mut race: FuturesUnordered<_> = answers
+                .iter()
+                .enumerate()
+                .map(|(index, answer)| async move {
+                    tokio::net::TcpStream::connect(answer.addr.as_str())
+                        .await
+                        .ok()
+                        .map(|_| index)
+                })
+                .collect();
+            let winner = tokio::time::timeout(MDNS_SELECTION_WINDOW, async {
+                while let Some(connected) = race.next().await {
+                    if connected.is_some() {
+                        return connected;
+                    }
+                }
+                None
+            })
+            .await;
+            winner.ok().flatten().unwrap_or(0)
+        }
+        MdnsInterfacePolicy::LowestLatency => 0,
+    };
+    if index < answers.len() {
+        Some(answers.swap_remove(index))
+    } else {
+        None
+    }
+}
+
+/// The options for [`probe_transports`].
+#[derive(Debug, Clone, Default)]
+pub struct ProbeOptions {
+    /// Probes the uri over plain http rather than https.
+    pub insecure: bool,
+    /// Falls back to plain http if the uri can't be reached over https.
+    pub allow_downgrade: bool,
+    /// Skips looking the robot up over mDNS.
+    pub disable_mdns: bool,
+}
+
+/// The transports a robot was found to support by [`probe_transports`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransportSupport {
+    /// Whether the robot accepts direct gRPC connections.
+    pub grpc: bool,
+    /// Whether the robot accepts webRTC connections.
+    pub webrtc: bool,
+    /// The metadata the robot advertised over mDNS, if it answered an mDNS query.
+    pub mdns: Option<RobotMdnsInfo>,
+}
+
+/// Finds out which transports the robot at the given uri supports, without establishing a
+/// connection over any of them. The robot is looked up over mDNS, whose txt records say
+/// which transports it advertises, while the uri is dialed over gRPC and asked for its
+/// optional webRTC config, which only robots serving the signaling service answer. A
+/// transport is reported as supported if either probe finds it. Fails only if neither probe
+/// reaches the robot.
+pub async fn probe_transports(uri: &str, options: ProbeOptions) -> Result<TransportSupport> {
+    let mut builder = DialOptions::builder().uri(uri).without_credentials();
+    builder.config.insecure = options.insecure;
+    builder.config.allow_downgrade = options.allow_downgrade;
+    builder.config.disable_mdns = options.disable_mdns;
+    let mut parts = builder
+        .duplicate_uri()
+        .ok_or(anyhow::anyhow!("Attempting to probe but there was no uri"))?;
+    if options.insecure {
+        parts.scheme = Some(Scheme::HTTP);
+    }
+    let original_uri = Uri::from_parts(parts)?;
+    let domain = original_uri
+        .authority()
+        .map(Authority::to_string)
+        .unwrap_or_default();
+
+    let mdns_probe = async {
+        webrtc::action_with_timeout(builder.get_mdns_uri_and_info(), MDNS_URI_TIMEOUT)
+            .await
+            .ok()
+            .flatten()
+            .map(|(_, info)| info)
+    };
+    let direct_probe = async {
+        let channel = DialBuilder::<WithoutCredentials>::create_channel(
+            options.allow_downgrade,
+            &domain,
+            infer_remote_uri_from_authority(original_uri.clone()),
+            false,
+            false,
+            None,
+            None,
+            None,
+            TcpSettings::default(),
+            AddressFamily::Dual,
+            None,
+        )
+        .await?;
+        let rpc_host = HeaderValue::from_str(&domain)?;
+        let channel = ServiceBuilder::new()
+            .layer(SetRequestHeaderLayer::overriding(
+                HeaderName::from_static("rpc-host"),
+                rpc_host,
+            ))
+            .service(channel);
+        let webrtc = match SignalingServiceClient::new(channel)
+            .optional_web_rtc_config(OptionalWebRtcConfigRequest::default())
+            .await
+        {
+            Ok(_) => true,
+            Err(e) => {
+                dial_log!(
+                    debug,
+                    "Robot did not answer for its optional webRTC config: {e}"
+                );
+                false
+            }
+        };
+        anyhow::Ok(webrtc)
+    };
+    let (mdns, direct) = tokio::join!(mdns_probe, direct_probe);
+
+    let mut support = TransportSupport::default();
+    match direct {
+        Ok(webrtc) => {
+            support.grpc = true;
+            support.webrtc = webrtc;
+        }
+        Err(e) if mdns.is_none() => {
+            return Err(e.context(format!("Unable to reach {uri} directly or over mDNS")))
+        }
+        Err(e) => dial_log!(debug, "Unable to reach {uri} directly: {e}"),
+    }
+    if let Some(info) = &mdns {
+        support.grpc |= info.grpc;
+        support.webrtc |= info.webrtc;
+    }
+    support.mdns = mdns;
+    Ok(support)
+}
+
+/// The metadata a robot advertises in the txt records of its mDNS responses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RobotMdnsInfo {
+    /// Whether the robot accepts direct gRPC connections.
+    pub grpc: bool,
+    /// Whether the robot accepts webRTC connections.
+    pub webrtc: bool,
+    /// The id of the robot, if it advertises one.
+    pub robot_id: Option<String>,
+    /// Whether the robot only accepts connections over TLS.
+    pub secure: bool,
+}
+
+impl RobotMdnsInfo {
+    /// Returns whether the robot can be connected to over any transport.
+    pub fn has_transport(&self) -> bool {
+        self.grpc || self.webrtc
+    }
+}
+
+/// Parses the txt records of an mDNS response into the metadata a robot advertises. Records
+/// are either bare flags (e.g. "grpc") or key=value pairs (e.g. "robot_id=abc123"), with keys
+/// compared case-insensitively. Records that are empty, unknown or have an invalid value are
+/// ignored, and only the first robot id is kept.
+pub fn parse_txt_records<S: AsRef<str>>(records: &[S]) -> RobotMdnsInfo {
+    let mut info = RobotMdnsInfo::default();
+    for record in records {
+        let (key, value) = match record.as_ref().split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim())),
+            None => (record.as_ref().trim(), None),
+        };
+        let flag = || match value {
+            None | Some("") => Some(true),
+            Some(value) if value.eq_ignore_ascii_case("true") || value == "1" => Some(true),
+            Some(value) if value.eq_ignore_ascii_case("false") || value == "0" => Some(false),
+            Some(_) => None,
+        };
+        if key.eq_ignore_ascii_case(MDNS_GRPC_TXT_KEY) {
+            info.grpc = flag().unwrap_or(info.grpc);
+        } else if key.eq_ignore_ascii_case(MDNS_WEBRTC_TXT_KEY) {
+            info.webrtc = flag().unwrap_or(info.webrtc);
+        } else if key.eq_ignore_ascii_case(MDNS_SECURE_TXT_KEY) {
+            info.secure = flag().unwrap_or(info.secure);
+        } else if key.eq_ignore_ascii_case(MDNS_ROBOT_ID_TXT_KEY) && info.robot_id.is_none() {
+            info.robot_id = value.filter(|id| !id.is_empty()).map(str::to_string);
+        }
+    }
+    info
+}
+
+// Returns whether an mDNS response's txt records advertise the expected robot id. Every
+// response matches if no robot id is expected.
+fn txt_records_match_robot_id<'a>(
+    txt_records: impl Iterator<Item = &'a str>,
+    expected_robot_id: Option<&str>,
+) -> bool {
+    let expected_robot_id = match expected_robot_id {
+        Some(id) => id,
+        None => return true,
+    };
+    let records: Vec<&str> = txt_records.collect();
+    parse_txt_records(&records).robot_id.as_deref() == Some(expected_robot_id)
+}
+
+/// A robot found on the local network over mDNS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredRobot {
+    /// The hostname the robot answered with.
+    pub hostname: String,
+    /// The address the robot can be reached at.
+    pub addr: SocketAddr,
+    /// The metadata the robot advertised.
+    pub info: RobotMdnsInfo,
+}
+
+impl DiscoveredRobot {
+    // Returns the robot an mDNS response describes, or None if it's missing the robot's
+    // address or advertises no transport to connect over.
+    fn from_response(response: &Response) -> Option<Self> {
+        let txt_records: Vec<&str> = response.txt_records().collect();
+        let info = parse_txt_records(&txt_records);
+        if !info.has_transport() {
+            return None;
+        }
+        Some(Self {
+            hostname: response.hostname()?.to_string(),
+            addr: SocketAddr::new(response.ip_addr()?, response.port()?),
+            info,
+        })
+    }
+}
+
+/// Looks for robots on the local network over mDNS, yielding each one as soon as it first
+/// answers rather than once the search is over, so that interactive tools can list robots as
+/// they're found. A robot answering again from the same address isn't yielded again. The
+/// stream ends once the timeout elapses.
+pub fn discover_robots_stream(timeout: Duration) -> Result<impl Stream<Item = DiscoveredRobot>> {
+    let responses =
+        discover::all_with_loopback(VIAM_MDNS_SERVICE_NAME, MDNS_QUERY_INTERVAL)?.listen();
+    let robots = responses.filter_map(|response| {
+        futures::future::ready(
+            response
+                .ok()
+                .as_ref()
+                .and_then(DiscoveredRobot::from_response),
+        )
+    });
+    Ok(discovered_until(robots, timeout))
+}
+
+// Yields each robot the first time it's discovered, until the timeout elapses.
+fn discovered_until(
+    robots: impl Stream<Item = DiscoveredRobot>,
+    timeout: Duration,
+) -> impl Stream<Item = DiscoveredRobot> {
+    let mut seen = HashSet::new();
+    robots
+        .take_until(tokio::time::sleep(timeout))
+        .filter(move |robot| {
+            futures::future::ready(seen.insert((robot.hostname.clone(), robot.addr)))
+        })
+}
+
+fn duplicate_uri(parts: &Parts) -> Option<Parts> {
+    let uri = Uri::builder()
+        .authority(parts.authority.clone()?)
+        .path_and_query(parts.path_and_query.clone()?)
+        .scheme(parts.scheme.clone()?);
+    Some(uri.build().ok()?.into_parts())
+}
+
+fn uri_parts_with_defaults(uri: &str) -> Parts {
+    let mut uri_parts = uri.parse::<Uri>().unwrap().into_parts();
+    uri_parts.scheme = Some(Scheme::HTTPS);
+    uri_parts.path_and_query = Some(PathAndQuery::from_static(""));
+    uri_parts
+}
+
+fn metadata_from_parts(parts: &http::request::Parts) -> Metadata {
+    let mut md = HashMap::new();
+    for (k, v) in parts.headers.iter() {
+        let k = k.to_string();
+        let v = Strings {
+            values: vec![HeaderValue::to_str(v).unwrap().to_string()],
+        };
+        md.insert(k, v);
+    }
+    Metadata { md }
+}
+
+// Returns the time left for a call to complete, from its grpc-timeout header or the deadline a
+// TimedChannel gave it, whichever is sooner, or None if it has neither.
+fn timeout_from_parts(parts: &http::request::Parts) -> Option<prost_types::Duration> {
+    let requested = parts
+        .headers
+        .get(GRPC_TIMEOUT_HEADER)
+        .and_then(parse_grpc_timeout);
+    let remaining = parts.extensions.get::<CallDeadline>().map(|deadline| {
+        deadline
+            .0
+            .saturating_duration_since(tokio::time::Instant::now())
+    });
+    let timeout = match (requested, remaining) {
+        (Some(requested), Some(remaining)) => requested.min(remaining),
+        (requested, remaining) => requested.or(remaining)?,
+    };
+    prost_types::Duration::try_from(timeout).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        capture_diagnostics, discovered_until, grpc_message_header_value,
+        grpc_timeout_header_value, infer_remote_uri_from_authority, is_loopback_host,
+        maybe_connect_via_webrtc, parse_grpc_timeout, parse_txt_records, select_mdns_answer,
+        timed_connector, timed_connector_with_resolver, timeout_from_parts,
+        txt_records_match_robot_id, uri_parts_with_defaults, AddressFamily, AuthorizedChannel,
+        CallDeadline, ConnectReport, DialBuilder, DialCoalescer, DialEvent, DialEventHook,
+        DialGroup, DialKey, DialOptions, DiscoveredRobot, MdnsAnswer, MdnsInterfacePolicy,
+        MdnsQueryOptions, RPCCredentials, RobotMdnsInfo, TcpSettings, ViamChannel,
+        WithoutCredentials, VIAM_MDNS_SERVICE_NAME,
+    };
+    use crate::gen::proto::rpc::examples::echo::v1::{
+        echo_service_client::EchoServiceClient, EchoRequest, EchoResponse,
+    };
+    use crate::gen::proto::rpc::webrtc::v1::request::Type as RequestType;
+    use crate::gen::proto::rpc::webrtc::v1::response::Type as ResponseType;
+    use crate::gen::proto::rpc::webrtc::v1::{
+        call_response::Stage,
+        call_update_request::Update,
+        signaling_service_server::{SignalingService, SignalingServiceServer},
+        AnswerRequest, AnswerResponse, CallRequest, CallResponse, CallResponseInitStage,
+        CallResponseUpdateStage, CallUpdateRequest, CallUpdateResponse, IceCandidate,
+        OptionalWebRtcConfigRequest, OptionalWebRtcConfigResponse, PacketMessage,
+        Request as WebRTCRequest, RequestMessage, Response as WebRTCResponse, ResponseHeaders,
+        ResponseMessage, ResponseTrailers,
+    };
+    use crate::rpc::{
+        client_channel::WebRTCClientChannel,
+        log_prefixes,
+        webrtc::{self, new_peer_connection_for_client, tests::answer_offer, Options},
+    };
+    use ::http::{header::HeaderName, HeaderValue};
+    use ::webrtc::{
+        data_channel::{
+            data_channel_init::RTCDataChannelInit, data_channel_state::RTCDataChannelState,
+            RTCDataChannel,
+        },
+        ice_transport::ice_candidate::RTCIceCandidateInit,
+        peer_connection::{
+            configuration::RTCConfiguration, peer_connection_state::RTCPeerConnectionState,
+            RTCPeerConnection,
+        },
+    };
+    use bytes::Bytes;
+    use hyper::{body::HttpBody, client::connect::dns::Name};
+    use prost::Message;
+    use std::{
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+    use tokio::sync::mpsc;
+    use tokio_stream::{
+        wrappers::{TcpListenerStream, UnboundedReceiverStream},
+        Stream, StreamExt,
+    };
+    use tonic::{
+        transport::{Body, Channel, Endpoint},
+        Request, Response, Status, Streaming,
+    };
+    use tower::{Service, ServiceBuilder, ServiceExt};
+    use tower_http::{auth::AddAuthorizationLayer, set_header::SetRequestHeaderLayer};
+
+    type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+    // A signaling server that answers calls with an in-process peer and records the
+    // candidates the caller sends it, how many calls it's had and the messages the peer
+    // receives. It can be made to fail its first few calls, or to trickle extra candidates to
+    // the caller after its answer. Its peer can also answer echo calls.
+    #[derive(Clone, Default)]
+    struct AnsweringSignaling {
+        answerer: Arc<Mutex<Option<Arc<RTCPeerConnection>>>>,
+        candidates: Arc<Mutex<Vec<String>>>,
+        calls: Arc<AtomicUsize>,
+        failed_calls: usize,
+        messages: Arc<Mutex<Vec<Bytes>>>,
+        remote_candidates: Vec<String>,
+        echo: bool,
+    }
+
+    // Serves the signaling server in-process, returning the address it's served at.
+    async fn listen_signaling(signaling: &AnsweringSignaling) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(SignalingServiceServer::new(signaling.clone()))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        address
+    }
+
+    // Serves the signaling server in-process, returning a channel to it that carries the
+    // headers the dial adds to signaling.
+    async fn serve_signaling(signaling: &AnsweringSignaling) -> AuthorizedChannel {
+        let address = listen_signaling(signaling).await;
+        let uri: ::http::Uri = format!("http://{address}").parse().unwrap();
+        ServiceBuilder::new()
+            .layer(AddAuthorizationLayer::basic(
+                "fake username",
+                "fake password",
+            ))
+            .layer(SetRequestHeaderLayer::overriding(
+                HeaderName::from_static("rpc-host"),
+                Some(HeaderValue::from_static("robot")),
+            ))
+            .service(Channel::builder(uri).connect().await.unwrap())
+    }
+
+    // Answers the last message of an echo call with the message it carries, as the echo
+    // service does.
+    async fn echo_request(data_channel: &RTCDataChannel, data: Bytes) {
+        let request = WebRTCRequest::decode(data).unwrap();
+        let message = match request.r#type {
+            Some(RequestType::Message(RequestMessage {
+                packet_message: Some(packet),
+                ..
+            })) if packet.eom && !packet.data.is_empty() => packet.data,
+            _ => return,
+        };
+        let echoed = EchoResponse {
+            message: EchoRequest::decode(message.as_slice()).unwrap().message,
+        };
+        let responses = [
+            ResponseType::Headers(ResponseHeaders::default()),
+            ResponseType::Message(ResponseMessage {
+                packet_message: Some(PacketMessage {
+                    data: echoed.encode_to_vec(),
+                    eom: true,
+                }),
+            }),
+            ResponseType::Trailers(ResponseTrailers::default()),
+        ];
+        for r#type in responses {
+            let response = WebRTCResponse {
+                stream: request.stream.clone(),
+                r#type: Some(r#type),
+            };
+            let _ = data_channel
+                .send(&Bytes::from(response.encode_to_vec()))
+                .await;
+        }
+    }
+
+    #[tonic::async_trait]
+    impl SignalingService for AnsweringSignaling {
+        type CallStream = ResponseStream<CallResponse>;
+
+        async fn call(
+            &self,
+            request: Request<CallRequest>,
+        ) -> Result<Response<Self::CallStream>, Status> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failed_calls {
+                return Err(Status::unavailable("ICE failed"));
+            }
+            let offer = webrtc::decode_sdp(&request.into_inner().sdp).unwrap();
+            let answerer = Arc::new(answer_offer(offer).await);
+            let data_channel = answerer
+                .create_data_channel(
+                    "data",
+                    Some(RTCDataChannelInit {
+                        negotiated: Some(0),
+                        ordered: Some(true),
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .unwrap();
+            let messages = self.messages.clone();
+            let sender = data_channel.clone();
+            let echo = self.echo;
+            data_channel.on_message(Box::new(move |message| {
+                messages.lock().unwrap().push(message.data.clone());
+                let sender = sender.clone();
+                Box::pin(async move {
+                    if echo {
+                        echo_request(&sender, message.data).await;
+                    }
+                })
+            }));
+            let answer = webrtc::encode_sdp(&answerer.local_description().await.unwrap()).unwrap();
+            *self.answerer.lock().unwrap() = Some(answerer);
+            let init = CallResponse {
+                uuid: "call-uuid".to_string(),
+                stage: Some(Stage::Init(CallResponseInitStage { sdp: answer })),
+            };
+            let updates = self.remote_candidates.iter().map(|candidate| CallResponse {
+                uuid: "call-uuid".to_string(),
+                stage: Some(Stage::Update(CallResponseUpdateStage {
+                    candidate: Some(IceCandidate {
+                        candidate: candidate.clone(),
+                        sdp_mid: Some("".to_string()),
+                        sdpm_line_index: Some(0),
+                        username_fragment: None,
+                    }),
+                })),
+            });
+            let responses: Vec<Result<_, Status>> =
+                std::iter::once(init).chain(updates).map(Ok).collect();
+            // keep the call open, as a real signaling server does until the caller is done
+            let responses = tokio_stream::iter(responses).chain(tokio_stream::pending());
+            Ok(Response::new(Box::pin(responses)))
+        }
+
+        async fn call_update(
+            &self,
+            request: Request<CallUpdateRequest>,
+        ) -> Result<Response<CallUpdateResponse>, Status> {
+            if let Some(Update::Candidate(candidate)) = request.into_inner().update {
+                self.candidates
+                    .lock()
+                    .unwrap()
+                    .push(candidate.candidate.clone());
+                let answerer = self.answerer.lock().unwrap().clone();
+                if let Some(answerer) = answerer {
+                    let _ = answerer
+                        .add_ice_candidate(RTCIceCandidateInit {
+                            candidate: candidate.candidate,
+                            ..Default::default()
+                        })
+                        .await;
+                }
+            }
+            Ok(Response::new(CallUpdateResponse {}))
+        }
+
+        type AnswerStream = ResponseStream<AnswerRequest>;
+
+        async fn answer(
+            &self,
+            _request: Request<Streaming<AnswerResponse>>,
+        ) -> Result<Response<Self::AnswerStream>, Status> {
+            Err(Status::unimplemented("not used by these tests"))
+        }
+
+        async fn optional_web_rtc_config(
+            &self,
+            _request: Request<OptionalWebRtcConfigRequest>,
+        ) -> Result<Response<OptionalWebRtcConfigResponse>, Status> {
+            Ok(Response::new(OptionalWebRtcConfigResponse::default()))
+        }
+    }
+
+    #[tokio::test]
+    async fn dial_coalescer_shares_in_flight_dials() {
+        let coalescer = Arc::new(DialCoalescer::new());
+        let handshakes = Arc::new(AtomicUsize::new(0));
+        let creds = RPCCredentials::new(None, "robot-location-secret".to_string(), "a".into());
+        let key = DialKey::new("https://robot.local", Some(&creds));
+        let mut dials = Vec::new();
+        for _ in 0..5 {
+            let coalescer = coalescer.clone();
+            let handshakes = handshakes.clone();
+            let key = key.clone();
+            dials.push(tokio::spawn(async move {
+                coalescer
+                    .dial(key, async move {
+                        handshakes.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+                        Ok(ViamChannel::Direct(channel))
+                    })
+                    .await
+            }));
+        }
+        for dial in dials {
+            assert!(dial.await.unwrap().is_ok());
+        }
+        assert_eq!(handshakes.load(Ordering::SeqCst), 1);
+
+        // once that dial is done, dialing again connects anew, and failures are shared too
+        let other_creds =
+            RPCCredentials::new(None, "robot-location-secret".to_string(), "b".into());
+        assert_ne!(DialKey::new("https://robot.local", Some(&other_creds)), key);
+        let failing_dial = || {
+            let handshakes = handshakes.clone();
+            async move {
+                handshakes.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Err(anyhow::anyhow!("handshake failed"))
+            }
+        };
+        let (first, second) = tokio::join!(
+            coalescer.dial(key.clone(), failing_dial()),
+            coalescer.dial(key.clone(), failing_dial()),
+        );
+        for res in [first, second] {
+            match res {
+                Ok(_) => panic!("failed dial returned a channel"),
+                Err(e) => assert_eq!(e.to_string(), "handshake failed"),
+            }
+        }
+        assert_eq!(handshakes.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn dial_coalescer_drops_dials_nobody_waits_on() {
+        let coalescer = Arc::new(DialCoalescer::new());
+        // held by every dial future, so that it's unique again once they're all dropped
+        let held = Arc::new(());
+        let waiters: Vec<_> = (0..2)
+            .map(|_| {
+                let coalescer = coalescer.clone();
+                let held = held.clone();
+                tokio::spawn(async move {
+                    let dial = async move {
+                        let _held = held;
+                        futures::future::pending().await
+                    };
+                    let res = coalescer
+                        .dial(DialKey::new("https://robot.local", None), dial)
+                        .await;
+                    res.is_ok()
+                })
+            })
+            .collect();
+
+        // give both dials a chance to start before abandoning them
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(coalescer.in_flight.lock().unwrap().len(), 1);
+        for waiter in waiters {
+            waiter.abort();
+            assert!(waiter.await.unwrap_err().is_cancelled());
+        }
+        assert!(coalescer.in_flight.lock().unwrap().is_empty());
+        assert_eq!(Arc::strong_count(&held), 1);
+    }
+
+    #[test]
+    fn dial_keys_need_a_uri_and_keep_credentials_out_of_debug() {
+        let creds = RPCCredentials::new(None, "robot-location-secret".to_string(), "a".into());
+        let key = DialOptions::builder()
+            .uri("robot.local")
+            .with_credentials(creds.clone())
+            .dial_key()
+            .unwrap();
+        assert_eq!(key, DialKey::new("https://robot.local/", Some(&creds)));
+        assert_eq!(
+            format!("{key:?}"),
+            r#"DialKey { uri: "https://robot.local/", credentials: <Supplied> }"#
+        );
+
+        assert!(DialOptions::builder()
+            .uri("/robot")
+            .without_credentials()
+            .dial_key()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn dial_group_cancels_in_flight_dials() {
+        let group = Arc::new(DialGroup::new());
+        let mut dials = Vec::new();
+        for _ in 0..5 {
+            let group = group.clone();
+            dials.push(tokio::spawn(async move {
+                group
+                    .dial(async {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        Err(anyhow::anyhow!("slow dial finished"))
+                    })
+                    .await
+            }));
+        }
+
+        // give every dial a chance to start before cancelling
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        group.cancel_all();
+
+        for dial in dials {
+            let res = tokio::time::timeout(Duration::from_secs(1), dial)
+                .await
+                .expect("dial was not cancelled promptly")
+                .unwrap();
+            match res {
+                Ok(_) => panic!("cancelled dial returned a channel"),
+                Err(e) => assert_eq!(e.to_string(), "Dial was cancelled"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn capture_diagnostics_writes_bundle_for_failed_dial() {
+        let path = std::env::temp_dir().join("capture_diagnostics_test.json");
+        let opts = DialOptions::builder()
+            .uri("127.0.0.1:1")
+            .without_credentials()
+            .insecure()
+            .disable_mdns()
+            .disable_webrtc();
+        let bundle = capture_diagnostics(opts, &path).await.unwrap();
+        let error = bundle.dial.error.clone().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        let sections = [
+            "uri",
+            "environment",
+            "dial",
+            "signaling",
+            "rtt_ms",
+            "stats",
+            "logs",
+        ];
+        for section in sections {
+            assert!(json.get(section).is_some(), "missing section {section}");
+        }
+        assert_eq!(json["uri"], "127.0.0.1:1");
+        assert_eq!(json["dial"]["error"], error);
+        assert!(json["dial"]["transport"].is_null());
+        assert!(json["signaling"]["offer_sdp"].is_null());
+
+        // the dial's own lines are captured without a logger installed, and only those
+        let dial_id = error.split_whitespace().nth(1).unwrap();
+        let attempt = format!("DEBUG [dial {dial_id}] {}", log_prefixes::DIAL_ATTEMPT);
+        assert!(bundle.logs.contains(&attempt), "{:?}", bundle.logs);
+        assert!(bundle
+            .logs
+            .iter()
+            .all(|line| line.contains(&format!("[dial {dial_id}]"))));
+    }
+
+    #[tokio::test]
+    async fn capture_diagnostics_records_signaling_and_closes_the_connection() {
+        let signaling = AnsweringSignaling {
+            echo: true,
+            ..Default::default()
+        };
+        let address = listen_signaling(&signaling).await.to_string();
+        let path = std::env::temp_dir().join("capture_diagnostics_webrtc_test.json");
+        let opts = DialOptions::builder()
+            .uri(&address)
+            .without_credentials()
+            .insecure()
+            .disable_mdns()
+            .signaling_server(&address);
+        let bundle = capture_diagnostics(opts, &path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bundle.dial.transport.as_deref(), Some("webrtc"));
+        assert!(bundle.rtt_ms.is_some());
+        assert!(bundle.stats.is_some());
+        assert!(bundle.signaling.offer_sdp.is_some());
+        assert!(bundle.signaling.answer_sdp.is_some());
+        assert!(!bundle.signaling.local_candidates.is_empty());
+        // the connection's lines are captured alongside the dial's
+        assert!(bundle
+            .logs
+            .iter()
+            .any(|line| line.contains("Closing base channel")));
+        let sent = signaling.candidates.lock().unwrap().clone();
+        assert!(bundle
+            .signaling
+            .local_candidates
+            .iter()
+            .all(|candidate| sent.contains(candidate)));
+
+        let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+        let closed = async {
+            while answerer.connection_state() == RTCPeerConnectionState::Connected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), closed)
+            .await
+            .unwrap();
+        answerer.close().await.unwrap();
+    }
+
+    #[test]
+    fn txt_records_match_only_expected_robot_id() {
+        let expected_robot = ["grpc", "webrtc", "robot_id=abc123"];
+        let other_robot = ["grpc", "webrtc", "robot_id=abc1234"];
+
+        assert!(txt_records_match_robot_id(
+            expected_robot.into_iter(),
+            Some("abc123")
+        ));
+        assert!(!txt_records_match_robot_id(
+            other_robot.into_iter(),
+            Some("abc123")
+        ));
+        assert!(!txt_records_match_robot_id(
+            ["grpc", "webrtc"].into_iter(),
+            Some("abc123")
+        ));
+
+        // without an expected id, any response is accepted
+        assert!(txt_records_match_robot_id(other_robot.into_iter(), None));
+    }
+
+    #[test]
+    fn parse_txt_records_extracts_robot_metadata() {
+        assert_eq!(
+            parse_txt_records(&["grpc", "webrtc", "robot_id=abc123", "secure=true"]),
+            RobotMdnsInfo {
+                grpc: true,
+                webrtc: true,
+                robot_id: Some("abc123".to_string()),
+                secure: true,
+            }
+        );
+        assert_eq!(
+            parse_txt_records(&["GRPC=1".to_string(), " secure = false ".to_string()]),
+            RobotMdnsInfo {
+                grpc: true,
+                ..Default::default()
+            }
+        );
+
+        // malformed and unknown entries are skipped without discarding the valid ones
+        let info = parse_txt_records(&[
+            "",
+            "=",
+            "webrtc=maybe",
+            "robot_id=",
+            "robot_id=first",
+            "robot_id=second",
+            "version=0.2.17",
+            "grpc",
+        ]);
+        assert_eq!(
+            info,
+            RobotMdnsInfo {
+                grpc: true,
+                robot_id: Some("first".to_string()),
+                ..Default::default()
+            }
+        );
+        assert!(info.has_transport());
+
+        let none: [&str; 0] = [];
+        assert!(!parse_txt_records(&none).has_transport());
+    }
+
+    #[tokio::test]
+    async fn failed_webrtc_call_reports_status_message() {
+        // the data channel never opens, so writing the request headers fails
+        let (peer_connection, data_channel) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let channel = WebRTCClientChannel::new(peer_connection, data_channel).await;
+
+        let mut service = EchoServiceClient::new(ViamChannel::WebRTC(channel));
+        let echo_request = EchoRequest {
+            message: "hi".to_string(),
+        };
+        let status = service.echo(echo_request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unknown);
+        assert!(
+            status.message().starts_with("error writing headers: "),
+            "{}",
+            status.message()
+        );
+    }
+
+    // Connects to an in-process peer through the signaling server, which records the messages
+    // the peer receives.
+    async fn connect_to_answerer(signaling: &AnsweringSignaling) -> Arc<WebRTCClientChannel> {
+        let channel = serve_signaling(signaling).await;
+        let options = Options {
+            config: RTCConfiguration::default(),
+            ..Default::default()
+        };
+        maybe_connect_via_webrtc(channel, Some(options))
+            .await
+            .unwrap()
+    }
+
+    // The requests the peer has received so far.
+    fn received_requests(signaling: &AnsweringSignaling) -> Vec<WebRTCRequest> {
+        signaling
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|message| WebRTCRequest::decode(message.clone()).unwrap())
+            .collect()
+    }
+
+    // Starts a call over the channel whose request body is fed through the returned sender.
+    fn call_with_streamed_body(
+        channel: &Arc<WebRTCClientChannel>,
+    ) -> (
+        hyper::body::Sender,
+        tokio::task::JoinHandle<http::Response<Body>>,
+    ) {
+        let (sender, body) = Body::channel();
+        let body = body
+            .map_err(|e| Status::from_error(Box::new(e)))
+            .boxed_unsync();
+        let request = http::Request::builder()
+            .uri("/proto.rpc.examples.echo.v1.EchoService/EchoBiDi")
+            .body(body)
+            .unwrap();
+        let mut channel = ViamChannel::WebRTC(channel.clone());
+        let call = tokio::spawn(async move { channel.call(request).await.unwrap() });
+        (sender, call)
+    }
+
+    // A length-prefixed gRPC message of the given size.
+    fn grpc_message(len: usize) -> Bytes {
+        let mut message = vec![0u8];
+        message.extend_from_slice(&(len as u32).to_be_bytes());
+        message.resize(5 + len, 7);
+        Bytes::from(message)
+    }
+
+    #[tokio::test]
+    async fn webrtc_request_bodies_are_sent_as_they_stream_in() {
+        let signaling = AnsweringSignaling::default();
+        let channel = connect_to_answerer(&signaling).await;
+        let (mut sender, call) = call_with_streamed_body(&channel);
+        let completed_messages = || {
+            received_requests(&signaling)
+                .into_iter()
+                .filter(|request| match &request.r#type {
+                    Some(RequestType::Message(message)) => {
+                        message.packet_message.as_ref().is_some_and(|p| p.eom)
+                    }
+                    _ => false,
+                })
+                .count()
+        };
+
+        // 8MiB in all, but each message has to reach the peer before the next but one is
+        // produced, so no more than two are ever held at once
+        let messages = 32;
+        for sent in 1..=messages {
+            sender.send_data(grpc_message(256 * 1024)).await.unwrap();
+            // the latest message is held back until the body shows whether it's the last
+            let arrived = async {
+                while completed_messages() < sent - 1 {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            };
+            tokio::time::timeout(Duration::from_secs(10), arrived)
+                .await
+                .unwrap();
+        }
+        drop(sender);
+        let finished = async {
+            while completed_messages() < messages {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), finished)
+            .await
+            .unwrap();
+        let last = received_requests(&signaling).pop().unwrap();
+        assert!(
+            matches!(last.r#type, Some(RequestType::Message(message)) if message.eos),
+            "the last message ends the stream"
+        );
+
+        call.abort();
+        channel.close().await;
+        let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+        answerer.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn webrtc_request_body_errors_fail_the_call_and_reset_the_stream() {
+        let signaling = AnsweringSignaling::default();
+        let channel = connect_to_answerer(&signaling).await;
+        let (mut sender, call) = call_with_streamed_body(&channel);
+        sender.send_data(grpc_message(1024)).await.unwrap();
+        sender.send_data(grpc_message(1024)).await.unwrap();
+        sender.abort();
+
+        let response = tokio::time::timeout(Duration::from_secs(10), call)
+            .await
+            .unwrap()
+            .unwrap();
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), tonic::Code::Unknown);
+        assert!(
+            status.message().contains("error reading request body"),
+            "{}",
+            status.message()
+        );
+
+        let reset = async {
+            loop {
+                let requests = received_requests(&signaling);
+                if let Some(request) = requests
+                    .iter()
+                    .find(|request| request.r#type == Some(RequestType::RstStream(true)))
+                {
+                    return request.clone();
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        let reset = tokio::time::timeout(Duration::from_secs(10), reset)
+            .await
+            .unwrap();
+        // the message that made it out before the error was never marked as the last
+        let requests = received_requests(&signaling);
+        let stream = requests[0].stream.clone();
+        assert_eq!(reset.stream, stream);
+        assert!(!requests.iter().any(|request| matches!(
+            &request.r#type,
+            Some(RequestType::Message(message)) if message.eos
+        )));
+
+        channel.close().await;
+        let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+        answerer.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn additional_local_candidates_are_sent_to_signaling_server() {
+        let signaling = AnsweringSignaling::default();
+        let channel = serve_signaling(&signaling).await;
+
+        let manual = "candidate:1 1 udp 2130706431 203.0.113.7 50000 typ host";
+        let options = Options {
+            config: RTCConfiguration::default(),
+            ..Default::default()
+        }
+        .additional_local_candidate(manual.to_string())
+        .additional_local_candidate("not a candidate".to_string());
+        let client_channel = maybe_connect_via_webrtc(channel, Some(options))
+            .await
+            .unwrap();
+
+        // the manual candidate goes out once gathering finishes, which can be after the data
+        // channel has opened
+        let sent = async {
+            while !signaling
+                .candidates
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|c| c == manual)
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), sent)
+            .await
+            .unwrap();
+        let candidates = signaling.candidates.lock().unwrap().clone();
+        assert!(candidates.len() > 1, "{candidates:?}");
+        assert!(!candidates.iter().any(|c| c.contains("not a candidate")));
+
+        client_channel.close().await;
+        let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+        answerer.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn signaling_events_are_reported_in_order() {
+        // a candidate nothing answers at, which the caller adds but never pairs with
+        let remote_candidate = "candidate:1 1 udp 2130706431 127.0.0.1 9 typ host".to_string();
+        let signaling = AnsweringSignaling {
+            remote_candidates: vec![remote_candidate.clone()],
+            ..Default::default()
+        };
+        let channel = serve_signaling(&signaling).await;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let options = Options {
+            config: RTCConfiguration::default(),
+            ..Default::default()
+        }
+        .dial_event_hook(DialEventHook(Some(Arc::new(move |event| {
+            recorded.lock().unwrap().push(event)
+        }))));
+
+        let client_channel = maybe_connect_via_webrtc(channel, Some(options))
+            .await
+            .unwrap();
+        client_channel.close().await;
+        let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+        answerer.close().await.unwrap();
+
+        let events = events.lock().unwrap().clone();
+        assert!(
+            matches!(events[0], DialEvent::SignalingOfferSent { .. }),
+            "{events:?}"
+        );
+        assert!(
+            matches!(events[1], DialEvent::SignalingAnswerReceived { .. }),
+            "{events:?}"
+        );
+        assert!(events[2..].iter().all(|event| matches!(
+            event,
+            DialEvent::CandidateSent { .. } | DialEvent::CandidateReceived { .. }
+        )));
+        assert!(events.contains(&DialEvent::CandidateReceived {
+            candidate: remote_candidate
+        }));
+        // candidates are only reported as sent once the signaling server has them
+        let sent: Vec<String> = events
+            .iter()
+            .filter_map(|event| match event {
+                DialEvent::CandidateSent { candidate } => Some(candidate.clone()),
+                _ => None,
+            })
+            .collect();
+        assert!(!sent.is_empty());
+        let received = signaling.candidates.lock().unwrap();
+        assert!(sent.iter().all(|candidate| received.contains(candidate)));
+    }
+
+    #[tokio::test]
+    async fn failed_negotiation_is_retried_on_a_new_peer_connection() {
+        let signaling = AnsweringSignaling {
+            failed_calls: 1,
+            ..Default::default()
+        };
+        let channel = serve_signaling(&signaling).await;
+        let options = Options {
+            config: RTCConfiguration::default(),
+            ..Default::default()
+        };
+        let err = maybe_connect_via_webrtc(channel.clone(), Some(options.clone()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("ICE failed"), "{err}");
+        assert_eq!(signaling.calls.load(Ordering::SeqCst), 1);
+
+        let signaling = AnsweringSignaling {
+            failed_calls: 1,
+            ..Default::default()
+        };
+        let channel = serve_signaling(&signaling).await;
+        let client_channel =
+            maybe_connect_via_webrtc(channel, Some(options.negotiation_retries(2)))
+                .await
+                .unwrap();
+        assert_eq!(signaling.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            client_channel.base_channel.data_channel.ready_state(),
+            RTCDataChannelState::Open
+        );
+
+        client_channel.close().await;
+        let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+        answerer.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn first_message_after_probed_open_arrives() {
+        for _ in 0..3 {
+            let signaling = AnsweringSignaling::default();
+            let channel = serve_signaling(&signaling).await;
+            let options = Options {
+                config: RTCConfiguration::default(),
+                ..Default::default()
+            }
+            .post_open_probe(Duration::from_millis(20));
+            let client_channel = maybe_connect_via_webrtc(channel, Some(options))
+                .await
+                .unwrap();
+            let base_channel = &client_channel.base_channel;
+            assert_eq!(
+                base_channel.peer_connection.connection_state(),
+                RTCPeerConnectionState::Connected
+            );
+
+            base_channel
+                .data_channel
+                .send(&Bytes::from_static(b"first"))
+                .await
+                .unwrap();
+            let arrived = async {
+                while signaling.messages.lock().unwrap().is_empty() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            };
+            tokio::time::timeout(Duration::from_secs(5), arrived)
+                .await
+                .unwrap();
+            assert_eq!(
+                signaling.messages.lock().unwrap().as_slice(),
+                [Bytes::from_static(b"first")]
+            );
+
+            client_channel.close().await;
+            let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+            answerer.close().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn skip_webrtc_on_local_skips_signaling_when_connected_via_mdns() {
+        let signaling = AnsweringSignaling::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mdns_uri = format!("https://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(SignalingServiceServer::new(signaling.clone()))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        // the robot serves plain HTTP at the address mDNS found, so the mDNS connection has
+        // to be downgraded
+        let dial = |skip_webrtc_on_local: bool| {
+            let builder = DialOptions::builder()
+                .uri("robot.local.viam.cloud")
+                .without_credentials()
+                .allow_downgrade();
+            let builder = if skip_webrtc_on_local {
+                builder.skip_webrtc_on_local()
+            } else {
+                builder
+            };
+            builder.connect_inner(
+                Some(uri_parts_with_defaults(&mdns_uri)),
+                uri_parts_with_defaults("robot.local.viam.cloud"),
+                Arc::new(Mutex::new(ConnectReport::default())),
+            )
+        };
+
+        let channel = dial(true).await.unwrap();
+        assert!(matches!(channel, ViamChannel::Direct(_)));
+        assert_eq!(signaling.calls.load(Ordering::SeqCst), 0);
+
+        // without the flag, the same connection goes on to negotiate webRTC
+        let channel = dial(false).await.unwrap();
+        assert_eq!(signaling.calls.load(Ordering::SeqCst), 1);
+        match channel {
+            ViamChannel::WebRTC(channel) => channel.close().await,
+            _ => panic!("expected a webRTC channel"),
+        }
+        let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+        answerer.close().await.unwrap();
+    }
+
+    #[test]
+    fn conflicting_options_are_refused() {
+        let builder = || {
+            DialOptions::builder()
+                .uri("robot.local")
+                .without_credentials()
+        };
+        let conflicts = [
+            (
+                builder()
+                    .disable_webrtc()
+                    .signaling_server("signaling.example.com"),
+                "disable_webrtc and signaling_server",
+            ),
+            (
+                builder()
+                    .signaling_header("route", "robot")
+                    .disable_webrtc(),
+                "disable_webrtc and signaling_header",
+            ),
+            (
+                builder()
+                    .disable_webrtc()
+                    .additional_local_candidate("candidate:1 1 udp 1 203.0.113.7 1 typ host"),
+                "disable_webrtc and additional_local_candidate",
+            ),
+            (
+                builder()
+                    .disable_webrtc()
+                    .post_open_probe(Duration::from_millis(10)),
+                "disable_webrtc and post_open_probe",
+            ),
+            (
+                builder()
+                    .disable_webrtc()
+                    .ice_disconnected_timeout(Duration::from_secs(1)),
+                "disable_webrtc and ice_disconnected_timeout",
+            ),
+            (
+                builder()
+                    .disable_webrtc()
+                    .ice_failed_timeout(Duration::from_secs(1)),
+                "disable_webrtc and ice_failed_timeout",
+            ),
+            (
+                builder().disable_webrtc().skip_webrtc_on_local(),
+                "disable_webrtc and skip_webrtc_on_local",
+            ),
+            (
+                builder().disable_mdns().expected_robot_id("robot-id"),
+                "disable_mdns and expected_robot_id",
+            ),
+            (
+                builder().disable_mdns().skip_webrtc_on_local(),
+                "disable_mdns and skip_webrtc_on_local",
+            ),
+            (
+                builder().lazy_connect().allow_downgrade(),
+                "lazy_connect and allow_downgrade",
+            ),
+            (
+                DialBuilder::from_endpoint(Endpoint::from_static("https://robot.local"))
+                    .without_credentials()
+                    .tls_sni("robot.example.com"),
+                "from_endpoint and tls_sni",
+            ),
+            (
+                DialBuilder::from_endpoint(Endpoint::from_static("https://robot.local"))
+                    .without_credentials()
+                    .allow_downgrade(),
+                "from_endpoint and allow_downgrade",
+            ),
+            (
+                DialBuilder::from_endpoint(Endpoint::from_static("https://robot.local"))
+                    .without_credentials()
+                    .http2_keepalive(Duration::from_secs(10), Duration::from_secs(5)),
+                "from_endpoint and http2_keepalive",
+            ),
+            (
+                DialBuilder::from_endpoint(Endpoint::from_static("https://robot.local"))
+                    .without_credentials()
+                    .tcp_keepalive(Duration::from_secs(30)),
+                "from_endpoint and tcp_keepalive",
+            ),
+            (
+                DialBuilder::from_endpoint(Endpoint::from_static("https://robot.local"))
+                    .without_credentials()
+                    .tcp_connect_timeout(Duration::from_secs(2)),
+                "from_endpoint and tcp_connect_timeout",
+            ),
+            (
+                DialBuilder::from_endpoint(Endpoint::from_static("https://robot.local"))
+                    .without_credentials()
+                    .tls_ca_certificate("-----BEGIN CERTIFICATE-----"),
+                "from_endpoint and tls_ca_certificate",
+            ),
+        ];
+        for (builder, expected) in conflicts {
+            let err = builder.config.validate().unwrap_err().to_string();
+            assert!(err.starts_with("Conflicting dial options: "), "{err}");
+            assert!(err.contains(expected), "{err}");
+        }
+
+        // every conflict is listed
+        let err = builder()
+            .disable_mdns()
+            .expected_robot_id("robot-id")
+            .lazy_connect()
+            .allow_downgrade()
+            .config
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("disable_mdns and expected_robot_id"), "{err}");
+        assert!(err.contains("lazy_connect and allow_downgrade"), "{err}");
+
+        let compatible = builder()
+            .disable_webrtc()
+            .disable_mdns()
+            .allow_downgrade()
+            .tls_sni("robot.example.com");
+        compatible.config.validate().unwrap();
+    }
+
+    #[tokio::test]
+    async fn connect_refuses_conflicting_options() {
+        let creds = RPCCredentials::new(None, "api-key".to_string(), "secret".to_string());
+        let err = DialOptions::builder()
+            .uri("robot.local")
+            .with_credentials(creds)
+            .disable_webrtc()
+            .signaling_server("signaling.example.com")
+            .connect()
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Conflicting dial options: disable_webrtc and signaling_server (it only applies to \
+             webRTC)"
+        );
+    }
+
+    #[tokio::test]
+    async fn connect_refuses_an_invalid_signaling_server() {
+        for signaling_server in ["not a uri", "/no/authority"] {
+            let err = DialOptions::builder()
+                .uri("robot.local")
+                .without_credentials()
+                .signaling_server(signaling_server)
+                .connect()
+                .await
+                .err()
+                .unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!("Invalid signaling server {signaling_server:?}")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn closing_a_channel_twice_is_safe() {
+        let direct = ViamChannel::Direct(
+            tonic::transport::Channel::from_static("http://localhost:8080").connect_lazy(),
+        );
+        direct.close().await.unwrap();
+        direct.close().await.unwrap();
+
+        let signaling = AnsweringSignaling::default();
+        let channel = serve_signaling(&signaling).await;
+        let options = Options {
+            config: RTCConfiguration::default(),
+            ..Default::default()
+        };
+        let client_channel = maybe_connect_via_webrtc(channel, Some(options))
+            .await
+            .unwrap();
+        let webrtc = ViamChannel::WebRTC(client_channel.clone());
+        webrtc.close().await.unwrap();
+        assert!(client_channel.base_channel.is_closed());
+        assert_eq!(
+            client_channel
+                .base_channel
+                .peer_connection
+                .connection_state(),
+            RTCPeerConnectionState::Closed
+        );
+        webrtc.close().await.unwrap();
+
+        let answerer = signaling.answerer.lock().unwrap().take().unwrap();
+        answerer.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn context_attached_to_a_channel_is_read_back() {
+        #[derive(Debug, PartialEq)]
+        struct Robot {
+            id: String,
+        }
+
+        let channel = ViamChannel::Direct(
+            tonic::transport::Channel::from_static("http://localhost:8080").connect_lazy(),
+        );
+        let channel = channel.with_context(Robot {
+            id: "robot-1".to_string(),
+        });
+        let cloned = channel.clone();
+        assert_eq!(
+            cloned.context::<Robot>(),
+            Some(&Robot {
+                id: "robot-1".to_string()
+            })
+        );
+        assert!(channel.context::<String>().is_none());
+        assert!(matches!(channel.into_channel(), ViamChannel::Direct(_)));
+    }
+
+    #[test]
+    fn supplied_webrtc_options_are_used() {
+        let turn = ::webrtc::ice_transport::ice_server::RTCIceServer {
+            urls: vec!["turn:relay.example.com:3478".to_string()],
+            username: "user".to_string(),
+            credential: "turn-secret".to_string(),
+        };
+        let description = DialOptions::builder()
+            .uri("my-robot.abc123.viam.cloud")
+            .without_credentials()
+            .webrtc_options(Options::new().with_ice_servers(vec![turn]))
+            .webrtc_negotiation_retries(1)
+            .describe();
+        assert!(
+            description.contains("supplied ICE servers: turn:relay.example.com:3478"),
+            "{description}"
+        );
+        assert!(
+            description.contains("negotiation retries: 1"),
+            "{description}"
+        );
+        assert!(!description.contains("turn-secret"), "{description}");
+    }
+
+    #[test]
+    fn http2_keepalive_is_cloned_and_described() {
+        let builder = DialOptions::builder()
+            .uri("localhost:8080")
+            .without_credentials()
+            .http2_keepalive(Duration::from_secs(10), Duration::from_secs(5));
+        assert_eq!(
+            builder.clone().config.http2_keepalive,
+            Some((Duration::from_secs(10), Duration::from_secs(5)))
+        );
+        let description = builder.describe();
+        assert!(
+            description.contains("HTTP/2 keepalive: every 10s, timing out after 5s"),
+            "{description}"
+        );
+    }
+
+    #[test]
+    fn tcp_settings_are_cloned_and_described() {
+        let builder = DialOptions::builder()
+            .uri("localhost:8080")
+            .without_credentials()
+            .tcp_keepalive(Duration::from_secs(30))
+            .tcp_connect_timeout(Duration::from_secs(2));
+        let tcp = builder.clone().config.tcp;
+        assert_eq!(tcp.keepalive, Some(Duration::from_secs(30)));
+        assert_eq!(tcp.connect_timeout, Some(Duration::from_secs(2)));
+        let description = builder.describe();
+        assert!(
+            description.contains("TCP keepalive: after 30s idle"),
+            "{description}"
+        );
+        assert!(
+            description.contains("TCP connect timeout: 2s"),
+            "{description}"
+        );
+    }
+
+    #[test]
+    fn describe_includes_inferred_settings() {
+        let creds = RPCCredentials::new(None, "api-key".to_string(), "secret".to_string());
+        let description = DialOptions::builder()
+            .uri("my-robot.abc123.viam.cloud")
+            .with_credentials(creds)
+            .connect_timeout(Duration::from_secs(5))
+            .describe();
+        assert!(
+            description
+                .contains("signaling server: https://app.viam.com:443/ (inferred from the uri)"),
+            "{description}"
+        );
+        assert!(
+            description.contains("credentials: api-key for entity my-robot.abc123.viam.cloud"),
+            "{description}"
+        );
+        assert!(description.contains("overall timeout 5s"), "{description}");
+        assert!(description.contains("mDNS: enabled"), "{description}");
+        assert!(!description.contains("secret"), "{description}");
+
+        let description = DialOptions::builder()
+            .uri("localhost:8080")
+            .without_credentials()
+            .insecure()
+            .disable_mdns()
+            .disable_webrtc()
+            .describe();
+        assert!(
+            description.contains("uri: http://localhost:8080/"),
+            "{description}"
+        );
+        assert!(description.contains("credentials: none"), "{description}");
+        assert!(
+            description.contains("HTTP/2 keepalive: none"),
+            "{description}"
+        );
+        assert!(description.contains("mDNS: disabled"), "{description}");
+        assert!(description.contains("webRTC: disabled"), "{description}");
+    }
+
+    // Encodes a dotted name as a sequence of DNS labels.
+    fn dns_name(name: &str) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        for label in name.split('.') {
+            encoded.push(label.len() as u8);
+            encoded.extend_from_slice(label.as_bytes());
+        }
+        encoded.push(0);
+        encoded
+    }
+
+    fn dns_record(name: &str, kind: u16, data: &[u8]) -> Vec<u8> {
+        let mut record = dns_name(name);
+        record.extend_from_slice(&kind.to_be_bytes());
+        record.extend_from_slice(&1u16.to_be_bytes()); // IN
+        record.extend_from_slice(&120u32.to_be_bytes()); // ttl
+        record.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        record.extend_from_slice(data);
+        record
+    }
+
+    // An mDNS response advertising a robot's gRPC and webRTC service at address.
+    fn mdns_response(instance: &str, address: SocketAddr) -> Vec<u8> {
+        let instance_name = format!("{instance}.{VIAM_MDNS_SERVICE_NAME}");
+        let host = format!("{instance}.local");
+        let mut srv = vec![0, 0, 0, 0];
+        srv.extend_from_slice(&address.port().to_be_bytes());
+        srv.extend_from_slice(&dns_name(&host));
+        let ip = match address.ip() {
+            IpAddr::V4(ip) => ip.octets(),
+            IpAddr::V6(_) => unreachable!(),
+        };
+
+        let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 4, 0, 0, 0, 0];
+        packet.extend(dns_record(
+            VIAM_MDNS_SERVICE_NAME,
+            12,
+            &dns_name(&instance_name),
+        ));
+        packet.extend(dns_record(&instance_name, 33, &srv));
+        packet.extend(dns_record(&instance_name, 16, b"\x04grpc\x06webrtc"));
+        packet.extend(dns_record(&host, 1, &ip));
+        packet
+    }
+
+    #[tokio::test]
+    async fn mdns_interface_policy_selects_between_interfaces() {
+        // the robot is found on eth0 first, at an address nothing listens on, and then on eth1
+        // at one that accepts connections
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().to_string();
+        let answers = || {
+            vec![
+                MdnsAnswer {
+                    interface: "eth0".to_string(),
+                    addr: closed.clone(),
+                    info: RobotMdnsInfo::default(),
+                },
+                MdnsAnswer {
+                    interface: "eth1".to_string(),
+                    addr: open.clone(),
+                    info: RobotMdnsInfo::default(),
+                },
+            ]
+        };
+        let selected = |policy: MdnsInterfacePolicy| {
+            let answers = answers();
+            async move {
+                select_mdns_answer(&policy, answers)
+                    .await
+                    .map(|answer| answer.interface)
+            }
+        };
+
+        assert_eq!(
+            selected(MdnsInterfacePolicy::FirstWins).await.as_deref(),
+            Some("eth0")
+        );
+        assert_eq!(
+            selected(MdnsInterfacePolicy::PreferInterface("eth1".to_string()))
+                .await
+                .as_deref(),
+            Some("eth1")
+        );
+        assert_eq!(
+            selected(MdnsInterfacePolicy::PreferInterface("wlan0".to_string()))
+                .await
+                .as_deref(),
+            Some("eth0")
+        );
+        assert_eq!(
+            selected(MdnsInterfacePolicy::LowestLatency)
+                .await
+                .as_deref(),
+            Some("eth1")
+        );
+        assert!(
+            select_mdns_answer(&MdnsInterfacePolicy::LowestLatency, vec![])
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn mdns_discovery_repeats_queries_until_answered() {
+        let iface_ip = local_ip_address::list_afinet_netifas()
+            .unwrap()
+            .into_iter()
+            .find_map(|(_, ip)| match ip {
+                IpAddr::V4(ip) if !ip.is_loopback() => Some(ip),
+                _ => None,
+            });
+        let Some(iface_ip) = iface_ip else {
+            // there is no interface to multicast on
+            return;
+        };
+
+        let multicast = Ipv4Addr::new(224, 0, 0, 251);
+        let socket = socket2::Socket::new(
+            socket2::Domain::IPV4,
+            socket2::Type::DGRAM,
+            Some(socket2::Protocol::UDP),
+        )
+        .unwrap();
+        socket.set_reuse_address(true).unwrap();
+        socket.set_reuse_port(true).unwrap();
+        socket
+            .bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, 5353)).into())
+            .unwrap();
+        socket.join_multicast_v4(&multicast, &iface_ip).unwrap();
+        socket.set_multicast_loop_v4(true).unwrap();
+        socket.set_nonblocking(true).unwrap();
+        let responder = tokio::net::UdpSocket::from_std(socket.into()).unwrap();
+
+        // answers every query but the first one it sees, as if the first answer were lost
+        let queries = Arc::new(AtomicUsize::new(0));
+        let responder_queries = queries.clone();
+        let robot_address = SocketAddr::from(([127, 0, 0, 1], 8080));
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 4096];
+            loop {
+                let (len, _) = responder.recv_from(&mut buf).await.unwrap();
+                let is_query = len > 12 && buf[2] & 0x80 == 0;
+                if is_query && responder_queries.fetch_add(1, Ordering::SeqCst) > 0 {
+                    let response = mdns_response("robot-abc", robot_address);
+                    responder
+                        .send_to(&response, (multicast, 5353))
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+
+        let query = MdnsQueryOptions {
+            repeats: 4,
+            jitter: Duration::from_millis(50),
+        };
+        let iface_addr = IpAddr::V4(iface_ip);
+        let addr = DialBuilder::<WithoutCredentials>::get_addr_from_interface(
+            ("test", vec![&iface_addr]),
+            &vec!["robot-abc".to_string()],
+            None,
+            query,
+        )
+        .await;
+        responder.abort();
+
+        assert_eq!(addr.map(|(addr, _)| addr), Some(robot_address.to_string()));
+        assert!(queries.load(Ordering::SeqCst) >= 2);
+    }
+
+    #[test]
+    fn grpc_message_header_is_percent_encoded() {
+        assert_eq!(
+            grpc_message_header_value("stream 3 closed"),
+            "stream 3 closed"
+        );
+        assert_eq!(
+            grpc_message_header_value("100% of\nbudget: caf\u{e9}"),
+            "100%25 of%0Abudget: caf%C3%A9"
+        );
+    }
+
+    #[test]
+    fn grpc_timeout_header_round_trips() {
+        let cases = [
+            (Duration::from_nanos(500), "500n"),
+            (Duration::from_millis(250), "250000u"),
+            (Duration::from_secs(30), "30000000u"),
+            (Duration::from_secs(1000), "1000000m"),
+            (Duration::from_secs(200_000), "200000S"),
+        ];
+        for (timeout, header) in cases {
+            let value = grpc_timeout_header_value(timeout);
+            assert_eq!(value, header);
+            assert_eq!(parse_grpc_timeout(&value), Some(timeout));
+        }
+
+        assert_eq!(
+            parse_grpc_timeout(&HeaderValue::from_static("2H")),
+            Some(Duration::from_secs(2 * 60 * 60))
+        );
+        for malformed in ["", "m", "10", "10x", "123456789m"] {
+            assert_eq!(
+                parse_grpc_timeout(&HeaderValue::from_static(malformed)),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn request_headers_carry_the_call_timeout() {
+        let parts = |timeout: Option<&'static str>, deadline: Option<Duration>| {
+            let mut request = http::Request::builder();
+            if let Some(timeout) = timeout {
+                request = request.header("grpc-timeout", timeout);
+            }
+            if let Some(deadline) = deadline {
+                request = request.extension(CallDeadline(tokio::time::Instant::now() + deadline));
+            }
+            request.body(()).unwrap().into_parts().0
+        };
+
+        assert_eq!(timeout_from_parts(&parts(None, None)), None);
+        let timeout = timeout_from_parts(&parts(Some("1500m"), None)).unwrap();
+        assert_eq!((timeout.seconds, timeout.nanos), (1, 500_000_000));
+
+        // the sooner of the header and the deadline wins
+        let timeout =
+            timeout_from_parts(&parts(Some("2S"), Some(Duration::from_secs(60)))).unwrap();
+        assert_eq!((timeout.seconds, timeout.nanos), (2, 0));
+        let timeout =
+            timeout_from_parts(&parts(Some("60S"), Some(Duration::from_secs(10)))).unwrap();
+        assert!(timeout.seconds == 9 || (timeout.seconds, timeout.nanos) == (10, 0));
+    }
+
+    #[tokio::test]
+    async fn discovered_robots_are_yielded_as_they_answer() {
+        let robot = |hostname: &str, port: u16| DiscoveredRobot {
+            hostname: hostname.to_string(),
+            addr: SocketAddr::from(([192, 168, 0, 10], port)),
+            info: parse_txt_records(&["grpc"]),
+        };
+        let (first, second) = (robot("first.local", 8080), robot("second.local", 8080));
+        let (answers, answered) = mpsc::unbounded_channel();
+        let robots = discovered_until(
+            UnboundedReceiverStream::new(answered),
+            Duration::from_millis(500),
+        );
+        futures::pin_mut!(robots);
+
+        // the first robot is yielded before the second has answered
+        answers.send(first.clone()).unwrap();
+        assert_eq!(robots.next().await, Some(first.clone()));
+
+        // answering again doesn't yield a robot twice, but answering from another address does
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        answers.send(first.clone()).unwrap();
+        answers.send(second.clone()).unwrap();
+        answers.send(robot("first.local", 8081)).unwrap();
+        assert_eq!(robots.next().await, Some(second));
+        assert_eq!(robots.next().await, Some(robot("first.local", 8081)));
+
+        // the stream ends at the timeout although the responder is still around
+        let started = std::time::Instant::now();
+        assert_eq!(robots.next().await, None);
+        assert!(started.elapsed() < Duration::from_millis(500));
+        answers.send(first).unwrap();
+    }
+
+    #[test]
+    fn ipv6_literal_uris_keep_their_brackets() {
+        for (uri, host, port) in [
+            ("[::1]:8080", "[::1]", 8080),
+            ("[2001:db8::1]:443", "[2001:db8::1]", 443),
+        ] {
+            let parts = uri_parts_with_defaults(uri);
+            let authority = parts.authority.clone().unwrap();
+            assert_eq!(authority.host(), host);
+            assert_eq!(authority.port_u16(), Some(port));
+            assert_eq!(
+                ::http::Uri::from_parts(parts).unwrap().to_string(),
+                format!("https://{uri}/")
+            );
+        }
+
+        let description = DialOptions::builder()
+            .uri("[::1]:8080")
+            .without_credentials()
+            .insecure()
+            .describe();
+        assert!(
+            description.contains("uri: http://[::1]:8080/"),
+            "{description}"
+        );
+    }
+
+    #[test]
+    fn ipv6_loopback_is_a_local_connection() {
+        for host in ["[::1]", "::1", "[::]", "127.0.0.1"] {
+            assert!(is_loopback_host(host), "{host}");
+        }
+        for host in ["[2001:db8::1]", "robot.local", "[::1"] {
+            assert!(!is_loopback_host(host), "{host}");
+        }
+        let uri: ::http::Uri = "https://[::1]:8080".parse().unwrap();
+        assert_eq!(infer_remote_uri_from_authority(uri.clone()), uri);
+    }
+
+    #[tokio::test]
+    async fn dials_ipv6_literal_uris() {
+        // not every host has IPv6, in which case there's nothing to dial
+        let Ok(listener) = tokio::net::TcpListener::bind("[::1]:0").await else {
+            eprintln!("skipping dials_ipv6_literal_uris: unable to bind to [::1]");
+            return;
+        };
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(SignalingServiceServer::new(AnsweringSignaling::default()))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        let channel = DialOptions::builder()
+            .uri(&address)
+            .without_credentials()
+            .insecure()
+            .disable_mdns()
+            .disable_webrtc()
+            .connect()
+            .await
+            .unwrap();
+        assert!(matches!(channel, ViamChannel::Direct(_)));
+    }
+
+    #[tokio::test]
+    async fn address_family_limits_the_addresses_attempted() {
+        let v4_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = v4_listener.local_addr().unwrap().port();
+        // not every host has IPv6, in which case there's no second family to limit
+        let Ok(_v6_listener) = tokio::net::TcpListener::bind(("::1", port)).await else {
+            eprintln!(
+                "skipping address_family_limits_the_addresses_attempted: unable to bind to [::1]"
+            );
+            return;
+        };
+        let v4: SocketAddr = ([127, 0, 0, 1], port).into();
+        let v6: SocketAddr = (Ipv6Addr::LOCALHOST, port).into();
+        // a host resolving to addresses of both families, IPv4 first
+        let dual_stack = tower::service_fn(move |_: Name| async move {
+            Ok::<_, std::io::Error>(vec![v4, v6].into_iter())
+        });
+        let connect = |family, uri: &str| {
+            let elapsed = Arc::new(Mutex::new(None));
+            let connector =
+                timed_connector_with_resolver(elapsed, TcpSettings::default(), family, dual_stack);
+            connector.oneshot(uri.parse().unwrap())
+        };
+
+        let uri = format!("http://dual-stack.test:{port}");
+        for (family, expected) in [
+            (AddressFamily::V4, v4),
+            (AddressFamily::V6, v6),
+            (AddressFamily::Dual, v4),
+        ] {
+            let stream = connect(family, &uri).await.unwrap();
+            assert_eq!(stream.peer_addr().unwrap(), expected, "{family:?}");
+        }
+
+        let err = connect(AddressFamily::V6, &format!("http://127.0.0.1:{port}"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not a V6 address"), "{err}");
+        let err = connect(AddressFamily::V4, &format!("http://[::1]:{port}"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not a V4 address"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn timed_connector_applies_the_tcp_settings() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let connect = |tcp| {
+            timed_connector(Arc::new(Mutex::new(None)), tcp, AddressFamily::Dual)
+                .oneshot(format!("http://{address}").parse().unwrap())
+        };
+        let stream = connect(TcpSettings::default()).await.unwrap();
+        assert!(!socket2::SockRef::from(&stream).keepalive().unwrap());
+        let stream = connect(TcpSettings {
+            keepalive: Some(Duration::from_secs(30)),
+            connect_timeout: Some(Duration::from_secs(5)),
+        })
+        .await
+        .unwrap();
+        let socket = socket2::SockRef::from(&stream);
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.keepalive_time().unwrap(), Duration::from_secs(30));
+
+        // a listener that never accepts, with its backlog already taken, leaves further
+        // connections waiting on the handshake
+        let socket =
+            socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+        socket
+            .bind(&SocketAddr::from(([127, 0, 0, 1], 0)).into())
+            .unwrap();
+        socket.listen(0).unwrap();
+        let address = socket.local_addr().unwrap().as_socket().unwrap();
+        let mut backlog = Vec::new();
+        for _ in 0..4 {
+            let stream = tokio::net::TcpStream::connect(address);
+            if let Ok(Ok(stream)) = tokio::time::timeout(Duration::from_millis(200), stream).await {
+                backlog.push(stream);
+            }
+        }
+        let start = std::time::Instant::now();
+        let err = timed_connector(
+            Arc::new(Mutex::new(None)),
+            TcpSettings {
+                keepalive: None,
+                connect_timeout: Some(Duration::from_millis(300)),
+            },
+            AddressFamily::Dual,
+        )
+        .oneshot(format!("http://{address}").parse().unwrap())
+        .await
+        .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(5), "{err}");
+        assert!(err.to_string().contains("deadline has elapsed"), "{err}");
     }
-    Metadata { md }
 }
@@ -0,0 +1,119 @@
+// dial_log.rs tags the log lines of a dial, and of the connection it makes, with a short
+// correlation id, so that the log lines of concurrent dials can be told apart. It can also
+// record the log lines of the dials run within capture_dial_logs, whatever the logger in use.
+
+use rand::Rng;
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+tokio::task_local! {
+    // The correlation id of the dial the current task is running, if it's running one.
+    static DIAL_ID: Option<String>;
+    // The capture that dials started by the current task record their log lines in.
+    static CAPTURE: Arc<DialLogCapture>;
+}
+
+// The captures currently recording, and how many of them there are, so that logging needn't
+// take the lock when none are.
+static CAPTURES: Mutex<Vec<Arc<DialLogCapture>>> = Mutex::new(Vec::new());
+static CAPTURE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// The ids of the dials started within a capture_dial_logs, and their log lines.
+#[derive(Default)]
+struct DialLogCapture {
+    dial_ids: Mutex<Vec<String>>,
+    lines: Mutex<Vec<String>>,
+}
+
+// Logs like the log crate's macros, prefixing the message with the id of the dial being run.
+// Code that runs outside of the dial's tasks, such as the methods of the channel it returned,
+// passes the id of the dial that made the channel as `dial: id`.
+macro_rules! dial_log {
+    (dial: $id:expr, $level:ident, $($arg:tt)+) => {
+        match &$id {
+            Some(id) => match format_args!($($arg)+) {
+                message => {
+                    $crate::rpc::dial_log::capture_line(id, stringify!($level), &message);
+                    log::$level!("[dial {id}] {}", message)
+                }
+            },
+            None => log::$level!($($arg)+),
+        }
+    };
+    ($level:ident, $($arg:tt)+) => {
+        dial_log!(dial: $crate::rpc::dial_log::current_dial_id(), $level, $($arg)+)
+    };
+}
+
+// Returns a short random id to correlate the log lines of a dial by. If called within
+// capture_dial_logs, the dial's log lines are recorded from then on.
+pub(crate) fn new_dial_id() -> String {
+    let id = format!("{:08x}", rand::thread_rng().gen::<u32>());
+    let _ = CAPTURE.try_with(|capture| capture.dial_ids.lock().unwrap().push(id.clone()));
+    id
+}
+
+// Returns the id of the dial the current task is running, if any, for carrying over to the
+// tasks and callbacks the dial sets off with in_dial.
+pub(crate) fn current_dial_id() -> Option<String> {
+    DIAL_ID.try_with(Clone::clone).ok().flatten()
+}
+
+// Runs fut as part of the dial with the given id, so that it tags its log lines with it.
+pub(crate) fn in_dial<F: Future>(id: Option<String>, fut: F) -> impl Future<Output = F::Output> {
+    DIAL_ID.scope(id, fut)
+}
+
+// Records a log line of the dial with the given id in the captures recording that dial.
+pub(crate) fn capture_line(dial_id: &str, level: &str, message: &fmt::Arguments) {
+    if CAPTURE_COUNT.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    for capture in CAPTURES.lock().unwrap().iter() {
+        if capture
+            .dial_ids
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|id| id == dial_id)
+        {
+            capture.lines.lock().unwrap().push(format!(
+                "{} [dial {dial_id}] {message}",
+                level.to_uppercase()
+            ));
+        }
+    }
+}
+
+// Stops a capture from recording once capture_dial_logs is done with it, even if its future
+// is dropped early.
+struct CaptureGuard(Arc<DialLogCapture>);
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        CAPTURES
+            .lock()
+            .unwrap()
+            .retain(|capture| !Arc::ptr_eq(capture, &self.0));
+        CAPTURE_COUNT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Runs fut, returning its output along with every line logged, at any level, by the dials it
+// started and the connections they made while fut ran, formatted as `LEVEL [dial <id>] message`.
+pub(crate) async fn capture_dial_logs<F: Future>(fut: F) -> (F::Output, Vec<String>) {
+    let capture = Arc::new(DialLogCapture::default());
+    CAPTURES.lock().unwrap().push(capture.clone());
+    CAPTURE_COUNT.fetch_add(1, Ordering::Relaxed);
+    let guard = CaptureGuard(capture.clone());
+    let output = CAPTURE.scope(capture.clone(), fut).await;
+    drop(guard);
+    let lines = std::mem::take(&mut *capture.lines.lock().unwrap());
+    (output, lines)
+}
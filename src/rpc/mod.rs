@@ -1,7 +1,14 @@
+// declared first so that the modules after it can use its dial_log macro
+#[macro_use]
+mod dial_log;
+
 mod base_channel;
 mod base_stream;
-mod client_channel;
+pub(crate) mod client_channel;
 mod client_stream;
 pub mod dial;
 pub mod log_prefixes;
-mod webrtc;
+pub mod rtt;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod webrtc;
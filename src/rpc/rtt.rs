@@ -0,0 +1,73 @@
+//! Measuring the round-trip-time of established connections.
+
+use crate::gen::proto::rpc::examples::echo::v1::{
+    echo_service_client::EchoServiceClient, EchoRequest,
+};
+use crate::rpc::dial::ViamChannel;
+use anyhow::{anyhow, Result};
+use std::{ops::Add, time};
+use tonic::{Code, Status};
+
+/// The round-trip-times measured by [`measure_rtt`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RttStats {
+    pub avg: time::Duration,
+    pub min: time::Duration,
+    pub max: time::Duration,
+}
+
+/// Returns the average, shortest and longest round-trip-time over num_pings for the
+/// passed-in channel. Pings that never reach the remote don't count towards the stats, and
+/// if none of them do an error is returned.
+pub async fn measure_rtt(ch: ViamChannel, num_pings: u32) -> Result<RttStats> {
+    if num_pings == 0 {
+        return Err(anyhow!("cannot divide by zero"));
+    }
+    let mut total_ping = time::Duration::new(0, 0);
+    let mut min_ping = time::Duration::MAX;
+    let mut max_ping = time::Duration::ZERO;
+    let mut round_trips = 0;
+    let mut last_error = None;
+    for _ in 0..num_pings {
+        let start = time::Instant::now();
+
+        // Send an echo request across the channel. It's unlikely the remote will be able to
+        // respond to this request, but we'll still get a good sense of RTT.
+        let mut service = EchoServiceClient::new(ch.clone());
+        let echo_request = EchoRequest {
+            message: "dialdbg".to_string(),
+        };
+        if let Err(status) = service.echo(echo_request).await {
+            if is_transport_failure(&status) {
+                last_error = Some(status);
+                continue;
+            }
+        }
+
+        let ping = time::Instant::now().duration_since(start);
+        total_ping = total_ping.add(ping);
+        min_ping = min_ping.min(ping);
+        max_ping = max_ping.max(ping);
+        round_trips += 1;
+    }
+    match total_ping.checked_div(round_trips) {
+        Some(avg_ping) => Ok(RttStats {
+            avg: avg_ping,
+            min: min_ping,
+            max: max_ping,
+        }),
+        None => Err(anyhow!(
+            "none of the {num_pings} pings reached the remote: {}",
+            last_error.map(|e| e.to_string()).unwrap_or_default()
+        )),
+    }
+}
+
+// Whether a ping failed before the remote could answer it. Any other status, such as the
+// Unimplemented a robot without the echo service replies with, is still a round trip.
+fn is_transport_failure(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unknown | Code::Unavailable | Code::Cancelled | Code::DeadlineExceeded
+    )
+}
@@ -0,0 +1,18 @@
+//! Helpers for checking connections in tests, available with the `test-util` feature.
+
+use crate::gen::proto::rpc::examples::echo::v1::{
+    echo_service_client::EchoServiceClient, EchoRequest,
+};
+use crate::rpc::dial::ViamChannel;
+use anyhow::Result;
+
+/// Sends the message to the echo service over the channel in a single unary call and returns
+/// the message the service echoed back. This is a quick way for a test to check that it can
+/// talk to a robot (or other server) that runs the echo service.
+pub async fn echo_roundtrip(channel: ViamChannel, message: &str) -> Result<String> {
+    let request = EchoRequest {
+        message: message.to_string(),
+    };
+    let response = EchoServiceClient::new(channel).echo(request).await?;
+    Ok(response.into_inner().message)
+}
@@ -1,6 +1,8 @@
+use super::dial::DialEventHook;
+use super::dial_log::{current_dial_id, in_dial};
 use super::log_prefixes;
 use crate::gen::proto::rpc::webrtc::v1::{IceServer, ResponseTrailers, WebRtcConfig};
-use anyhow::Result;
+use anyhow::{Context, Result};
 use bytes::Bytes;
 use core::fmt;
 use futures::Future;
@@ -20,7 +22,8 @@ use webrtc::{
     ice_transport::ice_server::RTCIceServer,
     interceptor::registry::Registry,
     peer_connection::{
-        configuration::RTCConfiguration, peer_connection_state::RTCPeerConnectionState,
+        certificate::RTCCertificate, configuration::RTCConfiguration,
+        peer_connection_state::RTCPeerConnectionState,
         sdp::session_description::RTCSessionDescription, signaling_state::RTCSignalingState,
         RTCPeerConnection,
     },
@@ -29,14 +32,43 @@ use webrtc::{
 // set to 20sec to match _defaultOfferDeadline in goutils/rpc/wrtc_call_queue.go
 const WEBRTC_TIMEOUT: Duration = Duration::from_secs(20);
 
-/// Options for connecting via webRTC.
-#[derive(Default, Clone)]
-pub(crate) struct Options {
+/// Options for connecting via webRTC. Most of them are set through a DialBuilder's webRTC
+/// methods; an `Options` built with [`Options::new`] can also be handed to the builder
+/// whole with `webrtc_options`.
+#[derive(Clone)]
+pub struct Options {
     pub(crate) disable_webrtc: bool,
     pub(crate) disable_trickle_ice: bool,
     pub(crate) config: RTCConfiguration,
-    pub(crate) signaling_insecure: bool,
-    pub(crate) signaling_server_address: String,
+    pub(crate) dtls_certificate: Option<RTCCertificate>,
+    // An explicitly configured endpoint to signal through, as given to the builder; it's
+    // parsed when connecting. When unset, signaling runs over the channel opened to the
+    // dialed uri.
+    pub(crate) signaling_server: Option<String>,
+    // Headers added only to the signaling RPCs, as (name, value) pairs.
+    pub(crate) signaling_headers: Vec<(String, String)>,
+    // Candidates, in ICE candidate string form, advertised alongside the gathered ones.
+    pub(crate) additional_local_candidates: Vec<String>,
+    // `None` leaves the webrtc crate's default in place.
+    pub(crate) ice_disconnected_timeout: Option<Duration>,
+    pub(crate) ice_failed_timeout: Option<Duration>,
+    // Whether session descriptions are exchanged in the url-safe base64 alphabet rather than
+    // the standard one.
+    pub(crate) sdp_base64_urlsafe: bool,
+    // How long to let a connection settle once it's established, if it's to be probed.
+    pub(crate) post_open_probe: Option<Duration>,
+    // How many times to start negotiating over on a fresh peer connection after a failure.
+    pub(crate) negotiation_retries: u32,
+    // ICE servers supplied by the user, which take precedence over the configured ones and
+    // the ones the signaling server suggests.
+    pub(crate) ice_servers: Vec<RTCIceServer>,
+    // Told about each step of the signaling exchange.
+    pub(crate) dial_event_hook: DialEventHook,
+    // The most bytes of received messages, whole or partial, that the channel holds across its
+    // streams before they're read.
+    pub(crate) max_receive_buffer: Option<usize>,
+    // The most streams the channel has open at once.
+    pub(crate) max_concurrent_streams: Option<usize>,
 }
 
 impl fmt::Debug for Options {
@@ -50,18 +82,90 @@ impl fmt::Debug for Options {
             // RTCConfiguration does not derive Debug
             .field("config", &format_args!("{}", "<Opaque>"))
             .field(
-                "signaling_insecure",
-                &format_args!("{}", self.signaling_insecure),
+                "dtls_certificate",
+                &format_args!(
+                    "{}",
+                    match self.dtls_certificate {
+                        Some(_) => "<Supplied>",
+                        None => "<Generated>",
+                    }
+                ),
             )
+            .field("signaling_server", &self.signaling_server)
+            .field("signaling_headers", &self.signaling_headers)
             .field(
-                "signaling_server_address",
-                &format_args!("{}", self.signaling_server_address),
+                "additional_local_candidates",
+                &self.additional_local_candidates,
+            )
+            .field("ice_disconnected_timeout", &self.ice_disconnected_timeout)
+            .field("ice_failed_timeout", &self.ice_failed_timeout)
+            .field("sdp_base64_urlsafe", &self.sdp_base64_urlsafe)
+            .field("post_open_probe", &self.post_open_probe)
+            .field("negotiation_retries", &self.negotiation_retries)
+            .field("max_receive_buffer", &self.max_receive_buffer)
+            .field("max_concurrent_streams", &self.max_concurrent_streams)
+            // only the urls, to keep TURN credentials out of logs
+            .field(
+                "ice_servers",
+                &self
+                    .ice_servers
+                    .iter()
+                    .map(|server| &server.urls)
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "dial_event_hook",
+                &format_args!(
+                    "{}",
+                    match self.dial_event_hook.is_set() {
+                        true => "<Supplied>",
+                        false => "<None>",
+                    }
+                ),
             )
             .finish()
     }
 }
 
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Options {
+    /// Returns the options webRTC connections are made with by default, which gather
+    /// candidates through a public STUN server.
+    pub fn new() -> Self {
+        Options {
+            disable_webrtc: false,
+            disable_trickle_ice: false,
+            config: default_configuration(),
+            dtls_certificate: None,
+            signaling_server: None,
+            signaling_headers: Vec::new(),
+            additional_local_candidates: Vec::new(),
+            ice_disconnected_timeout: None,
+            ice_failed_timeout: None,
+            sdp_base64_urlsafe: false,
+            post_open_probe: None,
+            negotiation_retries: 0,
+            ice_servers: Vec::new(),
+            dial_event_hook: DialEventHook::default(),
+            max_receive_buffer: None,
+            max_concurrent_streams: None,
+        }
+    }
+
+    /// Adds the given STUN or TURN servers to the ones candidates are gathered through, for
+    /// example to relay through one's own TURN servers from behind a restrictive NAT. Any
+    /// username and credential on them are used as is. Where a server shares a url with one
+    /// the signaling server suggests, the one given here is used instead.
+    pub fn with_ice_servers(mut self, ice_servers: Vec<RTCIceServer>) -> Self {
+        self.ice_servers.extend(ice_servers);
+        self
+    }
+
     pub(crate) fn infer_signaling_server_address(uri: &Uri) -> Option<(String, bool)> {
         // TODO(RSDK-235): remove hard coding of signaling server address and prefer SRV lookup instead
         let path = uri.to_string();
@@ -74,26 +178,86 @@ impl Options {
         }
     }
 
-    pub(crate) fn infer_from_uri(uri: Uri) -> Self {
-        match Self::infer_signaling_server_address(&uri) {
-            None => Options {
-                config: default_configuration(),
-                ..Default::default()
-            },
-            Some((signaling_server_address, secure)) => Options {
-                config: default_configuration(),
-                signaling_server_address,
-                signaling_insecure: !secure,
-                ..Default::default()
-            },
-        }
-    }
-
     /// Disables connecting via webRTC, forcing a direct connect
     pub(crate) fn disable_webrtc(mut self) -> Self {
         self.disable_webrtc = true;
         self
     }
+
+    /// Uses the provided certificate for DTLS instead of generating a new one per connection
+    pub(crate) fn dtls_certificate(mut self, certificate: RTCCertificate) -> Self {
+        self.dtls_certificate = Some(certificate);
+        self
+    }
+
+    /// Signals through the given endpoint rather than the dialed one
+    pub(crate) fn signaling_server(mut self, address: String) -> Self {
+        self.signaling_server = Some(address);
+        self
+    }
+
+    /// Adds a header to the signaling RPCs without adding it to calls on the connection
+    pub(crate) fn signaling_header(mut self, name: String, value: String) -> Self {
+        self.signaling_headers.push((name, value));
+        self
+    }
+
+    /// Advertises a local candidate to the remote peer in addition to the gathered ones
+    pub(crate) fn additional_local_candidate(mut self, candidate: String) -> Self {
+        self.additional_local_candidates.push(candidate);
+        self
+    }
+
+    /// Encodes and decodes session descriptions in the url-safe base64 alphabet
+    pub(crate) fn sdp_base64_urlsafe(mut self) -> Self {
+        self.sdp_base64_urlsafe = true;
+        self
+    }
+
+    /// Probes the connection once its data channel opens, waiting for it to be fully
+    /// established and then for the given settle delay before handing it out
+    pub(crate) fn post_open_probe(mut self, settle: Duration) -> Self {
+        self.post_open_probe = Some(settle);
+        self
+    }
+
+    /// Negotiates again from scratch, on a fresh peer connection, up to the given number of
+    /// times after a failed negotiation
+    pub(crate) fn negotiation_retries(mut self, retries: u32) -> Self {
+        self.negotiation_retries = retries;
+        self
+    }
+
+    /// Caps the bytes of unread messages held across the channel's streams
+    pub(crate) fn max_receive_buffer(mut self, bytes: usize) -> Self {
+        self.max_receive_buffer = Some(bytes);
+        self
+    }
+
+    /// Caps how many streams the channel has open at once
+    pub(crate) fn max_concurrent_streams(mut self, count: usize) -> Self {
+        self.max_concurrent_streams = Some(count);
+        self
+    }
+
+    /// Reports each step of the signaling exchange to the given hook
+    pub(crate) fn dial_event_hook(mut self, hook: DialEventHook) -> Self {
+        self.dial_event_hook = hook;
+        self
+    }
+
+    /// Sets how long the ICE agent goes without network activity before considering the
+    /// connection disconnected
+    pub(crate) fn ice_disconnected_timeout(mut self, timeout: Duration) -> Self {
+        self.ice_disconnected_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long the ICE agent stays disconnected before considering the connection failed
+    pub(crate) fn ice_failed_timeout(mut self, timeout: Duration) -> Self {
+        self.ice_failed_timeout = Some(timeout);
+        self
+    }
 }
 
 fn default_configuration() -> RTCConfiguration {
@@ -116,28 +280,44 @@ fn ice_server_from_proto(ice_server: IceServer) -> RTCIceServer {
     }
 }
 
+// Adds the ICE servers from the signaling server's optional config to the original config,
+// then puts the preferred (user supplied) servers ahead of them all, dropping any server that
+// shares a url with a preferred one.
 pub(crate) fn extend_webrtc_config(
     original: RTCConfiguration,
     optional: Option<WebRtcConfig>,
+    preferred: &[RTCIceServer],
 ) -> RTCConfiguration {
-    match optional {
-        None => original,
-        Some(optional) => {
-            let mut new_ice_servers = original.ice_servers;
-            for additional_server in optional.additional_ice_servers {
-                let additional_server = ice_server_from_proto(additional_server);
-                new_ice_servers.push(additional_server);
-            }
-
-            RTCConfiguration {
-                ice_servers: new_ice_servers,
-                ..original
-            }
+    let mut new_ice_servers = preferred.to_vec();
+    let additional_ice_servers = optional
+        .into_iter()
+        .flat_map(|optional| optional.additional_ice_servers)
+        .map(ice_server_from_proto);
+    for server in original
+        .ice_servers
+        .iter()
+        .cloned()
+        .chain(additional_ice_servers)
+    {
+        let overridden = server
+            .urls
+            .iter()
+            .any(|url| preferred.iter().any(|p| p.urls.contains(url)));
+        if !overridden {
+            new_ice_servers.push(server);
         }
     }
+
+    RTCConfiguration {
+        ice_servers: new_ice_servers,
+        ..original
+    }
 }
 
-fn new_webrtc_api() -> Result<API> {
+fn new_webrtc_api(
+    ice_disconnected_timeout: Option<Duration>,
+    ice_failed_timeout: Option<Duration>,
+) -> Result<API> {
     let mut media_engine = MediaEngine::default();
     media_engine.register_default_codecs()?;
     let registry = Registry::new();
@@ -159,6 +339,7 @@ fn new_webrtc_api() -> Result<API> {
     setting_engine.set_srtp_protection_profiles(srtp_protection_profiles);
     setting_engine.set_ice_multicast_dns_mode(MulticastDnsMode::QueryAndGather);
     setting_engine.set_include_loopback_candidate(true);
+    setting_engine.set_ice_timeouts(ice_disconnected_timeout, ice_failed_timeout, None);
 
     Ok(APIBuilder::new()
         .with_media_engine(media_engine)
@@ -167,15 +348,84 @@ fn new_webrtc_api() -> Result<API> {
         .build())
 }
 
+/// Encodes a session description in the format Viam's signaling service exchanges it in:
+/// base64 encoded JSON.
+pub fn encode_sdp(sdp: &RTCSessionDescription) -> Result<String> {
+    encode_sdp_with_alphabet(sdp, false)
+}
+
+/// Encodes a session description like [`encode_sdp`], in the url-safe base64 alphabet if
+/// `urlsafe` is set and the standard one otherwise.
+pub fn encode_sdp_with_alphabet(sdp: &RTCSessionDescription, urlsafe: bool) -> Result<String> {
+    let sdp = serde_json::to_vec(sdp).context("unable to serialize session description")?;
+    Ok(base64::encode_config(sdp, base64_config(urlsafe)))
+}
+
+fn base64_config(urlsafe: bool) -> base64::Config {
+    match urlsafe {
+        true => base64::URL_SAFE,
+        false => base64::STANDARD,
+    }
+}
+
+/// Decodes a session description from the base64 encoded JSON format Viam's signaling
+/// service exchanges it in.
+pub fn decode_sdp(sdp: &str) -> Result<RTCSessionDescription> {
+    decode_sdp_with_alphabet(sdp, false)
+}
+
+/// Decodes a session description like [`decode_sdp`], expecting the url-safe base64 alphabet
+/// if `urlsafe` is set and the standard one otherwise.
+pub fn decode_sdp_with_alphabet(sdp: &str, urlsafe: bool) -> Result<RTCSessionDescription> {
+    let sdp = base64::decode_config(sdp, base64_config(urlsafe))
+        .context("session description is not valid base64")?;
+    let sdp = String::from_utf8(sdp).context("session description is not valid UTF-8")?;
+    serde_json::from_str::<RTCSessionDescription>(&sdp)
+        .context("session description is not a valid JSON session description")
+}
+
+/// Checks that a session description received from the signaling server has the data channel
+/// section every call over the connection runs on. Without it the webrtc crate accepts the
+/// description but the connection never opens, so checking up front fails the dial with an
+/// error pointing at the server's answer instead.
+pub fn validate_sdp(sdp: &RTCSessionDescription) -> Result<()> {
+    let parsed = sdp
+        .unmarshal()
+        .context("session description is not valid SDP")?;
+    let has_data_channel = parsed.media_descriptions.iter().any(|media| {
+        media.media_name.media == "application"
+            && media.media_name.protos.iter().any(|proto| proto == "SCTP")
+    });
+    if !has_data_channel {
+        let sections: Vec<String> = parsed
+            .media_descriptions
+            .iter()
+            .map(|media| media.media_name.to_string())
+            .collect();
+        anyhow::bail!(
+            "session description has no data channel section, only media sections {sections:?}"
+        );
+    }
+    Ok(())
+}
+
 fn create_invalid_sdp_err(err: serde_json::error::Error) -> webrtc::Error {
     webrtc::Error::Sdp(webrtc::sdp::Error::SdpInvalidValue(err.to_string()))
 }
 
 pub(crate) async fn new_peer_connection_for_client(
-    config: RTCConfiguration,
+    mut config: RTCConfiguration,
     disable_trickle_ice: bool,
+    dtls_certificate: Option<RTCCertificate>,
+    ice_disconnected_timeout: Option<Duration>,
+    ice_failed_timeout: Option<Duration>,
 ) -> Result<(Arc<RTCPeerConnection>, Arc<RTCDataChannel>)> {
-    let web_api = new_webrtc_api()?;
+    // When no certificate is supplied the webrtc crate generates a fresh one for every
+    // peer connection, so only override the configured set when we were given one.
+    if let Some(certificate) = dtls_certificate {
+        config.certificates = vec![certificate];
+    }
+    let web_api = new_webrtc_api(ice_disconnected_timeout, ice_failed_timeout)?;
     let peer_connection = Arc::new(web_api.new_peer_connection(config).await?);
 
     let data_channel_init = RTCDataChannelInit {
@@ -190,18 +440,23 @@ pub(crate) async fn new_peer_connection_for_client(
         ..Default::default()
     };
 
+    // the connection's callbacks run on the webrtc crate's tasks, so they're tagged with the
+    // id of the dial making the connection by hand
+    let dial_id = current_dial_id();
+    let state_dial_id = dial_id.clone();
     peer_connection.on_peer_connection_state_change(Box::new(
         move |connection: RTCPeerConnectionState| {
-            log::info!("peer connection state change: {connection}");
+            dial_log!(dial: state_dial_id, info, "peer connection state change: {connection}");
             if connection == RTCPeerConnectionState::Connected {
-                log::debug!("{}", log_prefixes::DIALED_WEBRTC);
+                dial_log!(dial: state_dial_id, debug, "{}", log_prefixes::DIALED_WEBRTC);
             }
             Box::pin(async move {})
         },
     ));
 
+    let signaling_dial_id = dial_id.clone();
     peer_connection.on_signaling_state_change(Box::new(move |ssc: RTCSignalingState| {
-        log::info!("new signaling state: {ssc}");
+        dial_log!(dial: signaling_dial_id, info, "new signaling state: {ssc}");
         Box::pin(async move {})
     }));
 
@@ -218,7 +473,7 @@ pub(crate) async fn new_peer_connection_for_client(
     negotiation_channel.on_message(Box::new(move |msg: DataChannelMessage| {
         let wpc = pc.clone();
         let nc = nc.clone();
-        Box::pin(async move {
+        Box::pin(in_dial(dial_id.clone(), async move {
             let pc = match wpc.upgrade() {
                 Some(pc) => pc,
                 None => return,
@@ -242,9 +497,9 @@ pub(crate) async fn new_peer_connection_for_client(
             .await;
 
             if let Err(e) = maybe_err {
-                log::error!("Error processing sdp in negotiation channel: {e}");
+                dial_log!(error, "Error processing sdp in negotiation channel: {e}");
             }
-        })
+        }))
     }));
 
     if disable_trickle_ice {
@@ -300,11 +555,11 @@ pub(crate) fn trailers_from_proto(proto: ResponseTrailers) -> HeaderMap {
             let (k, v) = match (k, v) {
                 (Ok(k), Ok(v)) => (k, v),
                 (Err(e), _) => {
-                    log::error!("Error converting proto trailer key: [{e}]");
+                    dial_log!(error, "Error converting proto trailer key: [{e}]");
                     continue;
                 }
                 (_, Err(e)) => {
-                    log::error!("Error converting proto trailer value: [{e}]");
+                    dial_log!(error, "Error converting proto trailer value: [{e}]");
                     continue;
                 }
             };
@@ -325,25 +580,303 @@ pub(crate) fn trailers_from_proto(proto: ResponseTrailers) -> HeaderMap {
             (Ok(k), Ok(v)) => {
                 trailers.insert(k, v);
             }
-            (Err(e), _) => log::error!("Error parsing HeaderName: {e}"),
-            (_, Err(e)) => log::error!("Error parsing HeaderValue: {e}"),
+            (Err(e), _) => dial_log!(error, "Error parsing HeaderName: {e}"),
+            (_, Err(e)) => dial_log!(error, "Error parsing HeaderValue: {e}"),
         }
     }
 
     let k = match HeaderName::from_str(status_name) {
         Ok(k) => k,
         Err(e) => {
-            log::error!("Error parsing HeaderName: {e}");
+            dial_log!(error, "Error parsing HeaderName: {e}");
             return trailers;
         }
     };
     let v = match HeaderValue::from_str(&status_code) {
         Ok(v) => v,
         Err(e) => {
-            log::error!("Error parsing HeaderValue: {e}");
+            dial_log!(error, "Error parsing HeaderValue: {e}");
             return trailers;
         }
     };
     trailers.insert(k, v);
     trailers
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{
+        decode_sdp, decode_sdp_with_alphabet, default_configuration, encode_sdp,
+        encode_sdp_with_alphabet, extend_webrtc_config, new_peer_connection_for_client,
+        validate_sdp, Options,
+    };
+    use crate::gen::proto::rpc::webrtc::v1::{IceServer, WebRtcConfig};
+    use std::time::{Duration, SystemTime};
+    use tokio::sync::mpsc;
+    use webrtc::{
+        api::APIBuilder,
+        dtls::crypto::Certificate,
+        ice_transport::{ice_connection_state::RTCIceConnectionState, ice_server::RTCIceServer},
+        peer_connection::{
+            certificate::RTCCertificate, configuration::RTCConfiguration,
+            sdp::session_description::RTCSessionDescription, RTCPeerConnection,
+        },
+    };
+
+    #[tokio::test]
+    async fn sdp_round_trips() {
+        let (peer_connection, _) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let offer = peer_connection.create_offer(None).await.unwrap();
+        peer_connection.close().await.unwrap();
+
+        let decoded = decode_sdp(&encode_sdp(&offer).unwrap()).unwrap();
+        assert_eq!(decoded.sdp_type, offer.sdp_type);
+        assert_eq!(decoded.sdp, offer.sdp);
+    }
+
+    #[tokio::test]
+    async fn sdp_round_trips_in_either_alphabet() {
+        let (peer_connection, _) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let offer = peer_connection.create_offer(None).await.unwrap();
+        peer_connection.close().await.unwrap();
+
+        for urlsafe in [false, true] {
+            let encoded = encode_sdp_with_alphabet(&offer, urlsafe).unwrap();
+            let decoded = decode_sdp_with_alphabet(&encoded, urlsafe).unwrap();
+            assert_eq!(decoded.sdp, offer.sdp);
+        }
+        assert_eq!(
+            encode_sdp_with_alphabet(&offer, false).unwrap(),
+            encode_sdp(&offer).unwrap()
+        );
+    }
+
+    #[test]
+    fn urlsafe_sdp_decodes_only_when_expected() {
+        // a run of five '?'s holds three that line up with a base64 group, which encode to
+        // "Pz8/" in the standard alphabet and "Pz8_" in the url-safe one
+        let sdp: RTCSessionDescription =
+            serde_json::from_str(r#"{"type":"offer","sdp":"s=?????"}"#).unwrap();
+        let encoded = encode_sdp_with_alphabet(&sdp, true).unwrap();
+        assert!(!encoded.contains(['+', '/']), "{encoded}");
+        assert_ne!(encoded, encode_sdp(&sdp).unwrap());
+
+        let decoded = decode_sdp_with_alphabet(&encoded, true).unwrap();
+        assert_eq!(decoded.sdp, sdp.sdp);
+        let err = decode_sdp(&encoded).unwrap_err();
+        assert_eq!(err.to_string(), "session description is not valid base64");
+    }
+
+    #[test]
+    fn user_ice_servers_take_precedence_and_keep_credentials() {
+        let turn = |url: &str, username: &str| RTCIceServer {
+            urls: vec![url.to_string()],
+            username: username.to_string(),
+            credential: format!("{username}-secret"),
+        };
+        let original = RTCConfiguration {
+            ice_servers: vec![turn("stun:stun.example.com:3478", "")],
+            ..Default::default()
+        };
+        let optional = WebRtcConfig {
+            additional_ice_servers: vec![
+                IceServer {
+                    urls: vec!["turn:relay.example.com:3478".to_string()],
+                    username: "server".to_string(),
+                    credential: "server-secret".to_string(),
+                },
+                IceServer {
+                    urls: vec!["turn:other.example.com:3478".to_string()],
+                    username: "other".to_string(),
+                    credential: "other-secret".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+        let preferred = vec![turn("turn:relay.example.com:3478", "user")];
+
+        let config = extend_webrtc_config(original.clone(), Some(optional), &preferred);
+        assert_eq!(
+            config.ice_servers,
+            vec![
+                turn("turn:relay.example.com:3478", "user"),
+                turn("stun:stun.example.com:3478", ""),
+                turn("turn:other.example.com:3478", "other"),
+            ]
+        );
+
+        let config = extend_webrtc_config(original.clone(), None, &[]);
+        assert_eq!(config.ice_servers, original.ice_servers);
+    }
+
+    #[test]
+    fn default_options_gather_through_the_default_stun_server() {
+        let options = Options::default();
+        assert_eq!(
+            options.config.ice_servers,
+            default_configuration().ice_servers
+        );
+        assert!(!options.config.ice_servers.is_empty());
+    }
+
+    #[test]
+    fn decoding_malformed_sdp_fails_clearly() {
+        let err = decode_sdp("not base64!").unwrap_err();
+        assert_eq!(err.to_string(), "session description is not valid base64");
+
+        let err = decode_sdp(&base64::encode("{\"type\": \"offer\"")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "session description is not a valid JSON session description"
+        );
+    }
+
+    #[tokio::test]
+    async fn sdp_without_a_data_channel_is_rejected() {
+        let (peer_connection, _) =
+            new_peer_connection_for_client(RTCConfiguration::default(), true, None, None, None)
+                .await
+                .unwrap();
+        let offer = peer_connection.create_offer(None).await.unwrap();
+        peer_connection.close().await.unwrap();
+        validate_sdp(&offer).unwrap();
+
+        let audio_only = RTCSessionDescription::answer(
+            [
+                "v=0",
+                "o=- 0 0 IN IP4 127.0.0.1",
+                "s=-",
+                "t=0 0",
+                "m=audio 9 UDP/TLS/RTP/SAVPF 111",
+                "c=IN IP4 0.0.0.0",
+                "a=mid:0",
+                "",
+            ]
+            .join("\r\n"),
+        )
+        .unwrap();
+        let err = validate_sdp(&audio_only).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "session description has no data channel section, only media sections \
+             [\"audio 9 UDP/TLS/RTP/SAVPF 111\"]"
+        );
+    }
+
+    async fn offer_fingerprint(certificate: Option<RTCCertificate>) -> String {
+        let (peer_connection, _) = new_peer_connection_for_client(
+            RTCConfiguration::default(),
+            false,
+            certificate,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let offer = peer_connection.create_offer(None).await.unwrap();
+        peer_connection.close().await.unwrap();
+        offer
+            .sdp
+            .lines()
+            .find(|line| line.starts_with("a=fingerprint:"))
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn supplied_dtls_certificate_is_reused() {
+        let certificate = RTCCertificate::from_existing(
+            Certificate::generate_self_signed(vec!["localhost".to_string()]).unwrap(),
+            SystemTime::now() + Duration::from_secs(60 * 60),
+        );
+        let expected = certificate.get_fingerprints()[0].value.clone();
+
+        let first = offer_fingerprint(Some(certificate.clone())).await;
+        let second = offer_fingerprint(Some(certificate)).await;
+        assert_eq!(first, second);
+        assert!(first.to_lowercase().ends_with(&expected));
+
+        let generated = offer_fingerprint(None).await;
+        assert_ne!(first, generated);
+    }
+
+    // Answers the offer of a client peer connection created with trickle ICE disabled using
+    // a plain in-process peer, which is returned.
+    pub(crate) async fn answer_with_local_peer(
+        peer_connection: &RTCPeerConnection,
+    ) -> RTCPeerConnection {
+        let remote = answer_offer(peer_connection.local_description().await.unwrap()).await;
+        peer_connection
+            .set_remote_description(remote.local_description().await.unwrap())
+            .await
+            .unwrap();
+        remote
+    }
+
+    // Returns a plain in-process peer that has answered the offer, with its candidates
+    // gathered into its local description.
+    pub(crate) async fn answer_offer(offer: RTCSessionDescription) -> RTCPeerConnection {
+        let remote = APIBuilder::new()
+            .build()
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap();
+        let mut remote_gathered = remote.gathering_complete_promise().await;
+        remote.set_remote_description(offer).await.unwrap();
+        let answer = remote.create_answer(None).await.unwrap();
+        remote.set_local_description(answer).await.unwrap();
+        let _ = remote_gathered.recv().await;
+        remote
+    }
+
+    async fn wait_for_state(
+        states: &mut mpsc::UnboundedReceiver<RTCIceConnectionState>,
+        expected: RTCIceConnectionState,
+        within: Duration,
+    ) -> bool {
+        let reached = async {
+            while let Some(state) = states.recv().await {
+                if state == expected {
+                    return;
+                }
+            }
+        };
+        tokio::time::timeout(within, reached).await.is_ok()
+    }
+
+    #[tokio::test]
+    async fn configured_ice_timeouts_are_applied() {
+        let (peer_connection, _) = new_peer_connection_for_client(
+            RTCConfiguration::default(),
+            true,
+            None,
+            Some(Duration::from_millis(500)),
+            Some(Duration::from_millis(500)),
+        )
+        .await
+        .unwrap();
+        let (state_sender, mut states) = mpsc::unbounded_channel();
+        peer_connection.on_ice_connection_state_change(Box::new(move |state| {
+            let _ = state_sender.send(state);
+            Box::pin(async {})
+        }));
+
+        let remote = answer_with_local_peer(&peer_connection).await;
+
+        let connected = RTCIceConnectionState::Connected;
+        assert!(wait_for_state(&mut states, connected, Duration::from_secs(10)).await);
+
+        // Stall the connection by silencing the remote peer. With the webrtc crate's defaults
+        // the connection wouldn't be declared failed for 30 seconds.
+        remote.close().await.unwrap();
+        let failed = RTCIceConnectionState::Failed;
+        assert!(wait_for_state(&mut states, failed, Duration::from_secs(5)).await);
+        peer_connection.close().await.unwrap();
+    }
+}
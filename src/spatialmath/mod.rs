@@ -1 +1,4 @@
+pub mod pose;
+pub mod quantized;
+pub mod similarity;
 pub mod utils;
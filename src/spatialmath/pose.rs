@@ -0,0 +1,179 @@
+use nalgebra::{Quaternion, Vector3};
+
+use super::utils::{quaternion_average, rotate_vector_by_quaternion};
+
+/// A rigid transform made up of a translation and an orientation. Applying a pose
+/// to a point rotates the point by the orientation and then translates it.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Pose {
+    pub translation: Vector3<f64>,
+    pub orientation: Quaternion<f64>,
+}
+
+impl Pose {
+    /// Creates a pose from a translation and an orientation. The orientation is
+    /// normalized so that it represents a pure rotation.
+    pub fn new(translation: Vector3<f64>, orientation: Quaternion<f64>) -> Self {
+        Pose {
+            translation,
+            orientation: orientation.normalize(),
+        }
+    }
+
+    /// Returns the pose resulting from applying `other` within the frame of this pose
+    /// (i.e. `other` is applied first, followed by `self`).
+    pub fn compose(&self, other: &Pose) -> Pose {
+        Pose {
+            translation: self.translation
+                + rotate_vector_by_quaternion(&self.orientation, &other.translation),
+            orientation: (self.orientation * other.orientation).normalize(),
+        }
+    }
+
+    /// Returns the pose that undoes this one, such that composing the two yields the
+    /// identity pose.
+    pub fn inverse(&self) -> Pose {
+        let orientation = self.orientation.conjugate();
+        Pose {
+            translation: -rotate_vector_by_quaternion(&orientation, &self.translation),
+            orientation,
+        }
+    }
+
+    /// Returns the point moved by this pose, rotated by the orientation and then translated.
+    pub fn apply_to_point(&self, point: Vector3<f64>) -> Vector3<f64> {
+        self.translation + rotate_vector_by_quaternion(&self.orientation, &point)
+    }
+
+    /// Returns this pose expressed relative to the `reference` pose, such that
+    /// `reference.compose(&self.relative_to(reference))` reconstructs this pose.
+    pub fn relative_to(&self, reference: &Pose) -> Pose {
+        reference.inverse().compose(self)
+    }
+
+    /// Returns the average of the poses, averaging their translations arithmetically and
+    /// their orientations with quaternion_average. Returns None if there are no poses.
+    pub fn average(poses: &[Pose]) -> Option<Pose> {
+        let orientations: Vec<Quaternion<f64>> =
+            poses.iter().map(|pose| pose.orientation).collect();
+        let orientation = quaternion_average(&orientations)?;
+        let translation = poses
+            .iter()
+            .fold(Vector3::zeros(), |sum, pose| sum + pose.translation)
+            / poses.len() as f64;
+        Some(Pose::new(translation, orientation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{Quaternion, Vector3};
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+
+    use super::Pose;
+
+    fn assert_poses_approx_eq(pose1: &Pose, pose2: &Pose) {
+        let translation_diff = pose1.translation - pose2.translation;
+        assert_approx_eq!(f64, translation_diff.norm(), 0.0, epsilon = 0.0001);
+        // q and -q represent the same rotation
+        let orientation_diff = (pose1.orientation.coords - pose2.orientation.coords)
+            .norm()
+            .min((pose1.orientation.coords + pose2.orientation.coords).norm());
+        assert_approx_eq!(f64, orientation_diff, 0.0, epsilon = 0.0001);
+    }
+
+    // 90 degrees about the z axis
+    fn quarter_turn() -> Pose {
+        Pose::new(
+            Vector3::new(-4.0, 0.5, 2.0),
+            Quaternion::new(FRAC_1_SQRT_2, 0.0, 0.0, FRAC_1_SQRT_2),
+        )
+    }
+
+    fn skewed() -> Pose {
+        Pose::new(
+            Vector3::new(1.0, -2.0, 3.5),
+            Quaternion::new(0.0436194, 0.3710372, 0.5565558, 0.7420744),
+        )
+    }
+
+    fn identity() -> Pose {
+        Pose::new(Vector3::zeros(), Quaternion::identity())
+    }
+
+    #[test]
+    fn pose_composed_with_its_inverse_is_identity() {
+        let pose = skewed();
+        assert_poses_approx_eq(&pose.compose(&pose.inverse()), &identity());
+        assert_poses_approx_eq(&pose.inverse().compose(&pose), &identity());
+    }
+
+    #[test]
+    fn pose_applied_to_point_rotates_then_translates() {
+        let pose = quarter_turn();
+        let point = pose.apply_to_point(Vector3::new(1.0, 0.0, 0.0));
+        assert_approx_eq!(f64, point.x, -4.0, epsilon = 0.0001);
+        assert_approx_eq!(f64, point.y, 1.5, epsilon = 0.0001);
+        assert_approx_eq!(f64, point.z, 2.0, epsilon = 0.0001);
+
+        let back = pose.inverse().apply_to_point(point);
+        assert_approx_eq!(
+            f64,
+            (back - Vector3::new(1.0, 0.0, 0.0)).norm(),
+            0.0,
+            epsilon = 0.0001
+        );
+
+        // composing poses matches applying them in turn, the inner one first
+        let other = skewed();
+        let p = Vector3::new(0.3, 0.7, -1.1);
+        let composed = pose.compose(&other).apply_to_point(p);
+        let in_turn = pose.apply_to_point(other.apply_to_point(p));
+        assert_approx_eq!(f64, (composed - in_turn).norm(), 0.0, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn relative_pose_to_self_is_identity() {
+        let pose = skewed();
+        assert_poses_approx_eq(&pose.relative_to(&pose), &identity());
+    }
+
+    #[test]
+    fn relative_pose_composes_back_to_original() {
+        let pose = skewed();
+        let reference = quarter_turn();
+        let relative = pose.relative_to(&reference);
+        assert_poses_approx_eq(&reference.compose(&relative), &pose);
+
+        // a point one unit along the reference frame's x axis sits one unit along the
+        // world y axis from the reference origin
+        let point = Pose::new(Vector3::new(-4.0, 1.5, 2.0), Quaternion::identity());
+        let relative_point = point.relative_to(&reference);
+        assert_approx_eq!(f64, relative_point.translation.x, 1.0, epsilon = 0.0001);
+        assert_approx_eq!(f64, relative_point.translation.y, 0.0, epsilon = 0.0001);
+        assert_approx_eq!(f64, relative_point.translation.z, 0.0, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn average_of_identical_poses_is_the_pose() {
+        let pose = skewed();
+        assert_poses_approx_eq(&Pose::average(&[pose, pose, pose]).unwrap(), &pose);
+        assert_poses_approx_eq(&Pose::average(&[pose]).unwrap(), &pose);
+        assert!(Pose::average(&[]).is_none());
+    }
+
+    #[test]
+    fn average_of_symmetric_poses_is_centered() {
+        // translations mirrored about (1, 1, 1), rotated 45 degrees either way about z
+        let about_z =
+            |angle: f64| Quaternion::new((angle / 2.0).cos(), 0.0, 0.0, (angle / 2.0).sin());
+        let poses = [
+            Pose::new(Vector3::new(2.0, 1.0, 0.0), about_z(FRAC_PI_4)),
+            Pose::new(Vector3::new(0.0, 1.0, 2.0), about_z(-FRAC_PI_4)),
+        ];
+        let expected = Pose::new(Vector3::new(1.0, 1.0, 1.0), Quaternion::identity());
+        assert_poses_approx_eq(&Pose::average(&poses).unwrap(), &expected);
+    }
+}
@@ -0,0 +1,184 @@
+use nalgebra::{Quaternion, Vector3};
+
+// Rounds each component to the index of the nearest multiple of the grid size.
+fn quantize<const N: usize>(components: [f64; N], grid: f64) -> [i64; N] {
+    assert!(
+        grid.is_finite() && grid > 0.0,
+        "quantization grid size must be positive and finite, got {grid}"
+    );
+    components.map(|component| (component / grid).round() as i64)
+}
+
+/// A vector whose components have been snapped to a grid so that it can be compared exactly
+/// and used as a map key. Two quantized vectors are equal, and hash equally, exactly when
+/// they share a grid size and every one of their components rounds to the same multiple of
+/// it. Vectors closer together than the grid size are usually equal, but can land in
+/// neighboring cells when they straddle a cell boundary; vectors further apart than the grid
+/// size never collide. NaN components quantize to 0 and out of range ones saturate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QuantizedVector3 {
+    cells: [i64; 3],
+    grid_bits: u64,
+}
+
+impl QuantizedVector3 {
+    /// Quantizes the vector to a grid of the given size, which must be positive and finite.
+    pub fn new(vector: &Vector3<f64>, grid: f64) -> Self {
+        Self {
+            cells: quantize([vector.x, vector.y, vector.z], grid),
+            grid_bits: grid.to_bits(),
+        }
+    }
+
+    /// Returns the grid size the vector was quantized to.
+    pub fn grid(&self) -> f64 {
+        f64::from_bits(self.grid_bits)
+    }
+
+    /// Returns the vector at the center of the quantized vector's grid cell.
+    pub fn to_vector3(&self) -> Vector3<f64> {
+        let [x, y, z] = self.cells.map(|cell| cell as f64 * self.grid());
+        Vector3::new(x, y, z)
+    }
+}
+
+/// A quaternion whose components have been snapped to a grid so that it can be compared
+/// exactly and used as a map key, with the same equality and collision semantics as
+/// QuantizedVector3. Because q and -q describe the same rotation, the quantized quaternion is
+/// negated when needed so that its first non-zero cell, in (w, i, j, k) order, is positive.
+/// It isn't normalized, so quaternions should be normalized beforehand for equal rotations to
+/// quantize equally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QuantizedQuaternion {
+    cells: [i64; 4],
+    grid_bits: u64,
+}
+
+impl QuantizedQuaternion {
+    /// Quantizes the quaternion to a grid of the given size, which must be positive and
+    /// finite.
+    pub fn new(quat: &Quaternion<f64>, grid: f64) -> Self {
+        // the sign is picked from the cells rather than the raw components, so that a
+        // component too small to leave its zero cell can't split q and -q apart
+        let cells = quantize([quat.w, quat.i, quat.j, quat.k], grid);
+        let negate = cells
+            .iter()
+            .find(|cell| **cell != 0)
+            .is_some_and(|cell| *cell < 0);
+        let cells = if negate {
+            cells.map(i64::saturating_neg)
+        } else {
+            cells
+        };
+        Self {
+            cells,
+            grid_bits: grid.to_bits(),
+        }
+    }
+
+    /// Returns the grid size the quaternion was quantized to.
+    pub fn grid(&self) -> f64 {
+        f64::from_bits(self.grid_bits)
+    }
+
+    /// Returns the quaternion at the center of the quantized quaternion's grid cell.
+    pub fn to_quaternion(&self) -> Quaternion<f64> {
+        let [w, i, j, k] = self.cells.map(|cell| cell as f64 * self.grid());
+        Quaternion::new(w, i, j, k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Quaternion, Vector3};
+    use std::collections::HashMap;
+
+    use super::{QuantizedQuaternion, QuantizedVector3};
+
+    #[test]
+    fn quantized_vectors_within_grid_are_equal() {
+        let grid = 0.01;
+        let mut cache = HashMap::new();
+        cache.insert(
+            QuantizedVector3::new(&Vector3::new(1.0, 2.0, 3.0), grid),
+            "a",
+        );
+
+        let nearby = QuantizedVector3::new(&Vector3::new(1.0004, 1.9996, 3.0001), grid);
+        assert_eq!(cache.get(&nearby), Some(&"a"));
+        assert_eq!(nearby.to_vector3(), Vector3::new(1.0, 2.0, 3.0));
+
+        let distinct = [
+            Vector3::new(1.01, 2.0, 3.0),
+            Vector3::new(1.0, 1.99, 3.0),
+            Vector3::new(1.0, 2.0, 3.02),
+            Vector3::new(-1.0, 2.0, 3.0),
+        ];
+        for vector in distinct {
+            let quantized = QuantizedVector3::new(&vector, grid);
+            assert!(!cache.contains_key(&quantized), "{vector:?}");
+            cache.insert(quantized, "b");
+        }
+        assert_eq!(cache.len(), 5);
+
+        // the same vector on a different grid is a different key
+        let coarse = QuantizedVector3::new(&Vector3::new(1.0, 2.0, 3.0), 0.5);
+        assert!(!cache.contains_key(&coarse));
+        assert_eq!(coarse.grid(), 0.5);
+    }
+
+    #[test]
+    fn quantized_quaternions_within_grid_are_equal() {
+        let grid = 0.001;
+        let quat = Quaternion::new(0.5, 0.5, -0.5, 0.5);
+        let mut cache = HashMap::new();
+        cache.insert(QuantizedQuaternion::new(&quat, grid), "a");
+
+        let nearby = Quaternion::new(0.5002, 0.4999, -0.5001, 0.5);
+        assert_eq!(
+            cache.get(&QuantizedQuaternion::new(&nearby, grid)),
+            Some(&"a")
+        );
+        // q and -q are the same rotation
+        assert_eq!(
+            cache.get(&QuantizedQuaternion::new(&-quat, grid)),
+            Some(&"a")
+        );
+        assert_eq!(QuantizedQuaternion::new(&-quat, grid).to_quaternion(), quat);
+
+        let distinct = [
+            Quaternion::new(0.5, 0.5, 0.5, 0.5),
+            Quaternion::new(0.502, 0.5, -0.5, 0.5),
+            Quaternion::new(0.0, -1.0, 0.0, 0.0),
+            Quaternion::new(1.0, 0.0, 0.0, 0.0),
+        ];
+        for quat in distinct {
+            let quantized = QuantizedQuaternion::new(&quat, grid);
+            assert!(!cache.contains_key(&quantized), "{quat:?}");
+            cache.insert(quantized, "b");
+        }
+        assert_eq!(cache.len(), 5);
+    }
+
+    #[test]
+    fn quaternions_with_a_vanishing_first_component_quantize_equally() {
+        let grid = 0.001;
+        let quat = Quaternion::new(1e-17, 0.6, -0.8, 0.0);
+        let quantized = QuantizedQuaternion::new(&quat, grid);
+        assert_eq!(QuantizedQuaternion::new(&-quat, grid), quantized);
+        assert_eq!(
+            QuantizedQuaternion::new(&Quaternion::new(-1e-17, 0.6, -0.8, 0.0), grid),
+            quantized
+        );
+        assert_eq!(
+            quantized.to_quaternion(),
+            Quaternion::new(0.0, 0.6, -0.8, 0.0)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn quantizing_to_an_empty_grid_panics() {
+        QuantizedVector3::new(&Vector3::zeros(), 0.0);
+    }
+}
@@ -0,0 +1,155 @@
+use nalgebra::{Quaternion, Vector3};
+
+use super::pose::Pose;
+use super::utils::rotate_vector_by_quaternion;
+
+/// A similarity transform made up of a translation, an orientation and a uniform scale.
+/// Applying a similarity to a point rotates the point by the orientation, scales it and
+/// then translates it. A similarity with a scale of one is a rigid transform, like a pose.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Similarity {
+    pub translation: Vector3<f64>,
+    pub orientation: Quaternion<f64>,
+    pub scale: f64,
+}
+
+impl Similarity {
+    /// Creates a similarity from a translation, an orientation and a scale. The orientation
+    /// is normalized so that it represents a pure rotation.
+    pub fn new(translation: Vector3<f64>, orientation: Quaternion<f64>, scale: f64) -> Self {
+        Similarity {
+            translation,
+            orientation: orientation.normalize(),
+            scale,
+        }
+    }
+
+    /// Returns the similarity resulting from applying `other` within the frame of this one
+    /// (i.e. `other` is applied first, followed by `self`).
+    pub fn compose(&self, other: &Similarity) -> Similarity {
+        Similarity {
+            translation: self.translation
+                + self.scale * rotate_vector_by_quaternion(&self.orientation, &other.translation),
+            orientation: (self.orientation * other.orientation).normalize(),
+            scale: self.scale * other.scale,
+        }
+    }
+
+    /// Returns the similarity that undoes this one, such that composing the two yields the
+    /// identity. Returns None if the scale is zero, as every point is then collapsed onto
+    /// the translation.
+    pub fn inverse(&self) -> Option<Similarity> {
+        if self.scale == 0.0 {
+            return None;
+        }
+        let orientation = self.orientation.conjugate();
+        let scale = 1.0 / self.scale;
+        Some(Similarity {
+            translation: -scale * rotate_vector_by_quaternion(&orientation, &self.translation),
+            orientation,
+            scale,
+        })
+    }
+
+    /// Returns the point moved by this similarity, rotated by the orientation, scaled and
+    /// then translated.
+    pub fn apply_to_point(&self, point: Vector3<f64>) -> Vector3<f64> {
+        self.translation + self.scale * rotate_vector_by_quaternion(&self.orientation, &point)
+    }
+}
+
+impl From<Pose> for Similarity {
+    fn from(pose: Pose) -> Self {
+        Similarity {
+            translation: pose.translation,
+            orientation: pose.orientation,
+            scale: 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{Quaternion, Vector3};
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use super::Similarity;
+    use crate::spatialmath::pose::Pose;
+
+    fn assert_vectors_approx_eq(vec1: Vector3<f64>, vec2: Vector3<f64>) {
+        assert_approx_eq!(f64, (vec1 - vec2).norm(), 0.0, epsilon = 0.0001);
+    }
+
+    // 90 degrees about the z axis
+    fn quarter_turn() -> Pose {
+        Pose::new(
+            Vector3::new(-4.0, 0.5, 2.0),
+            Quaternion::new(FRAC_1_SQRT_2, 0.0, 0.0, FRAC_1_SQRT_2),
+        )
+    }
+
+    fn skewed() -> Pose {
+        Pose::new(
+            Vector3::new(1.0, -2.0, 3.5),
+            Quaternion::new(0.0436194, 0.3710372, 0.5565558, 0.7420744),
+        )
+    }
+
+    #[test]
+    fn similarity_applied_to_point_rotates_scales_then_translates() {
+        let similarity = Similarity {
+            scale: 2.0,
+            ..quarter_turn().into()
+        };
+        let point = similarity.apply_to_point(Vector3::new(1.0, 0.0, 0.0));
+        assert_vectors_approx_eq(point, Vector3::new(-4.0, 2.5, 2.0));
+
+        let back = similarity.inverse().unwrap().apply_to_point(point);
+        assert_vectors_approx_eq(back, Vector3::new(1.0, 0.0, 0.0));
+        assert!(
+            Similarity::new(Vector3::zeros(), Quaternion::identity(), 0.0)
+                .inverse()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn composing_similarities_matches_applying_them_in_turn() {
+        let outer = Similarity {
+            scale: 2.0,
+            ..quarter_turn().into()
+        };
+        let inner = Similarity {
+            scale: 0.25,
+            ..skewed().into()
+        };
+        let composed = outer.compose(&inner);
+        assert_approx_eq!(f64, composed.scale, 0.5, epsilon = 0.0001);
+
+        let p = Vector3::new(0.3, 0.7, -1.1);
+        let in_turn = outer.apply_to_point(inner.apply_to_point(p));
+        assert_vectors_approx_eq(composed.apply_to_point(p), in_turn);
+
+        // undoing the composition returns the point
+        let undone = composed.inverse().unwrap().apply_to_point(in_turn);
+        assert_vectors_approx_eq(undone, p);
+    }
+
+    #[test]
+    fn unit_scale_similarity_matches_pose() {
+        let (pose, other) = (skewed(), quarter_turn());
+        let similarity = Similarity::from(pose);
+        let p = Vector3::new(0.3, 0.7, -1.1);
+        assert_vectors_approx_eq(similarity.apply_to_point(p), pose.apply_to_point(p));
+        assert_vectors_approx_eq(
+            similarity.inverse().unwrap().apply_to_point(p),
+            pose.inverse().apply_to_point(p),
+        );
+        assert_vectors_approx_eq(
+            similarity.compose(&other.into()).apply_to_point(p),
+            pose.compose(&other).apply_to_point(p),
+        );
+    }
+}
@@ -1,7 +1,15 @@
 use float_cmp::{ApproxEq, F64Margin};
-use nalgebra::{Quaternion, UnitQuaternion, UnitVector3, Vector3};
+use nalgebra::{Matrix4, Quaternion, SymmetricEigen, UnitQuaternion, UnitVector3, Vector3};
+use rand::Rng;
+use std::{f64::consts::PI, fmt};
 
 const ANGLE_ACCEPTANCE: f64 = 0.0001;
+// How small the real part of a normalized quaternion can get, relative to 1, before its
+// Rodrigues parameters (which grow as tan(angle / 2)) are treated as undefined.
+const RODRIGUES_MIN_REAL: f64 = 1e-9;
+// How small the squared norm of a quaternion can get before it's treated as zero and so
+// having no inverse.
+const INVERSE_MIN_NORM_SQUARED: f64 = 1e-12;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -100,6 +108,34 @@ impl AxisAngle {
             theta,
         }
     }
+
+    /// Returns the rotation vector (the axis scaled by theta) for this axis angle.
+    /// The axis is expected to be a unit vector.
+    pub fn to_rotation_vector(&self) -> Vector3<f64> {
+        self.axis * self.theta
+    }
+
+    /// Builds an axis angle from a rotation vector, whose magnitude is the rotation
+    /// angle and whose direction is the axis. A zero vector gives a zero axis angle.
+    pub fn from_rotation_vector(rotation_vector: &Vector3<f64>) -> Self {
+        let theta = rotation_vector.norm();
+        if theta == 0.0 {
+            return Self::new(0.0, 0.0, 0.0, 0.0);
+        }
+        Self {
+            axis: rotation_vector / theta,
+            theta,
+        }
+    }
+
+    /// Converts this axis angle into a unit quaternion, normalizing the axis first. A zero
+    /// axis has no direction to rotate about, so it gives the identity quaternion.
+    pub fn to_quaternion(&self) -> Quaternion<f64> {
+        match UnitVector3::try_new(self.axis, 0.0) {
+            Some(axis) => *UnitQuaternion::from_axis_angle(&axis, self.theta).quaternion(),
+            None => Quaternion::identity(),
+        }
+    }
 }
 
 impl TryFrom<Quaternion<f64>> for AxisAngle {
@@ -118,11 +154,46 @@ impl TryFrom<Quaternion<f64>> for AxisAngle {
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "OrientationVectorFields", into = "OrientationVectorFields")
+)]
 pub struct OrientationVector {
     pub o_vector: UnitVector3<f64>,
     pub theta: f64,
 }
 
+// The form orientation vectors take in robot configs. Deserializing goes through
+// OrientationVector::new so the vector comes out normalized.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct OrientationVectorFields {
+    x: f64,
+    y: f64,
+    z: f64,
+    theta: f64,
+}
+
+#[cfg(feature = "serde")]
+impl From<OrientationVectorFields> for OrientationVector {
+    fn from(fields: OrientationVectorFields) -> Self {
+        Self::new(fields.x, fields.y, fields.z, fields.theta)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<OrientationVector> for OrientationVectorFields {
+    fn from(ov: OrientationVector) -> Self {
+        Self {
+            x: ov.o_vector.x,
+            y: ov.o_vector.y,
+            z: ov.o_vector.z,
+            theta: ov.theta,
+        }
+    }
+}
+
 impl OrientationVector {
     pub fn new(o_x: f64, o_y: f64, o_z: f64, theta: f64) -> Self {
         let o_vector = UnitVector3::new_normalize(Vector3::new(o_x, o_y, o_z));
@@ -235,12 +306,393 @@ pub fn rotate_vector_by_quaternion(quat: &Quaternion<f64>, vector: &Vector3<f64>
         + (2.0 * quat_real) * quat_vec.cross(vector)
 }
 
+/// Rotates a point by a (unit) quaternion about a pivot point rather than the origin, i.e.
+/// translates the pivot to the origin, rotates, and translates back.
+pub fn rotate_vector_around_point(
+    vector: &Vector3<f64>,
+    pivot: &Vector3<f64>,
+    quat: &Quaternion<f64>,
+) -> Vector3<f64> {
+    pivot + rotate_vector_by_quaternion(quat, &(vector - pivot))
+}
+
+/// Returns whether two quaternions represent the same rotation to within epsilon. Because
+/// q and -q describe the same rotation, this compares the normalized quaternions against
+/// each other both as given and with one of them negated.
+pub fn quaternions_same_rotation(
+    quat1: &Quaternion<f64>,
+    quat2: &Quaternion<f64>,
+    epsilon: f64,
+) -> bool {
+    let quat1 = quat1.normalize();
+    let quat2 = quat2.normalize();
+    let within_epsilon = |diff: Quaternion<f64>| {
+        diff.coords
+            .iter()
+            .all(|component| component.abs() <= epsilon)
+    };
+    within_epsilon(quat1 - quat2) || within_epsilon(quat1 + quat2)
+}
+
+/// Returns the angle in radians, in the range (-pi, pi], that the quaternion rotates about
+/// the given axis. This is the angle of the twist in the swing-twist decomposition of the
+/// rotation around that axis. The twist is undefined when the rotation turns the axis
+/// completely around, in which case 0 is returned.
+pub fn quaternion_angle_about_axis(quat: &Quaternion<f64>, axis: &Vector3<f64>) -> f64 {
+    let axis = axis.normalize();
+    let projection = quat.imag().dot(&axis);
+    if projection == 0.0 && quat.w == 0.0 {
+        return 0.0;
+    }
+    let angle = 2.0 * projection.atan2(quat.w);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else if angle <= -PI {
+        angle + 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+/// Returns the heading (yaw) of the quaternion, i.e. its twist about the Z axis. In the
+/// Tait-Bryan convention, yaw is the rotation applied about the vertical axis. This only
+/// agrees with the yaw of EulerAngles for rotations that have no roll or pitch.
+pub fn quaternion_heading(quat: &Quaternion<f64>) -> f64 {
+    quaternion_angle_about_axis(quat, &Vector3::z())
+}
+
+/// Returns the pitch of the quaternion, i.e. its twist about the Y axis in the Tait-Bryan
+/// convention.
+pub fn quaternion_pitch(quat: &Quaternion<f64>) -> f64 {
+    quaternion_angle_about_axis(quat, &Vector3::y())
+}
+
+/// Returns the roll of the quaternion, i.e. its twist about the X axis in the Tait-Bryan
+/// convention.
+pub fn quaternion_roll_about_x(quat: &Quaternion<f64>) -> f64 {
+    quaternion_angle_about_axis(quat, &Vector3::x())
+}
+
+/// Returns the average of the rotations described by the quaternions, weighting each one by
+/// the corresponding entry in weights. This uses Markley's method: the result is the
+/// eigenvector with the largest eigenvalue of the weighted sum of the outer products of the
+/// normalized quaternions, which doesn't depend on the sign of any of them. Returns None if
+/// there are no quaternions, the lengths of the slices differ or the weights don't sum to
+/// a positive value.
+pub fn quaternion_weighted_average(
+    quats: &[Quaternion<f64>],
+    weights: &[f64],
+) -> Option<Quaternion<f64>> {
+    if quats.is_empty() || quats.len() != weights.len() || weights.iter().sum::<f64>() <= 0.0 {
+        return None;
+    }
+    let accumulated = quats
+        .iter()
+        .zip(weights)
+        .fold(Matrix4::zeros(), |acc, (quat, weight)| {
+            let coords = quat.normalize().coords;
+            acc + coords * coords.transpose() * *weight
+        });
+    let eigen = SymmetricEigen::new(accumulated);
+    let largest = eigen.eigenvalues.imax();
+    let average = Quaternion::from(eigen.eigenvectors.column(largest).into_owned());
+    // q and -q are the same rotation, so settle on the one with a non-negative real part
+    Some(if average.w < 0.0 { -average } else { average })
+}
+
+/// Returns the average of the rotations described by the quaternions, weighting them
+/// equally. See quaternion_weighted_average.
+pub fn quaternion_average(quats: &[Quaternion<f64>]) -> Option<Quaternion<f64>> {
+    quaternion_weighted_average(quats, &vec![1.0; quats.len()])
+}
+
+/// Returns the multiplicative inverse of the quaternion, its conjugate divided by its squared
+/// norm, so that multiplying the two gives the identity. Unlike the conjugate, this holds for
+/// quaternions that aren't normalized. None is returned when the squared norm is within 1e-12
+/// of zero, as the quaternion then has no inverse.
+pub fn quaternion_inverse(quat: &Quaternion<f64>) -> Option<Quaternion<f64>> {
+    let norm_squared = quat.norm_squared();
+    if norm_squared < INVERSE_MIN_NORM_SQUARED {
+        return None;
+    }
+    Some(quat.conjugate() / norm_squared)
+}
+
+/// Returns the Rodrigues (Gibbs) parameters of the rotation described by the quaternion:
+/// its vector part divided by its real part, which is the rotation axis scaled by
+/// tan(angle / 2). The parameters are singular for rotations by pi radians (180 degrees), where
+/// the real part is zero, so None is returned for rotations within about 1e-9 radians of pi.
+pub fn quaternion_to_rodrigues(quat: &Quaternion<f64>) -> Option<Vector3<f64>> {
+    let quat = quat.normalize();
+    if quat.w.abs() < RODRIGUES_MIN_REAL {
+        return None;
+    }
+    Some(quat.imag() / quat.w)
+}
+
+/// Returns the unit quaternion, with a positive real part, of the rotation described by
+/// Rodrigues (Gibbs) parameters. See quaternion_to_rodrigues.
+pub fn quaternion_from_rodrigues(rodrigues: &Vector3<f64>) -> Quaternion<f64> {
+    Quaternion::from_parts(1.0, *rodrigues).normalize()
+}
+
+/// Returns a random unit quaternion drawn uniformly from all rotations, using Shoemake's
+/// method of sampling SO(3).
+pub fn random_uniform_quaternion<R: Rng + ?Sized>(rng: &mut R) -> Quaternion<f64> {
+    let (u1, u2, u3): (f64, f64, f64) = rng.gen();
+    let (low, high) = ((1.0 - u1).sqrt(), u1.sqrt());
+    let (sin2, cos2) = (2.0 * PI * u2).sin_cos();
+    let (sin3, cos3) = (2.0 * PI * u3).sin_cos();
+    Quaternion::new(high * cos3, low * sin2, low * cos2, high * sin3)
+}
+
+/// Advances an orientation by an angular velocity, in radians per second about the axes of
+/// the orientation's own (body) frame as reported by an IMU, held for dt seconds. The
+/// orientation is composed with the small rotation exp(0.5 * omega * dt) and re-normalized so
+/// that integrating many samples doesn't drift away from a unit quaternion.
+pub fn integrate_angular_velocity(
+    quat: &Quaternion<f64>,
+    omega: &Vector3<f64>,
+    dt: f64,
+) -> Quaternion<f64> {
+    let step = Quaternion::from_imag(omega * (0.5 * dt)).exp();
+    (quat * step).normalize()
+}
+
+/// Returns the constant angular velocity, in radians per second about the axes of the
+/// orientation's own (body) frame, that carries quat to target in dt seconds, making it the
+/// inverse of integrate_angular_velocity. The velocity is twice the logarithm of the relative
+/// rotation from quat to target (its rotation vector) divided by dt, taking the shorter way
+/// around. Returns the zero vector if dt is not positive.
+pub fn angular_velocity_to(
+    quat: &Quaternion<f64>,
+    target: &Quaternion<f64>,
+    dt: f64,
+) -> Vector3<f64> {
+    if dt <= 0.0 {
+        return Vector3::zeros();
+    }
+    let mut relative = quat.normalize().conjugate() * target.normalize();
+    if relative.w < 0.0 {
+        relative = -relative;
+    }
+    UnitQuaternion::new_unchecked(relative).scaled_axis() / dt
+}
+
+/// Spherically interpolates between the rotations of two quaternions, returning the unit
+/// quaternion that is the fraction t of the way from quat to other at a constant angular
+/// velocity. Both quaternions are normalized first, and other is negated if need be so that
+/// the interpolation takes the shorter way around. When the rotations are within
+/// ANGLE_ACCEPTANCE radians of each other, the normalized linear interpolation between them
+/// is returned instead, to avoid dividing by the sine of a vanishing angle.
+pub fn quaternion_slerp(
+    quat: &Quaternion<f64>,
+    other: &Quaternion<f64>,
+    t: f64,
+) -> Quaternion<f64> {
+    let quat = quat.normalize();
+    let mut other = other.normalize();
+    let mut dot = quat.dot(&other);
+    if dot < 0.0 {
+        other = -other;
+        dot = -dot;
+    }
+    let angle = dot.min(1.0).acos();
+    if angle < ANGLE_ACCEPTANCE {
+        return quat.lerp(&other, t).normalize();
+    }
+    let sin_angle = angle.sin();
+    let from_weight = ((1.0 - t) * angle).sin() / sin_angle;
+    let to_weight = (t * angle).sin() / sin_angle;
+    (quat * from_weight + other * to_weight).normalize()
+}
+
+/// Converts a quaternion into the 3x3 rotation matrix of the same rotation, returned in
+/// row-major order (so that `matrix[row][column]`). The quaternion is normalized first.
+pub fn quaternion_to_rotation_matrix(quat: &Quaternion<f64>) -> [[f64; 3]; 3] {
+    let quat = quat.normalize();
+    let (w, i, j, k) = (quat.w, quat.i, quat.j, quat.k);
+    [
+        [
+            1.0 - 2.0 * (j * j + k * k),
+            2.0 * (i * j - k * w),
+            2.0 * (i * k + j * w),
+        ],
+        [
+            2.0 * (i * j + k * w),
+            1.0 - 2.0 * (i * i + k * k),
+            2.0 * (j * k - i * w),
+        ],
+        [
+            2.0 * (i * k - j * w),
+            2.0 * (j * k + i * w),
+            1.0 - 2.0 * (i * i + j * j),
+        ],
+    ]
+}
+
+/// Returns the angle in radians, in the range [0, pi], of the smallest rotation that carries
+/// the rotation of quat to that of other. The quaternions are normalized first.
+pub fn quaternion_angular_distance(quat: &Quaternion<f64>, other: &Quaternion<f64>) -> f64 {
+    let dot = quat.normalize().dot(&other.normalize()).abs();
+    2.0 * dot.min(1.0).acos()
+}
+
+/// Limits how far the rotation of quat may be from that of reference. If the angular
+/// distance between them is more than max_angle radians, the rotation max_angle radians from
+/// reference towards quat (by slerp) is returned; otherwise quat is returned as is.
+pub fn quaternion_clamp_angle(
+    quat: &Quaternion<f64>,
+    reference: &Quaternion<f64>,
+    max_angle: f64,
+) -> Quaternion<f64> {
+    let distance = quaternion_angular_distance(quat, reference);
+    if distance <= max_angle {
+        return *quat;
+    }
+    quaternion_slerp(reference, quat, max_angle.max(0.0) / distance)
+}
+
+/// Interpolates between the directions of two vectors along the great circle joining them,
+/// returning a unit vector that is the fraction t of the way from the direction of from to
+/// the direction of to. The magnitudes of the vectors are ignored. When the directions are
+/// opposite, any great circle joins them, and the one through the coordinate axis least
+/// aligned with from is used. Returns the zero vector if either vector is zero.
+pub fn vector_direction_slerp(from: &Vector3<f64>, to: &Vector3<f64>, t: f64) -> Vector3<f64> {
+    let (from, to) = match (from.try_normalize(0.0), to.try_normalize(0.0)) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return Vector3::zeros(),
+    };
+    let angle = from.dot(&to).clamp(-1.0, 1.0).acos();
+    let axis = if PI - angle < 1e-9 {
+        let least_aligned = if from.x.abs() < 0.9 {
+            Vector3::x()
+        } else {
+            Vector3::y()
+        };
+        from.cross(&least_aligned)
+    } else {
+        from.cross(&to)
+    };
+    match UnitVector3::try_new(axis, 1e-12) {
+        Some(axis) => UnitQuaternion::from_axis_angle(&axis, angle * t) * from,
+        // the directions are the same
+        None => from,
+    }
+}
+
+/// Converts a vector from Cartesian coordinates into spherical coordinates, returned as
+/// (radius, azimuth, elevation). The azimuth is the angle in radians, in the range
+/// (-pi, pi], from the positive X axis towards the positive Y axis of the vector's
+/// projection onto the XY plane. The elevation is the angle in radians, in the range
+/// [-pi/2, pi/2], from the XY plane towards the positive Z axis. Both angles are undefined
+/// for a zero vector, in which case they are returned as 0.
+pub fn vector_to_spherical(vector: &Vector3<f64>) -> (f64, f64, f64) {
+    let radius = vector.norm();
+    if radius == 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let azimuth = vector.y.atan2(vector.x);
+    let elevation = (vector.z / radius).clamp(-1.0, 1.0).asin();
+    (radius, azimuth, elevation)
+}
+
+/// Converts spherical coordinates into a Cartesian vector. The angles follow the
+/// conventions described in vector_to_spherical.
+pub fn vector_from_spherical(radius: f64, azimuth: f64, elevation: f64) -> Vector3<f64> {
+    let planar = radius * elevation.cos();
+    Vector3::new(
+        planar * azimuth.cos(),
+        planar * azimuth.sin(),
+        radius * elevation.sin(),
+    )
+}
+
+/// Computes the axis-aligned bounding box of a set of points, returned as its (min, max)
+/// corners, whose components are the smallest and largest of the points' components.
+/// Returns None if there are no points.
+pub fn vector_bounding_box(points: &[Vector3<f64>]) -> Option<(Vector3<f64>, Vector3<f64>)> {
+    let (first, rest) = points.split_first()?;
+    Some(rest.iter().fold((*first, *first), |(min, max), point| {
+        (min.inf(point), max.sup(point))
+    }))
+}
+
+/// The ways a string of comma separated components can fail to parse into a vector or
+/// quaternion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseComponentsError {
+    /// The string had a different number of components than expected.
+    WrongCount { expected: usize, found: usize },
+    /// The component at the (zero-based) index isn't a number.
+    InvalidNumber { index: usize, component: String },
+}
+
+impl fmt::Display for ParseComponentsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongCount { expected, found } => {
+                write!(f, "expected {expected} components but found {found}")
+            }
+            Self::InvalidNumber { index, component } => {
+                write!(f, "component {index} ({component:?}) is not a number")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseComponentsError {}
+
+fn parse_components<const N: usize>(s: &str) -> Result<[f64; N], ParseComponentsError> {
+    let components: Vec<&str> = s.split(',').map(str::trim).collect();
+    if components.len() != N {
+        return Err(ParseComponentsError::WrongCount {
+            expected: N,
+            found: components.len(),
+        });
+    }
+    let mut parsed = [0.0; N];
+    for (index, component) in components.into_iter().enumerate() {
+        parsed[index] = component
+            .parse()
+            .map_err(|_| ParseComponentsError::InvalidNumber {
+                index,
+                component: component.to_string(),
+            })?;
+    }
+    Ok(parsed)
+}
+
+/// Parses a vector from its comma separated x, y and z components, e.g. "1.0, 2.0, 3.0".
+/// Whitespace around the components is ignored.
+pub fn parse_vector3(s: &str) -> Result<Vector3<f64>, ParseComponentsError> {
+    let [x, y, z] = parse_components(s)?;
+    Ok(Vector3::new(x, y, z))
+}
+
+/// Parses a quaternion from its comma separated components in Real-I-J-K (w, x, y, z) order,
+/// e.g. "1, 0, 0, 0". Whitespace around the components is ignored, and the quaternion isn't
+/// normalized.
+pub fn parse_quaternion(s: &str) -> Result<Quaternion<f64>, ParseComponentsError> {
+    let [w, x, y, z] = parse_components(s)?;
+    Ok(Quaternion::new(w, x, y, z))
+}
+
 #[cfg(test)]
 mod tests {
     use float_cmp::assert_approx_eq;
-    use nalgebra::{Quaternion, Vector3};
+    use nalgebra::{Quaternion, UnitQuaternion, Vector3};
 
-    use super::{rotate_vector_by_quaternion, EulerAngles, OrientationVector};
+    use super::{
+        angular_velocity_to, integrate_angular_velocity, parse_quaternion, parse_vector3,
+        quaternion_angle_about_axis, quaternion_angular_distance, quaternion_average,
+        quaternion_clamp_angle, quaternion_from_rodrigues, quaternion_heading, quaternion_inverse,
+        quaternion_pitch, quaternion_roll_about_x, quaternion_slerp, quaternion_to_rodrigues,
+        quaternion_to_rotation_matrix, quaternion_weighted_average, quaternions_same_rotation,
+        random_uniform_quaternion, rotate_vector_around_point, rotate_vector_by_quaternion,
+        vector_bounding_box, vector_direction_slerp, vector_from_spherical, vector_to_spherical,
+        AxisAngle, EulerAngles, OrientationVector, ParseComponentsError,
+    };
 
     fn get_quaternion_diff_norm(quat1: &Quaternion<f64>, quat2: &Quaternion<f64>) -> f64 {
         let quat_diff = quat1.coords - quat2.coords;
@@ -306,6 +758,24 @@ mod tests {
         assert_approx_eq!(OrientationVector, calc_ov8, expected_ov8, epsilon = 0.0001);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn orientation_vector_serde_round_trip_normalizes() {
+        let ov: OrientationVector =
+            serde_json::from_str(r#"{"x": 0.0, "y": 3.0, "z": 4.0, "theta": 1.5}"#).unwrap();
+        assert_approx_eq!(f64, ov.o_vector.norm(), 1.0);
+        let expected = OrientationVector::new(0.0, 0.6, 0.8, 1.5);
+        assert_approx_eq!(OrientationVector, ov, expected);
+
+        let json = serde_json::to_value(ov).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"x": 0.0, "y": ov.o_vector.y, "z": ov.o_vector.z, "theta": 1.5})
+        );
+        let round_trip: OrientationVector = serde_json::from_value(json).unwrap();
+        assert_approx_eq!(OrientationVector, round_trip, ov);
+    }
+
     #[test]
     fn orientation_vector_to_quaternion_works() {
         let ov = OrientationVector::new(0.0, -1.0, 0.0, 1.5707963267948966);
@@ -410,4 +880,559 @@ mod tests {
         let diff = get_vector_diff_norm(&expected_vector2, &rotated_vector2);
         assert_approx_eq!(f64, diff, 0.0, epsilon = 0.0001);
     }
+
+    #[test]
+    fn quaternions_same_rotation_works() {
+        let quat = Quaternion::new(0.5, 0.5, -0.5, 0.5);
+        assert!(quaternions_same_rotation(&quat, &quat, 1e-9));
+        assert!(quaternions_same_rotation(&quat, &-quat, 1e-9));
+        assert!(quaternions_same_rotation(&quat, &(quat * 3.0), 1e-9));
+
+        let other = Quaternion::new(0.5, -0.5, 0.5, 0.5);
+        assert!(!quaternions_same_rotation(&quat, &other, 1e-9));
+        assert!(!quaternions_same_rotation(&quat, &-other, 1e-9));
+    }
+
+    #[test]
+    fn axis_angle_rotation_vector_round_trip_works() {
+        let axis_angles = [
+            AxisAngle::new(1.0, 0.0, 0.0, 0.5),
+            AxisAngle::new(0.0, -1.0, 0.0, std::f64::consts::PI),
+            AxisAngle::new(0.6, 0.0, 0.8, 2.0),
+        ];
+        for axis_angle in axis_angles {
+            let rotation_vector = axis_angle.to_rotation_vector();
+            assert_approx_eq!(f64, rotation_vector.norm(), axis_angle.theta);
+
+            let round_trip = AxisAngle::from_rotation_vector(&rotation_vector);
+            assert_approx_eq!(f64, round_trip.theta, axis_angle.theta);
+            assert!(get_vector_diff_norm(&round_trip.axis, &axis_angle.axis) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn axis_angle_quaternion_round_trip_works() {
+        let axis_angles = [
+            AxisAngle::new(1.0, 0.0, 0.0, 0.5),
+            AxisAngle::new(0.0, -1.0, 0.0, 2.5),
+            AxisAngle::new(0.6, 0.0, 0.8, 2.0),
+        ];
+        for axis_angle in axis_angles {
+            let quat = axis_angle.to_quaternion();
+            assert_approx_eq!(f64, quat.norm(), 1.0);
+
+            let round_trip = AxisAngle::try_from(quat).unwrap();
+            assert_approx_eq!(f64, round_trip.theta, axis_angle.theta, epsilon = 1e-12);
+            assert!(get_vector_diff_norm(&round_trip.axis, &axis_angle.axis) < 1e-12);
+        }
+
+        // the axis is normalized
+        let scaled = AxisAngle::new(0.0, 0.0, 3.0, 1.0).to_quaternion();
+        let unit = AxisAngle::new(0.0, 0.0, 1.0, 1.0).to_quaternion();
+        assert!(get_quaternion_diff_norm(&scaled, &unit) < 1e-12);
+    }
+
+    #[test]
+    fn axis_angle_with_zero_axis_is_the_identity_quaternion() {
+        let quat = AxisAngle::new(0.0, 0.0, 0.0, 1.0).to_quaternion();
+        assert_eq!(quat, Quaternion::identity());
+    }
+
+    #[test]
+    fn axis_angle_from_zero_rotation_vector_works() {
+        let axis_angle = AxisAngle::from_rotation_vector(&Vector3::zeros());
+        assert_eq!(axis_angle.theta, 0.0);
+        assert_eq!(axis_angle.axis, Vector3::zeros());
+        assert_eq!(axis_angle.to_rotation_vector(), Vector3::zeros());
+    }
+
+    #[test]
+    fn angle_about_axis_single_axis_rotations_works() {
+        let angle = 0.9;
+        let about_x = *UnitQuaternion::from_axis_angle(&Vector3::x_axis(), angle).quaternion();
+        let about_y = *UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -angle).quaternion();
+        let about_z = *UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 3.0).quaternion();
+
+        assert_approx_eq!(f64, quaternion_roll_about_x(&about_x), angle);
+        assert_approx_eq!(f64, quaternion_pitch(&about_y), -angle);
+        assert_approx_eq!(f64, quaternion_heading(&about_z), 3.0);
+
+        // q and -q are the same rotation and so have the same twist
+        assert_approx_eq!(f64, quaternion_heading(&-about_z), 3.0);
+        // rotations about other axes have no twist
+        assert_approx_eq!(f64, quaternion_heading(&about_x), 0.0);
+        assert_approx_eq!(f64, quaternion_roll_about_x(&about_y), 0.0);
+
+        let axis = Vector3::new(1.0, 1.0, 1.0);
+        let about_axis = *UnitQuaternion::from_scaled_axis(axis.normalize() * angle).quaternion();
+        assert_approx_eq!(
+            f64,
+            quaternion_angle_about_axis(&about_axis, &(axis * 2.0)),
+            angle
+        );
+    }
+
+    #[test]
+    fn angle_about_axis_combined_rotation_works() {
+        // a twist about Z followed by a swing about the perpendicular X axis
+        let twist = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 0.7);
+        let swing = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.4);
+        let combined = *(swing * twist).quaternion();
+        assert_approx_eq!(f64, quaternion_heading(&combined), 0.7, epsilon = 1e-12);
+
+        let swing = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -1.1);
+        let combined = *(swing * twist).quaternion();
+        assert_approx_eq!(f64, quaternion_heading(&combined), 0.7, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn quaternion_weighted_average_works() {
+        let about_z =
+            |angle: f64| *UnitQuaternion::from_axis_angle(&Vector3::z_axis(), angle).quaternion();
+        let quats = [about_z(0.2), -about_z(0.4), about_z(0.6)];
+
+        let average = quaternion_average(&quats).unwrap();
+        assert!(quaternions_same_rotation(&average, &about_z(0.4), 1e-9));
+        let equally_weighted = quaternion_weighted_average(&quats, &[2.0, 2.0, 2.0]).unwrap();
+        assert!(quaternions_same_rotation(&equally_weighted, &average, 1e-9));
+
+        // a dominant weight pulls the average towards its quaternion
+        let weighted = quaternion_weighted_average(&quats, &[100.0, 1.0, 1.0]).unwrap();
+        let angle = quaternion_heading(&weighted);
+        assert!(angle > 0.2 && angle < 0.25, "{angle}");
+        let only_last = quaternion_weighted_average(&quats, &[0.0, 0.0, 1.0]).unwrap();
+        assert!(quaternions_same_rotation(&only_last, &about_z(0.6), 1e-9));
+
+        assert!(quaternion_average(&[]).is_none());
+        assert!(quaternion_weighted_average(&quats, &[1.0, 1.0]).is_none());
+        assert!(quaternion_weighted_average(&quats, &[0.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn spherical_coordinates_round_trip_works() {
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+        let (radius, azimuth, elevation) = vector_to_spherical(&Vector3::new(0.0, 2.0, 0.0));
+        assert_approx_eq!(f64, radius, 2.0);
+        assert_approx_eq!(f64, azimuth, FRAC_PI_2);
+        assert_approx_eq!(f64, elevation, 0.0);
+
+        let (_, azimuth, elevation) = vector_to_spherical(&Vector3::new(1.0, 0.0, 1.0));
+        assert_approx_eq!(f64, azimuth, 0.0);
+        assert_approx_eq!(f64, elevation, FRAC_PI_4);
+
+        let vectors = [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(-3.0, 4.0, 0.0),
+            Vector3::new(1.5, -2.5, 3.5),
+            Vector3::new(-1.0, -1.0, -1.0),
+            // the poles, where the azimuth is undefined
+            Vector3::new(0.0, 0.0, 5.0),
+            Vector3::new(0.0, 0.0, -5.0),
+        ];
+        for vector in vectors {
+            let (radius, azimuth, elevation) = vector_to_spherical(&vector);
+            assert_approx_eq!(f64, radius, vector.norm());
+            let round_trip = vector_from_spherical(radius, azimuth, elevation);
+            assert!(get_vector_diff_norm(&vector, &round_trip) < 1e-12);
+        }
+
+        let (_, _, north) = vector_to_spherical(&Vector3::new(0.0, 0.0, 5.0));
+        assert_approx_eq!(f64, north, FRAC_PI_2);
+        let (_, _, south) = vector_to_spherical(&Vector3::new(0.0, 0.0, -5.0));
+        assert_approx_eq!(f64, south, -FRAC_PI_2);
+
+        assert_eq!(vector_to_spherical(&Vector3::zeros()), (0.0, 0.0, 0.0));
+        assert_eq!(vector_from_spherical(0.0, 1.0, -0.5), Vector3::zeros());
+    }
+
+    #[test]
+    fn integrate_angular_velocity_matches_closed_form() {
+        let start = UnitQuaternion::from_euler_angles(0.2, -0.4, 1.1).into_inner();
+        let omega = Vector3::new(0.3, -1.2, 0.5);
+        let (steps, dt) = (1000, 0.002);
+
+        let mut quat = start;
+        for _ in 0..steps {
+            quat = integrate_angular_velocity(&quat, &omega, dt);
+        }
+        assert_approx_eq!(f64, quat.norm(), 1.0, epsilon = 1e-12);
+
+        let elapsed = steps as f64 * dt;
+        let expected = start * UnitQuaternion::from_scaled_axis(omega * elapsed).into_inner();
+        assert!(quaternions_same_rotation(&quat, &expected, 1e-9));
+
+        let unchanged = integrate_angular_velocity(&start, &omega, 0.0);
+        assert!(quaternions_same_rotation(&unchanged, &start, 1e-12));
+    }
+
+    #[test]
+    fn angular_velocity_to_integrates_back_to_target() {
+        let start = UnitQuaternion::from_euler_angles(0.2, -0.4, 1.1).into_inner();
+        let targets = [
+            UnitQuaternion::from_euler_angles(-0.7, 0.3, 2.5).into_inner(),
+            // the same rotation with the opposite sign still takes the shorter way around
+            -UnitQuaternion::from_euler_angles(0.25, -0.35, 1.0).into_inner(),
+            start,
+        ];
+        for target in targets {
+            for dt in [0.01, 0.5, 3.0] {
+                let omega = angular_velocity_to(&start, &target, dt);
+                assert!(omega.norm() * dt <= std::f64::consts::PI + 1e-12);
+                let reached = integrate_angular_velocity(&start, &omega, dt);
+                assert!(quaternions_same_rotation(&reached, &target, 1e-12));
+            }
+        }
+
+        let omega = Vector3::new(0.3, -1.2, 0.5);
+        let target = integrate_angular_velocity(&start, &omega, 0.1);
+        let recovered = angular_velocity_to(&start, &target, 0.1);
+        assert!(get_vector_diff_norm(&recovered, &omega) < 1e-18);
+
+        assert_eq!(angular_velocity_to(&start, &target, 0.0), Vector3::zeros());
+        assert_eq!(angular_velocity_to(&start, &target, -1.0), Vector3::zeros());
+    }
+
+    #[test]
+    fn rotate_vector_around_point_keeps_pivot_fixed() {
+        let quarter_turn_about_z =
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2)
+                .into_inner();
+        let pivot = Vector3::new(1.0, 2.0, 3.0);
+
+        let rotated =
+            rotate_vector_around_point(&Vector3::new(2.0, 2.0, 5.0), &pivot, &quarter_turn_about_z);
+        assert!(get_vector_diff_norm(&rotated, &Vector3::new(1.0, 3.0, 5.0)) < 1e-24);
+        // rotating about the origin instead gives a different point
+        let about_origin =
+            rotate_vector_by_quaternion(&quarter_turn_about_z, &Vector3::new(2.0, 2.0, 5.0));
+        assert!(get_vector_diff_norm(&about_origin, &Vector3::new(1.0, 3.0, 5.0)) > 1.0);
+
+        let unmoved = rotate_vector_around_point(&pivot, &pivot, &quarter_turn_about_z);
+        assert!(get_vector_diff_norm(&unmoved, &pivot) < 1e-24);
+    }
+
+    #[test]
+    fn rodrigues_parameters_round_trip() {
+        use std::f64::consts::PI;
+
+        let rotations = [
+            UnitQuaternion::identity(),
+            UnitQuaternion::from_euler_angles(0.2, -0.4, 1.1),
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 3.0),
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -2.5),
+        ];
+        for rotation in rotations {
+            let quat = rotation.into_inner();
+            let rodrigues = quaternion_to_rodrigues(&quat).unwrap();
+            assert_approx_eq!(
+                f64,
+                rodrigues.norm(),
+                (rotation.angle() / 2.0).tan(),
+                epsilon = 1e-9
+            );
+            let round_trip = quaternion_from_rodrigues(&rodrigues);
+            assert!(quaternions_same_rotation(&round_trip, &quat, 1e-12));
+            // the sign of the quaternion doesn't change the parameters
+            assert!(
+                get_vector_diff_norm(&quaternion_to_rodrigues(&-quat).unwrap(), &rodrigues) < 1e-24
+            );
+        }
+        assert_eq!(
+            quaternion_from_rodrigues(&Vector3::zeros()),
+            Quaternion::identity()
+        );
+
+        let half_turn = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), PI).into_inner();
+        assert_eq!(quaternion_to_rodrigues(&half_turn), None);
+        let nearly_half_turn =
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), PI - 1e-12).into_inner();
+        assert_eq!(quaternion_to_rodrigues(&nearly_half_turn), None);
+    }
+
+    #[test]
+    fn quaternion_inverse_undoes_multiplication() {
+        let quat = Quaternion::new(2.0, -1.0, 0.5, 3.0);
+        let inverse = quaternion_inverse(&quat).unwrap();
+        assert!(get_quaternion_diff_norm(&(quat * inverse), &Quaternion::identity()) < 1e-12);
+        assert!(get_quaternion_diff_norm(&(inverse * quat), &Quaternion::identity()) < 1e-12);
+        // for a unit quaternion the inverse is the conjugate
+        let unit = quat.normalize();
+        assert!(
+            get_quaternion_diff_norm(&quaternion_inverse(&unit).unwrap(), &unit.conjugate())
+                < 1e-12
+        );
+        assert_eq!(
+            quaternion_inverse(&Quaternion::new(0.0, 0.0, 0.0, 0.0)),
+            None
+        );
+        assert_eq!(
+            quaternion_inverse(&Quaternion::new(1e-7, 0.0, 0.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_vectors_and_quaternions() {
+        assert_eq!(
+            parse_vector3("1.0,2.0,3.0").unwrap(),
+            Vector3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            parse_vector3(" -1e3 , 0.5,7 ").unwrap(),
+            Vector3::new(-1000.0, 0.5, 7.0)
+        );
+        assert_eq!(
+            parse_quaternion("0.5,-0.5,0.5,-0.5").unwrap(),
+            Quaternion::new(0.5, -0.5, 0.5, -0.5)
+        );
+
+        assert_eq!(
+            parse_vector3("1,2"),
+            Err(ParseComponentsError::WrongCount {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            parse_quaternion("1,2,3"),
+            Err(ParseComponentsError::WrongCount {
+                expected: 4,
+                found: 3
+            })
+        );
+        assert_eq!(
+            parse_vector3(""),
+            Err(ParseComponentsError::WrongCount {
+                expected: 3,
+                found: 1
+            })
+        );
+
+        let err = parse_vector3("1,two,3").unwrap_err();
+        assert_eq!(
+            err,
+            ParseComponentsError::InvalidNumber {
+                index: 1,
+                component: "two".to_string()
+            }
+        );
+        assert_eq!(err.to_string(), "component 1 (\"two\") is not a number");
+        assert_eq!(
+            parse_quaternion("1,0,0,").unwrap_err().to_string(),
+            "component 3 (\"\") is not a number"
+        );
+        assert_eq!(
+            parse_vector3("1,2,3,4").unwrap_err().to_string(),
+            "expected 3 components but found 4"
+        );
+    }
+
+    #[test]
+    fn quaternion_slerp_interpolates_rotations() {
+        use std::f64::consts::PI;
+
+        let start = UnitQuaternion::from_euler_angles(0.0, 0.0, 0.3).into_inner();
+        let end = UnitQuaternion::from_euler_angles(0.0, 0.0, 0.3 + PI / 2.0).into_inner();
+
+        assert!(quaternions_same_rotation(
+            &quaternion_slerp(&start, &end, 0.0),
+            &start,
+            1e-12
+        ));
+        assert!(quaternions_same_rotation(
+            &quaternion_slerp(&start, &end, 1.0),
+            &end,
+            1e-12
+        ));
+
+        // halfway through a 90 degree turn is a 45 degree turn, given unnormalized inputs
+        let midpoint = quaternion_slerp(&(2.0 * start), &(0.5 * end), 0.5);
+        let expected = UnitQuaternion::from_euler_angles(0.0, 0.0, 0.3 + PI / 4.0).into_inner();
+        assert_approx_eq!(f64, midpoint.norm(), 1.0, epsilon = 1e-12);
+        assert!(quaternions_same_rotation(&midpoint, &expected, 1e-12));
+
+        // the antipode of end is the same rotation, so the interpolation is the same short one
+        let antipodal = quaternion_slerp(&start, &-end, 0.5);
+        assert!(quaternions_same_rotation(&antipodal, &expected, 1e-12));
+        assert!(antipodal.dot(&start) > 0.0);
+
+        // nearly identical rotations fall back to normalized linear interpolation
+        let nearby = UnitQuaternion::from_euler_angles(0.0, 0.0, 0.3 + 1e-6).into_inner();
+        let close = quaternion_slerp(&start, &nearby, 0.5);
+        let expected = UnitQuaternion::from_euler_angles(0.0, 0.0, 0.3 + 5e-7).into_inner();
+        assert!(quaternions_same_rotation(&close, &expected, 1e-12));
+    }
+
+    #[test]
+    fn quaternion_to_rotation_matrix_matches_euler_angles() {
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+        let angles: [(f64, f64, f64); 5] = [
+            (0.0, 0.0, 0.0),
+            (FRAC_PI_2, 0.0, 0.0),
+            (0.0, FRAC_PI_4, 0.0),
+            (0.0, 0.0, -FRAC_PI_2),
+            (0.3, -0.7, 2.1),
+        ];
+        for (roll, pitch, yaw) in angles {
+            let quat = UnitQuaternion::from_euler_angles(roll, pitch, yaw).into_inner();
+            let matrix = quaternion_to_rotation_matrix(&(3.0 * quat));
+
+            // yaw about Z, then pitch about Y', then roll about X"
+            let (sr, cr) = roll.sin_cos();
+            let (sp, cp) = pitch.sin_cos();
+            let (sy, cy) = yaw.sin_cos();
+            let expected = [
+                [cy * cp, cy * sp * sr - sy * cr, cy * sp * cr + sy * sr],
+                [sy * cp, sy * sp * sr + cy * cr, sy * sp * cr - cy * sr],
+                [-sp, cp * sr, cp * cr],
+            ];
+            for row in 0..3 {
+                for column in 0..3 {
+                    assert_approx_eq!(
+                        f64,
+                        matrix[row][column],
+                        expected[row][column],
+                        epsilon = 1e-10
+                    );
+                    let dot: f64 = (0..3).map(|n| matrix[row][n] * matrix[column][n]).sum();
+                    let identity = if row == column { 1.0 } else { 0.0 };
+                    assert_approx_eq!(f64, dot, identity, epsilon = 1e-10);
+                }
+            }
+
+            // the angles read back off the matrix are the ones the quaternion converts to
+            let euler = EulerAngles::from_quaternion(&quat);
+            assert_approx_eq!(f64, (-matrix[2][0]).asin(), euler.pitch, epsilon = 1e-10);
+            assert_approx_eq!(
+                f64,
+                matrix[2][1].atan2(matrix[2][2]),
+                euler.roll,
+                epsilon = 1e-10
+            );
+            assert_approx_eq!(
+                f64,
+                matrix[1][0].atan2(matrix[0][0]),
+                euler.yaw,
+                epsilon = 1e-10
+            );
+        }
+    }
+
+    #[test]
+    fn quaternion_clamp_angle_limits_deviation() {
+        let reference = UnitQuaternion::from_euler_angles(0.1, 0.2, 0.3).into_inner();
+        let axis = Vector3::new(1.0, -2.0, 0.5).normalize();
+        let turned =
+            |angle: f64| reference * UnitQuaternion::from_scaled_axis(axis * angle).into_inner();
+
+        let within = turned(0.2);
+        assert_approx_eq!(
+            f64,
+            quaternion_angular_distance(&within, &reference),
+            0.2,
+            epsilon = 1e-12
+        );
+        assert_eq!(quaternion_clamp_angle(&within, &reference, 0.5), within);
+
+        let beyond = turned(1.5);
+        let clamped = quaternion_clamp_angle(&beyond, &reference, 0.5);
+        assert_approx_eq!(
+            f64,
+            quaternion_angular_distance(&clamped, &reference),
+            0.5,
+            epsilon = 1e-12
+        );
+        assert!(quaternions_same_rotation(&clamped, &turned(0.5), 1e-12));
+
+        // the sign of the quaternion doesn't change the distance to clamp to
+        let clamped = quaternion_clamp_angle(&-beyond, &reference, 0.5);
+        assert!(quaternions_same_rotation(&clamped, &turned(0.5), 1e-12));
+    }
+
+    #[test]
+    fn vector_direction_slerp_follows_great_circle() {
+        use std::f64::consts::{FRAC_PI_4, FRAC_PI_8};
+
+        let x: Vector3<f64> = Vector3::x();
+        let y: Vector3<f64> = Vector3::y();
+        let at_angle = |angle: f64| Vector3::new(angle.cos(), angle.sin(), 0.0);
+
+        let midpoint = vector_direction_slerp(&x, &(3.0 * y), 0.5);
+        assert!(get_vector_diff_norm(&midpoint, &at_angle(FRAC_PI_4)) < 1e-12);
+        // lerping the vectors and normalizing doesn't land on 45 degrees
+        let nlerp = (x + (3.0 * y - x) * 0.5).normalize();
+        assert!(get_vector_diff_norm(&nlerp, &at_angle(FRAC_PI_4)) > 1e-3);
+
+        let quarter = vector_direction_slerp(&x, &y, 0.25);
+        assert!(get_vector_diff_norm(&quarter, &at_angle(FRAC_PI_8)) < 1e-12);
+        let nlerp = (x + (y - x) * 0.25).normalize();
+        assert!(get_vector_diff_norm(&nlerp, &at_angle(FRAC_PI_8)) > 1e-3);
+
+        assert!(get_vector_diff_norm(&vector_direction_slerp(&x, &y, 0.0), &x) < 1e-12);
+        assert!(get_vector_diff_norm(&vector_direction_slerp(&x, &y, 1.0), &y) < 1e-12);
+        assert!(get_vector_diff_norm(&vector_direction_slerp(&x, &(2.0 * x), 0.5), &x) < 1e-12);
+
+        let opposite = -x;
+        let halfway = vector_direction_slerp(&x, &opposite, 0.5);
+        assert_approx_eq!(f64, halfway.norm(), 1.0, epsilon = 1e-12);
+        assert_approx_eq!(f64, halfway.dot(&x), 0.0, epsilon = 1e-12);
+        let end = vector_direction_slerp(&x, &opposite, 1.0);
+        assert!(get_vector_diff_norm(&end, &opposite) < 1e-12);
+
+        assert_eq!(
+            vector_direction_slerp(&Vector3::zeros(), &y, 0.5),
+            Vector3::zeros()
+        );
+    }
+
+    #[test]
+    fn random_uniform_quaternions_are_uniform() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // rotations drawn uniformly carry an axis to points spread uniformly over the sphere,
+        // whose z coordinates are in turn uniform over [-1, 1].
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples = 20_000;
+        let bins = 10;
+        let mut histogram = vec![0usize; bins];
+        let mut sum = Vector3::zeros();
+        for _ in 0..samples {
+            let quat = random_uniform_quaternion(&mut rng);
+            assert_approx_eq!(f64, quat.norm(), 1.0, epsilon = 1e-12);
+            let rotated = rotate_vector_by_quaternion(&quat, &Vector3::x());
+            sum += rotated;
+            let bin = (((rotated.z + 1.0) / 2.0) * bins as f64) as usize;
+            histogram[bin.min(bins - 1)] += 1;
+        }
+
+        let mean = sum / samples as f64;
+        assert!(mean.norm() < 0.02, "{mean:?}");
+        let expected = samples / bins;
+        for count in histogram {
+            assert!(
+                count.abs_diff(expected) < expected / 10,
+                "{count} vs {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn vector_bounding_box_works() {
+        let points = [
+            Vector3::new(1.0, -2.0, 3.0),
+            Vector3::new(-4.0, 5.0, 0.5),
+            Vector3::new(2.0, 0.0, -6.0),
+        ];
+        assert_eq!(
+            vector_bounding_box(&points),
+            Some((Vector3::new(-4.0, -2.0, -6.0), Vector3::new(2.0, 5.0, 3.0)))
+        );
+
+        let point = Vector3::new(1.0, 2.0, 3.0);
+        assert_eq!(vector_bounding_box(&[point]), Some((point, point)));
+
+        assert_eq!(vector_bounding_box(&[]), None);
+    }
 }
@@ -0,0 +1,197 @@
+//! Servers run in-process for the integration tests to dial, and helpers for serving them.
+
+// each test uses only some of these
+#![allow(dead_code)]
+
+use anyhow::Result;
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use tokio_stream::{wrappers::TcpListenerStream, Stream};
+use tonic::{
+    metadata::MetadataMap,
+    transport::{server::Router, Server},
+    Request, Response, Status, Streaming,
+};
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_server::{
+    EchoService, EchoServiceServer,
+};
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::{
+    EchoBiDiRequest, EchoBiDiResponse, EchoMultipleRequest, EchoMultipleResponse, EchoRequest,
+    EchoResponse,
+};
+use viam_rust_utils::gen::proto::rpc::v1::auth_service_server::{AuthService, AuthServiceServer};
+use viam_rust_utils::gen::proto::rpc::v1::{AuthenticateRequest, AuthenticateResponse};
+use viam_rust_utils::gen::proto::rpc::webrtc::v1::signaling_service_server::SignalingService;
+use viam_rust_utils::gen::proto::rpc::webrtc::v1::{
+    AnswerRequest, AnswerResponse, CallRequest, CallResponse, CallUpdateRequest,
+    CallUpdateResponse, OptionalWebRtcConfigRequest, OptionalWebRtcConfigResponse,
+};
+use viam_rust_utils::rpc::dial;
+
+/// The token Auth hands out.
+pub const ACCESS_TOKEN: &str = "robot-access-token";
+
+pub type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Records the method and metadata of every request the servers sharing it receive.
+#[derive(Clone, Default)]
+pub struct Recorder(Arc<Mutex<Vec<(String, MetadataMap)>>>);
+
+impl Recorder {
+    fn record<T>(&self, method: &str, request: &Request<T>) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((method.to_string(), request.metadata().clone()));
+    }
+
+    /// The method of each request, in the order they were received.
+    pub fn methods(&self) -> Vec<String> {
+        self.0.lock().unwrap().iter().map(|r| r.0.clone()).collect()
+    }
+
+    /// The metadata of each request, in the order they were received.
+    pub fn metadata(&self) -> Vec<MetadataMap> {
+        self.0.lock().unwrap().iter().map(|r| r.1.clone()).collect()
+    }
+
+    /// The value of the named header of each request, or None if it has none.
+    pub fn header(&self, name: &str) -> Vec<Option<String>> {
+        self.metadata()
+            .iter()
+            .map(|metadata| {
+                let value = metadata.get(name)?.to_str().ok()?;
+                Some(value.to_string())
+            })
+            .collect()
+    }
+}
+
+/// Hands out ACCESS_TOKEN to everyone who authenticates.
+#[derive(Clone, Default)]
+pub struct Auth(pub Recorder);
+
+#[tonic::async_trait]
+impl AuthService for Auth {
+    async fn authenticate(
+        &self,
+        request: Request<AuthenticateRequest>,
+    ) -> Result<Response<AuthenticateResponse>, Status> {
+        self.0.record("authenticate", &request);
+        Ok(Response::new(AuthenticateResponse {
+            access_token: ACCESS_TOKEN.to_string(),
+        }))
+    }
+}
+
+/// Echoes unary calls back.
+#[derive(Clone, Default)]
+pub struct Echo(pub Recorder);
+
+#[tonic::async_trait]
+impl EchoService for Echo {
+    async fn echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        self.0.record("echo", &request);
+        let message = request.into_inner().message;
+        Ok(Response::new(EchoResponse { message }))
+    }
+
+    type EchoMultipleStream = ResponseStream<EchoMultipleResponse>;
+
+    async fn echo_multiple(
+        &self,
+        _request: Request<EchoMultipleRequest>,
+    ) -> Result<Response<Self::EchoMultipleStream>, Status> {
+        Err(Status::unimplemented("not used by these tests"))
+    }
+
+    type EchoBiDiStream = ResponseStream<EchoBiDiResponse>;
+
+    async fn echo_bi_di(
+        &self,
+        _request: Request<Streaming<EchoBiDiRequest>>,
+    ) -> Result<Response<Self::EchoBiDiStream>, Status> {
+        Err(Status::unimplemented("not used by these tests"))
+    }
+}
+
+/// A signaling server that records what it's asked and then refuses to signal, so that the
+/// dial falls back to a direct connection. Unless it refuses outright, it hands out an empty
+/// webRTC config first so that the dial goes on to make a call.
+#[derive(Clone, Default)]
+pub struct Signaling {
+    pub recorder: Recorder,
+    pub refuse_config: bool,
+}
+
+#[tonic::async_trait]
+impl SignalingService for Signaling {
+    type CallStream = ResponseStream<CallResponse>;
+
+    async fn call(
+        &self,
+        request: Request<CallRequest>,
+    ) -> Result<Response<Self::CallStream>, Status> {
+        self.recorder.record("call", &request);
+        Err(Status::unavailable("signaling refused by test"))
+    }
+
+    async fn call_update(
+        &self,
+        request: Request<CallUpdateRequest>,
+    ) -> Result<Response<CallUpdateResponse>, Status> {
+        self.recorder.record("call_update", &request);
+        Err(Status::unavailable("signaling refused by test"))
+    }
+
+    type AnswerStream = ResponseStream<AnswerRequest>;
+
+    async fn answer(
+        &self,
+        _request: Request<Streaming<AnswerResponse>>,
+    ) -> Result<Response<Self::AnswerStream>, Status> {
+        Err(Status::unimplemented("not used by these tests"))
+    }
+
+    async fn optional_web_rtc_config(
+        &self,
+        request: Request<OptionalWebRtcConfigRequest>,
+    ) -> Result<Response<OptionalWebRtcConfigResponse>, Status> {
+        self.recorder.record("optional_web_rtc_config", &request);
+        if self.refuse_config {
+            return Err(Status::unavailable("signaling refused by test"));
+        }
+        Ok(Response::new(OptionalWebRtcConfigResponse::default()))
+    }
+}
+
+/// Serves the router's services on a local port, returning the address they're served at.
+pub async fn serve(router: Router) -> Result<String> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?.to_string();
+    tokio::spawn(router.serve_with_incoming(TcpListenerStream::new(listener)));
+    Ok(address)
+}
+
+/// Serves Echo on a local port, returning the address it's served at and what it records.
+pub async fn serve_echo() -> Result<(String, Recorder)> {
+    let echo = Echo::default();
+    let address =
+        serve(Server::builder().add_service(EchoServiceServer::new(echo.clone()))).await?;
+    Ok((address, echo.0))
+}
+
+/// Serves Auth on a local port, returning the address it's served at and what it records.
+pub async fn serve_auth() -> Result<(String, Recorder)> {
+    let auth = Auth::default();
+    let address =
+        serve(Server::builder().add_service(AuthServiceServer::new(auth.clone()))).await?;
+    Ok((address, auth.0))
+}
+
+/// Credentials that Auth accepts.
+pub fn credentials() -> dial::RPCCredentials {
+    dial::RPCCredentials::new(None, "api-key".to_string(), "secret".to_string())
+}
@@ -0,0 +1,186 @@
+/// Tests that connect bounds each attempt and the attempts as a whole by their own timeouts,
+/// and that it backs off between retries but doesn't retry rejected credentials, using auth
+/// servers run in-process that hang on or fail their first requests.
+use anyhow::Result;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tonic::{transport::Server, Request, Response, Status};
+use viam_rust_utils::gen::proto::rpc::v1::auth_service_server::{AuthService, AuthServiceServer};
+use viam_rust_utils::gen::proto::rpc::v1::{AuthenticateRequest, AuthenticateResponse};
+use viam_rust_utils::rpc::dial::{self, ViamChannel};
+
+mod common;
+
+// Hangs on the first `hanging` authenticate requests it receives, then answers the rest.
+struct FlakyAuth {
+    hanging: usize,
+    requests: Arc<AtomicUsize>,
+}
+
+#[tonic::async_trait]
+impl AuthService for FlakyAuth {
+    async fn authenticate(
+        &self,
+        _request: Request<AuthenticateRequest>,
+    ) -> Result<Response<AuthenticateResponse>, Status> {
+        if self.requests.fetch_add(1, Ordering::SeqCst) < self.hanging {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }
+        Ok(Response::new(AuthenticateResponse {
+            access_token: "token".to_string(),
+        }))
+    }
+}
+
+async fn serve(hanging: usize) -> Result<(String, Arc<AtomicUsize>)> {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let auth = FlakyAuth {
+        hanging,
+        requests: requests.clone(),
+    };
+    let address =
+        common::serve(Server::builder().add_service(AuthServiceServer::new(auth))).await?;
+    Ok((address, requests))
+}
+
+// Fails the first `failing` authenticate requests it receives with the given code, then
+// answers the rest.
+struct FailingAuth {
+    failing: usize,
+    code: tonic::Code,
+    requests: Arc<AtomicUsize>,
+}
+
+#[tonic::async_trait]
+impl AuthService for FailingAuth {
+    async fn authenticate(
+        &self,
+        _request: Request<AuthenticateRequest>,
+    ) -> Result<Response<AuthenticateResponse>, Status> {
+        if self.requests.fetch_add(1, Ordering::SeqCst) < self.failing {
+            return Err(Status::new(self.code, "failing on purpose"));
+        }
+        Ok(Response::new(AuthenticateResponse {
+            access_token: "token".to_string(),
+        }))
+    }
+}
+
+async fn serve_failing(failing: usize, code: tonic::Code) -> Result<(String, Arc<AtomicUsize>)> {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let auth = FailingAuth {
+        failing,
+        code,
+        requests: requests.clone(),
+    };
+    let address =
+        common::serve(Server::builder().add_service(AuthServiceServer::new(auth))).await?;
+    Ok((address, requests))
+}
+
+fn builder(address: &str) -> dial::DialBuilder<dial::WithCredentials> {
+    dial::DialOptions::builder()
+        .uri(address)
+        .with_credentials(common::credentials())
+        .insecure()
+        .allow_insecure_credentials()
+        .disable_mdns()
+        .disable_webrtc()
+}
+
+#[tokio::test]
+async fn test_hanging_attempt_is_retried() -> Result<()> {
+    let (address, requests) = serve(1).await?;
+
+    let start = Instant::now();
+    let c = builder(&address)
+        .max_attempts(3)
+        .per_attempt_timeout(Duration::from_millis(500))
+        .connect_timeout(Duration::from_secs(10))
+        .connect()
+        .await?;
+    assert!(matches!(c, ViamChannel::DirectPreAuthorized(_)));
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connect_timeout_bounds_all_attempts() -> Result<()> {
+    let (address, _) = serve(usize::MAX).await?;
+
+    let start = Instant::now();
+    let err = builder(&address)
+        .max_attempts(10)
+        .per_attempt_timeout(Duration::from_millis(300))
+        .connect_timeout(Duration::from_secs(1))
+        .connect()
+        .await
+        .err()
+        .expect("connecting to a hanging server should fail");
+    assert!(
+        format!("{err:#}").contains("unable to connect within"),
+        "{err}"
+    );
+    assert!(start.elapsed() < Duration::from_secs(3));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transient_failures_are_retried_with_backoff() -> Result<()> {
+    let (address, requests) = serve_failing(2, tonic::Code::Unavailable).await?;
+
+    let start = Instant::now();
+    let c = builder(&address)
+        .with_retries(3, Duration::from_millis(100))
+        .connect()
+        .await?;
+    assert!(matches!(c, ViamChannel::DirectPreAuthorized(_)));
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+    // backed off for 100ms and then 200ms
+    assert!(start.elapsed() >= Duration::from_millis(300));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_final_error_counts_attempts() -> Result<()> {
+    let (address, requests) = serve_failing(usize::MAX, tonic::Code::Unavailable).await?;
+
+    let err = builder(&address)
+        .with_retries(3, Duration::from_millis(10))
+        .connect()
+        .await
+        .err()
+        .expect("connecting to a failing server should fail");
+    assert!(format!("{err:#}").contains("after 3 attempts"), "{err}");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejected_credentials_are_not_retried() -> Result<()> {
+    let (address, requests) = serve_failing(usize::MAX, tonic::Code::Unauthenticated).await?;
+
+    let err = builder(&address)
+        .with_retries(3, Duration::from_millis(10))
+        .connect()
+        .await
+        .err()
+        .expect("connecting with rejected credentials should fail");
+    assert!(
+        format!("{err:#}").contains("rejected the credentials"),
+        "{err}"
+    );
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
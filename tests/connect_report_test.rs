@@ -0,0 +1,116 @@
+/// Tests that connect_with_report times each phase of a direct connection and reports who it
+/// authenticated as, using an auth server run in-process that takes a known amount of time to
+/// answer.
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tonic::{transport::Server, Request, Response, Status};
+use viam_rust_utils::gen::proto::rpc::v1::auth_service_server::{AuthService, AuthServiceServer};
+use viam_rust_utils::gen::proto::rpc::v1::{AuthenticateRequest, AuthenticateResponse};
+use viam_rust_utils::rpc::dial::{self, ViamChannel};
+
+mod common;
+
+const AUTH_DELAY: Duration = Duration::from_millis(200);
+
+// Records the entity of each authenticate request it answers.
+#[derive(Clone, Default)]
+struct SlowAuth {
+    entities: Arc<Mutex<Vec<String>>>,
+}
+
+#[tonic::async_trait]
+impl AuthService for SlowAuth {
+    async fn authenticate(
+        &self,
+        request: Request<AuthenticateRequest>,
+    ) -> Result<Response<AuthenticateResponse>, Status> {
+        let entity = request.into_inner().entity;
+        self.entities.lock().unwrap().push(entity);
+        tokio::time::sleep(AUTH_DELAY).await;
+        Ok(Response::new(AuthenticateResponse {
+            access_token: "token".to_string(),
+        }))
+    }
+}
+
+async fn serve(auth: SlowAuth) -> Result<String> {
+    common::serve(Server::builder().add_service(AuthServiceServer::new(auth))).await
+}
+
+async fn connect(
+    address: &str,
+    entity: Option<&str>,
+) -> Result<(dial::ViamChannel, dial::ConnectReport)> {
+    let creds = dial::RPCCredentials::new(
+        entity.map(str::to_string),
+        "api-key".to_string(),
+        "secret".to_string(),
+    );
+    dial::DialOptions::builder()
+        .uri(address)
+        .with_credentials(creds)
+        .insecure()
+        .allow_insecure_credentials()
+        .disable_mdns()
+        .disable_webrtc()
+        .connect_with_report()
+        .await
+}
+
+#[tokio::test]
+async fn test_connect_report_phases_sum_to_total() -> Result<()> {
+    let address = serve(SlowAuth::default()).await?;
+
+    let (c, report) = connect(&address, None).await?;
+    assert!(matches!(c, ViamChannel::DirectPreAuthorized(_)));
+
+    assert!(report.mdns.is_none(), "{report:?}");
+    assert!(report.webrtc.is_none(), "{report:?}");
+    let tcp_connect = report.tcp_connect.expect("tcp connect should be timed");
+    let handshake = report.handshake.expect("handshake should be timed");
+    let auth = report.auth.expect("auth should be timed");
+    assert!(auth >= AUTH_DELAY, "{report:?}");
+
+    let phases = tcp_connect + handshake + auth;
+    assert!(phases <= report.total, "{report:?}");
+    assert!(
+        report.total - phases < Duration::from_millis(100),
+        "{report:?}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connect_report_authenticated_entity() -> Result<()> {
+    let auth = SlowAuth::default();
+    let address = serve(auth.clone()).await?;
+
+    let (_, report) = connect(&address, Some("my-entity")).await?;
+    assert_eq!(report.authenticated_entity.as_deref(), Some("my-entity"));
+
+    // without an entity, the robot's host is authenticated as
+    let (_, report) = connect(&address, None).await?;
+    assert_eq!(
+        report.authenticated_entity.as_deref(),
+        Some(address.as_str())
+    );
+
+    assert_eq!(
+        *auth.entities.lock().unwrap(),
+        vec!["my-entity".to_string(), address.clone()]
+    );
+
+    let (_, report) = dial::DialOptions::builder()
+        .uri(&address)
+        .without_credentials()
+        .insecure()
+        .disable_mdns()
+        .disable_webrtc()
+        .connect_with_report()
+        .await?;
+    assert_eq!(report.authenticated_entity, None);
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "serde")]
+/// Tests that a DialConfig loaded from JSON builds a DialBuilder that connects to an echo
+/// server run in-process.
+use anyhow::Result;
+use viam_rust_utils::rpc::dial::{ConfiguredDialBuilder, DialConfig, ViamChannel};
+use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+mod common;
+
+#[tokio::test]
+async fn test_dial_config_connects() -> Result<()> {
+    let (address, _) = common::serve_echo().await?;
+
+    let config: DialConfig = serde_json::from_str(&format!(
+        r#"{{
+            "uri": "{address}",
+            "insecure": true,
+            "disable_mdns": true,
+            "disable_webrtc": true,
+            "max_attempts": 2,
+            "connect_timeout_secs": 5
+        }}"#
+    ))?;
+    let builder = config.into_builder()?;
+    assert!(matches!(
+        builder,
+        ConfiguredDialBuilder::WithoutCredentials(_)
+    ));
+    let c = builder.connect().await?;
+    assert!(matches!(c, ViamChannel::Direct(_)));
+
+    assert_eq!(echo_roundtrip(c, "hi").await?, "hi");
+    Ok(())
+}
+
+#[test]
+fn test_dial_config_credentials_and_errors() -> Result<()> {
+    let config: DialConfig = serde_json::from_str(
+        r#"{
+            "uri": "my-robot.abc123.viam.cloud",
+            "credentials": {"type": "api-key", "payload": "secret", "entity": "key-id"}
+        }"#,
+    )?;
+    match config.into_builder()? {
+        ConfiguredDialBuilder::WithCredentials(builder) => {
+            let description = builder.describe();
+            assert!(
+                description.contains("credentials: api-key for entity key-id"),
+                "{description}"
+            );
+        }
+        ConfiguredDialBuilder::WithoutCredentials(_) => panic!("expected credentials"),
+    }
+
+    // the danger_ spelling is read as allow_insecure_credentials
+    for field in [
+        "allow_insecure_credentials",
+        "danger_allow_insecure_credentials",
+    ] {
+        let config: DialConfig =
+            serde_json::from_str(&format!(r#"{{"uri": "robot", "{field}": true}}"#))?;
+        assert!(config.allow_insecure_credentials, "{field}");
+    }
+
+    // misspelled settings are rejected rather than silently ignored
+    let misspelled = serde_json::from_str::<DialConfig>(r#"{"uri": "robot", "insecur": true}"#);
+    assert!(misspelled.is_err());
+
+    let config: DialConfig =
+        serde_json::from_str(r#"{"uri": "robot", "connect_timeout_secs": -1}"#)?;
+    assert!(config.into_builder().is_err());
+    let config: DialConfig = serde_json::from_str(r#"{"uri": "not a uri"}"#)?;
+    assert!(config.into_builder().is_err());
+    Ok(())
+}
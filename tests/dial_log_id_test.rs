@@ -0,0 +1,294 @@
+/// Tests that the log lines of concurrent dials, and of the webRTC connections they make, are
+/// tagged with the id of the dial they came from, using a logger that captures them and
+/// signaling servers run in-process that answer with a plain webRTC peer.
+use anyhow::Result;
+use bytes::Bytes;
+use prost::Message;
+use std::sync::{Arc, Mutex};
+use tokio_stream::StreamExt;
+use tonic::{transport::Server, Request, Response, Status, Streaming};
+use viam_rust_utils::gen::google;
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::{
+    echo_service_client::EchoServiceClient, EchoRequest,
+};
+use viam_rust_utils::gen::proto::rpc::webrtc::v1::signaling_service_server::{
+    SignalingService, SignalingServiceServer,
+};
+use viam_rust_utils::gen::proto::rpc::webrtc::v1::{
+    call_response::Stage, call_update_request::Update, request, response, AnswerRequest,
+    AnswerResponse, CallRequest, CallResponse, CallResponseInitStage, CallUpdateRequest,
+    CallUpdateResponse, OptionalWebRtcConfigRequest, OptionalWebRtcConfigResponse,
+    Request as StreamRequest, Response as StreamResponse, ResponseHeaders, ResponseTrailers,
+};
+use viam_rust_utils::rpc::{
+    dial::{self, ViamChannel},
+    log_prefixes, webrtc,
+};
+
+mod common;
+
+use ::webrtc::{
+    api::APIBuilder,
+    data_channel::{
+        data_channel_init::RTCDataChannelInit, data_channel_message::DataChannelMessage,
+        RTCDataChannel,
+    },
+    ice_transport::ice_candidate::RTCIceCandidateInit,
+    peer_connection::{configuration::RTCConfiguration, RTCPeerConnection},
+};
+
+// The target, and the message, of every record logged by the crate's rpc modules.
+static RECORDS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.target().starts_with("viam_rust_utils::rpc") {
+            RECORDS
+                .lock()
+                .unwrap()
+                .push((record.target().to_string(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+// A signaling server that answers calls with an in-process peer, which fails every call made
+// to it as unimplemented.
+#[derive(Clone, Default)]
+struct AnsweringSignaling {
+    answerer: Arc<Mutex<Option<Arc<RTCPeerConnection>>>>,
+}
+
+// Returns a plain in-process peer that has answered the offer, with its candidates gathered
+// into its local description.
+async fn answer_offer(offer: &str) -> Result<RTCPeerConnection> {
+    let answerer = APIBuilder::new()
+        .build()
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+    let mut gathered = answerer.gathering_complete_promise().await;
+    answerer
+        .set_remote_description(webrtc::decode_sdp(offer)?)
+        .await?;
+    let answer = answerer.create_answer(None).await?;
+    answerer.set_local_description(answer).await?;
+    let _ = gathered.recv().await;
+    Ok(answerer)
+}
+
+// Answers the headers of each call made over the data channel with a status of unimplemented.
+fn fail_calls(data_channel: Arc<RTCDataChannel>) {
+    let sender = data_channel.clone();
+    data_channel.on_message(Box::new(move |message: DataChannelMessage| {
+        let sender = sender.clone();
+        Box::pin(async move {
+            let request = StreamRequest::decode(message.data).unwrap();
+            if !matches!(request.r#type, Some(request::Type::Headers(_))) {
+                return;
+            }
+            let headers = response::Type::Headers(ResponseHeaders::default());
+            let trailers = response::Type::Trailers(ResponseTrailers {
+                status: Some(google::rpc::Status {
+                    code: tonic::Code::Unimplemented as i32,
+                    message: "unimplemented".to_string(),
+                    details: vec![],
+                }),
+                metadata: None,
+            });
+            for r#type in [headers, trailers] {
+                let response = StreamResponse {
+                    stream: request.stream.clone(),
+                    r#type: Some(r#type),
+                };
+                let _ = sender.send(&Bytes::from(response.encode_to_vec())).await;
+            }
+        })
+    }));
+}
+
+#[tonic::async_trait]
+impl SignalingService for AnsweringSignaling {
+    type CallStream = common::ResponseStream<CallResponse>;
+
+    async fn call(
+        &self,
+        request: Request<CallRequest>,
+    ) -> Result<Response<Self::CallStream>, Status> {
+        let answerer = answer_offer(&request.into_inner().sdp)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let data_channel = answerer
+            .create_data_channel(
+                "data",
+                Some(RTCDataChannelInit {
+                    negotiated: Some(0),
+                    ordered: Some(true),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        fail_calls(data_channel);
+        let answer = webrtc::encode_sdp(&answerer.local_description().await.unwrap())
+            .map_err(|e| Status::internal(e.to_string()))?;
+        *self.answerer.lock().unwrap() = Some(Arc::new(answerer));
+        let init = CallResponse {
+            uuid: "call-uuid".to_string(),
+            stage: Some(Stage::Init(CallResponseInitStage { sdp: answer })),
+        };
+        // keep the call open, as a real signaling server does until the caller is done
+        let responses = tokio_stream::once(Ok(init)).chain(tokio_stream::pending());
+        Ok(Response::new(Box::pin(responses)))
+    }
+
+    async fn call_update(
+        &self,
+        request: Request<CallUpdateRequest>,
+    ) -> Result<Response<CallUpdateResponse>, Status> {
+        if let Some(Update::Candidate(candidate)) = request.into_inner().update {
+            let answerer = self.answerer.lock().unwrap().clone();
+            if let Some(answerer) = answerer {
+                let _ = answerer
+                    .add_ice_candidate(RTCIceCandidateInit {
+                        candidate: candidate.candidate,
+                        ..Default::default()
+                    })
+                    .await;
+            }
+        }
+        Ok(Response::new(CallUpdateResponse {}))
+    }
+
+    type AnswerStream = common::ResponseStream<AnswerRequest>;
+
+    async fn answer(
+        &self,
+        _request: Request<Streaming<AnswerResponse>>,
+    ) -> Result<Response<Self::AnswerStream>, Status> {
+        Err(Status::unimplemented("not used by these tests"))
+    }
+
+    async fn optional_web_rtc_config(
+        &self,
+        _request: Request<OptionalWebRtcConfigRequest>,
+    ) -> Result<Response<OptionalWebRtcConfigResponse>, Status> {
+        Ok(Response::new(OptionalWebRtcConfigResponse::default()))
+    }
+}
+
+async fn serve() -> Result<String> {
+    common::serve(
+        Server::builder().add_service(SignalingServiceServer::new(AnsweringSignaling::default())),
+    )
+    .await
+}
+
+// Dials the address over webRTC, with it as the signaling server, makes a call over the
+// connection that the peer fails, and returns the dial's id.
+async fn dial_id(address: &str) -> Result<String> {
+    let (channel, report) = dial::DialOptions::builder()
+        .uri(address)
+        .without_credentials()
+        .insecure()
+        .disable_mdns()
+        .signaling_server(address)
+        .connect_with_report()
+        .await?;
+    assert!(matches!(channel, ViamChannel::WebRTC(_)));
+    let status = EchoServiceClient::new(channel.clone())
+        .echo(EchoRequest {
+            message: "hi".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unimplemented);
+    if let ViamChannel::WebRTC(channel) = channel {
+        channel.close().await;
+    }
+    Ok(report.dial_id)
+}
+
+// Returns the id of the dial the message is tagged with.
+fn tag(message: &str) -> Option<&str> {
+    message.strip_prefix("[dial ")?.split(']').next()
+}
+
+#[tokio::test]
+async fn test_concurrent_dials_tag_their_log_lines() -> Result<()> {
+    log::set_boxed_logger(Box::new(CapturingLogger))?;
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let (first, second) = (serve().await?, serve().await?);
+    let (first_id, second_id) = tokio::try_join!(dial_id(&first), dial_id(&second))?;
+    assert_ne!(first_id, second_id);
+
+    // a dial that fails names itself in its error
+    let err = dial::DialOptions::builder()
+        .uri("127.0.0.1:1")
+        .without_credentials()
+        .insecure()
+        .disable_mdns()
+        .disable_webrtc()
+        .connect()
+        .await
+        .err()
+        .expect("dialing a closed port should fail");
+    let failed_id = err
+        .to_string()
+        .strip_prefix("Dial ")
+        .and_then(|rest| rest.strip_suffix(" failed"))
+        .map(str::to_string)
+        .unwrap_or_else(|| panic!("{err:#}"));
+
+    // the channels carry on logging as they're torn down, so check what's been logged so far
+    let records = RECORDS.lock().unwrap().clone();
+    for (target, message) in &records {
+        let id = tag(message).unwrap_or_else(|| panic!("{target}: {message}"));
+        assert!(
+            [&first_id, &second_id, &failed_id].contains(&&id.to_string()),
+            "{target}: {message}"
+        );
+    }
+    for id in [&first_id, &second_id] {
+        let tagged: Vec<_> = records
+            .iter()
+            .filter(|(_, message)| tag(message) == Some(id.as_str()))
+            .collect();
+        // the dial itself, its connection's callbacks and the call made over the connection
+        for target in [
+            "viam_rust_utils::rpc::dial",
+            "viam_rust_utils::rpc::webrtc",
+            "viam_rust_utils::rpc::base_channel",
+            "viam_rust_utils::rpc::client_channel",
+            "viam_rust_utils::rpc::client_stream",
+        ] {
+            assert!(
+                tagged.iter().any(|(t, _)| t == target),
+                "nothing from {target} for dial {id}: {records:?}"
+            );
+        }
+        assert!(
+            tagged
+                .iter()
+                .any(|(_, message)| message.contains(log_prefixes::CANDIDATE_SELECTED)),
+            "{records:?}"
+        );
+        assert!(
+            tagged
+                .iter()
+                .any(|(_, message)| message.contains("peer connection state change")),
+            "{records:?}"
+        );
+    }
+    assert!(records
+        .iter()
+        .any(|(_, message)| tag(message) == Some(failed_id.as_str())));
+    Ok(())
+}
@@ -3,11 +3,12 @@
 use anyhow::Result;
 use std::env;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_client::EchoServiceClient;
 use viam_rust_utils::gen::proto::rpc::examples::echo::v1::{
     EchoBiDiRequest, EchoMultipleRequest, EchoRequest,
 };
-use viam_rust_utils::rpc::dial;
+use viam_rust_utils::rpc::dial::{self, TimedChannel};
 
 async fn dial_direct() -> Result<dial::ViamChannel> {
     let port = env::var("SERVER_PORT").unwrap().to_owned();
@@ -118,6 +119,18 @@ async fn dial_webrtc() -> Result<dial::ViamChannel> {
         .await
 }
 
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn test_echo_roundtrip() -> Result<()> {
+    use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+    for c in [dial_direct().await?, dial_webrtc().await?] {
+        assert_eq!(echo_roundtrip(c, "hi").await?, "hi");
+    }
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_dial_webrtc_unary() -> Result<()> {
     let c = dial_webrtc().await?;
@@ -182,3 +195,67 @@ async fn test_dial_webrtc_bidi() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_dial_webrtc_large_unary() -> Result<()> {
+    let c = dial_webrtc().await?;
+
+    // large enough that the request has to be split across several data channel packets.
+    let message = "a".repeat(100_000);
+    let mut service = EchoServiceClient::new(c);
+    let echo_request = EchoRequest {
+        message: message.clone(),
+    };
+    let resp = service.echo(echo_request).await?.into_inner();
+    assert_eq!(resp.message, message);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dial_webrtc_timed_channel() -> Result<()> {
+    let c = dial_webrtc().await?;
+
+    let mut service = EchoServiceClient::new(TimedChannel::new(c.clone(), Duration::from_secs(5)));
+    let echo_request = EchoRequest {
+        message: "hi".to_string(),
+    };
+    let resp = service.echo(echo_request).await?.into_inner();
+    assert_eq!(resp.message, "hi".to_string());
+
+    // no echo over webRTC can come back this quickly, so the call is always aborted locally.
+    let mut service = EchoServiceClient::new(TimedChannel::new(c, Duration::from_nanos(1)));
+    let echo_request = EchoRequest {
+        message: "hi".to_string(),
+    };
+    let status = service.echo(echo_request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::DeadlineExceeded);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dial_webrtc_suspend_resume() -> Result<()> {
+    let c = dial_webrtc().await?;
+    let webrtc_channel = match &c {
+        dial::ViamChannel::WebRTC(channel) => channel.clone(),
+        _ => panic!("expected a webRTC channel"),
+    };
+
+    webrtc_channel.suspend();
+    let mut service = EchoServiceClient::new(c.clone());
+    let echo_request = EchoRequest {
+        message: "hi".to_string(),
+    };
+    let status = service.echo(echo_request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+
+    webrtc_channel.resume();
+    let echo_request = EchoRequest {
+        message: "hi".to_string(),
+    };
+    let resp = service.echo(echo_request).await?.into_inner();
+    assert_eq!(resp.message, "hi".to_string());
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+/// Tests that a DialBuilder created from a tonic endpoint connects through that endpoint,
+/// using an echo server run in-process that records the user agent of the calls it gets.
+use anyhow::Result;
+use tonic::transport::Endpoint;
+use viam_rust_utils::rpc::dial::{DialBuilder, ViamChannel};
+use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+mod common;
+
+#[tokio::test]
+async fn test_dial_from_endpoint() -> Result<()> {
+    let (address, echo) = common::serve_echo().await?;
+
+    let endpoint =
+        Endpoint::from_shared(format!("http://{address}"))?.user_agent("from-endpoint")?;
+    let c = DialBuilder::from_endpoint(endpoint)
+        .without_credentials()
+        .disable_webrtc()
+        .connect()
+        .await?;
+    assert!(matches!(c, ViamChannel::Direct(_)));
+
+    assert_eq!(echo_roundtrip(c, "hi").await?, "hi");
+    let user_agents = echo.header("user-agent");
+    assert_eq!(user_agents.len(), 1);
+    assert!(
+        user_agents[0]
+            .as_ref()
+            .is_some_and(|user_agent| user_agent.starts_with("from-endpoint")),
+        "{user_agents:?}"
+    );
+
+    Ok(())
+}
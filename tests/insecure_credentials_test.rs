@@ -0,0 +1,72 @@
+/// Tests that credentials are only sent over an insecure connection once the dial allows it,
+/// using an auth server run in-process.
+use anyhow::Result;
+use viam_rust_utils::rpc::dial::{self, DialBuilder, WithCredentials};
+
+mod common;
+
+fn builder(address: &str) -> DialBuilder<WithCredentials> {
+    dial::DialOptions::builder()
+        .uri(address)
+        .with_credentials(common::credentials())
+        .insecure()
+        .disable_mdns()
+        .disable_webrtc()
+}
+
+#[tokio::test]
+async fn test_insecure_credentials_are_refused_by_default() -> Result<()> {
+    let (address, auth) = common::serve_auth().await?;
+    let err = builder(&address).connect().await.err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Refusing to dial insecurely with credentials, which would be sent unencrypted; call \
+         allow_insecure_credentials to send them anyway"
+    );
+    assert!(auth.methods().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insecure_credentials_are_sent_once_allowed() -> Result<()> {
+    let (address, auth) = common::serve_auth().await?;
+    builder(&address)
+        .allow_insecure_credentials()
+        .connect()
+        .await?;
+    assert_eq!(auth.methods(), vec!["authenticate"]);
+    Ok(())
+}
+
+// Dials the plain http server over https, allowing a downgrade to plain http.
+fn downgrading_builder(address: &str) -> DialBuilder<WithCredentials> {
+    dial::DialOptions::builder()
+        .uri(address)
+        .with_credentials(common::credentials())
+        .allow_downgrade()
+        .disable_mdns()
+        .disable_webrtc()
+}
+
+#[tokio::test]
+async fn test_credentialed_dials_are_not_downgraded_by_default() -> Result<()> {
+    let (address, auth) = common::serve_auth().await?;
+    let err = downgrading_builder(&address).connect().await.err().unwrap();
+    assert!(
+        format!("{err:#}").contains("Not downgrading to plain http"),
+        "{err:?}"
+    );
+    assert!(auth.methods().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_credentialed_dials_are_downgraded_once_allowed() -> Result<()> {
+    let (address, auth) = common::serve_auth().await?;
+    downgrading_builder(&address)
+        .danger_allow_insecure_credentials()
+        .connect()
+        .await?;
+    assert_eq!(auth.methods(), vec!["authenticate"]);
+    Ok(())
+}
@@ -0,0 +1,71 @@
+/// Tests that lazily connected direct channels don't open a connection until their first
+/// call, using an echo server run in-process.
+use anyhow::Result;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_server::EchoServiceServer;
+use viam_rust_utils::rpc::dial;
+use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+mod common;
+
+// Serves the echo service, counting the connections accepted.
+async fn serve() -> Result<(String, Arc<AtomicUsize>)> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?.to_string();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    let incoming = TcpListenerStream::new(listener).map(move |conn| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        conn
+    });
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(EchoServiceServer::new(common::Echo::default()))
+            .serve_with_incoming(incoming),
+    );
+    Ok((address, accepted))
+}
+
+fn builder(address: &str) -> dial::DialBuilder<dial::WithoutCredentials> {
+    dial::DialOptions::builder()
+        .uri(address)
+        .without_credentials()
+        .insecure()
+        .disable_mdns()
+        .disable_webrtc()
+}
+
+#[tokio::test]
+async fn test_lazy_connect_defers_connection_to_first_call() -> Result<()> {
+    let (address, accepted) = serve().await?;
+
+    let c = builder(&address).lazy_connect().connect().await?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(accepted.load(Ordering::SeqCst), 0);
+
+    assert_eq!(echo_roundtrip(c, "hi").await?, "hi");
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_eager_connect_connects_while_dialing() -> Result<()> {
+    let (address, accepted) = serve().await?;
+
+    let c = builder(&address).connect().await?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+    assert_eq!(echo_roundtrip(c, "hi").await?, "hi");
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
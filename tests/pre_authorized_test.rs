@@ -0,0 +1,33 @@
+/// Tests that a channel wrapped with ViamChannel::direct_pre_authorized sends its token and
+/// rpc-host with every call, using an echo server run in-process that records those headers.
+use anyhow::Result;
+use tonic::transport::Endpoint;
+use viam_rust_utils::rpc::dial::ViamChannel;
+use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+mod common;
+
+#[tokio::test]
+async fn test_pre_authorized_channel_sends_token() -> Result<()> {
+    let (address, echo) = common::serve_echo().await?;
+
+    let channel = Endpoint::from_shared(format!("http://{address}"))?
+        .connect()
+        .await?;
+    let c = ViamChannel::direct_pre_authorized(channel.clone(), "robot.local", "cached-token")?;
+    assert!(matches!(c, ViamChannel::DirectPreAuthorized(_)));
+
+    for _ in 0..2 {
+        assert_eq!(echo_roundtrip(c.clone(), "hi").await?, "hi");
+    }
+    let expected = Some("Bearer cached-token".to_string());
+    assert_eq!(
+        echo.header("authorization"),
+        vec![expected.clone(), expected]
+    );
+    let expected = Some("robot.local".to_string());
+    assert_eq!(echo.header("rpc-host"), vec![expected.clone(), expected]);
+
+    assert!(ViamChannel::direct_pre_authorized(channel, "robot.local", "bad\ntoken").is_err());
+    Ok(())
+}
@@ -0,0 +1,69 @@
+/// Tests that probing a robot's transports reports what an in-process echo server supports,
+/// with and without the signaling service alongside it.
+use anyhow::Result;
+use tonic::transport::Server;
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_server::EchoServiceServer;
+use viam_rust_utils::gen::proto::rpc::webrtc::v1::signaling_service_server::SignalingServiceServer;
+use viam_rust_utils::rpc::dial::{self, ProbeOptions, TransportSupport};
+
+mod common;
+
+// Serves echo and, if asked to, a signaling server alongside it that hands out the optional
+// webRTC config, which is all a probe asks of it.
+async fn serve(with_signaling: bool) -> Result<String> {
+    let signaling =
+        with_signaling.then(|| SignalingServiceServer::new(common::Signaling::default()));
+    let router = Server::builder()
+        .add_service(EchoServiceServer::new(common::Echo::default()))
+        .add_optional_service(signaling);
+    common::serve(router).await
+}
+
+fn options() -> ProbeOptions {
+    ProbeOptions {
+        insecure: true,
+        disable_mdns: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_probe_reports_grpc_only_echo_server() -> Result<()> {
+    let address = serve(false).await?;
+    let support = dial::probe_transports(&address, options()).await?;
+    assert_eq!(
+        support,
+        TransportSupport {
+            grpc: true,
+            webrtc: false,
+            mdns: None,
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_probe_reports_webrtc_with_signaling() -> Result<()> {
+    let address = serve(true).await?;
+    let support = dial::probe_transports(&address, options()).await?;
+    assert_eq!(
+        support,
+        TransportSupport {
+            grpc: true,
+            webrtc: true,
+            mdns: None,
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_probe_fails_when_nothing_answers() -> Result<()> {
+    // bind and drop a listener to find a port nothing listens on
+    let address = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await?
+        .local_addr()?
+        .to_string();
+    assert!(dial::probe_transports(&address, options()).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,186 @@
+/// Tests that a reconnectable channel dials again with its original options, using auth and
+/// echo servers run in-process that only accept the most recently issued token.
+use anyhow::Result;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+use tonic::{transport::Server, Request, Response, Status};
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_server::{
+    EchoService, EchoServiceServer,
+};
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::{
+    EchoBiDiRequest, EchoBiDiResponse, EchoMultipleRequest, EchoMultipleResponse, EchoRequest,
+    EchoResponse,
+};
+use viam_rust_utils::gen::proto::rpc::v1::auth_service_server::{AuthService, AuthServiceServer};
+use viam_rust_utils::gen::proto::rpc::v1::{AuthenticateRequest, AuthenticateResponse};
+use viam_rust_utils::rpc::dial::{self, ViamChannel};
+use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+mod common;
+
+// Issues "token-1", "token-2" and so on, counting how many it has issued, unless it's been
+// told to refuse.
+#[derive(Clone, Default)]
+struct Auth {
+    issued: Arc<AtomicUsize>,
+    refusing: Arc<AtomicBool>,
+}
+
+#[tonic::async_trait]
+impl AuthService for Auth {
+    async fn authenticate(
+        &self,
+        _request: Request<AuthenticateRequest>,
+    ) -> Result<Response<AuthenticateResponse>, Status> {
+        if self.refusing.load(Ordering::SeqCst) {
+            return Err(Status::unavailable("auth refused by test"));
+        }
+        let issued = self.issued.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(Response::new(AuthenticateResponse {
+            access_token: format!("token-{issued}"),
+        }))
+    }
+}
+
+// Echoes calls carrying the most recently issued token, and rejects the rest as
+// unauthenticated.
+#[derive(Clone)]
+struct Echo {
+    issued: Arc<AtomicUsize>,
+}
+
+#[tonic::async_trait]
+impl EchoService for Echo {
+    type EchoMultipleStream = futures::stream::Empty<Result<EchoMultipleResponse, Status>>;
+    type EchoBiDiStream = futures::stream::Empty<Result<EchoBiDiResponse, Status>>;
+
+    async fn echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let current = format!("Bearer token-{}", self.issued.load(Ordering::SeqCst));
+        let authorization = request.metadata().get("authorization");
+        if authorization.and_then(|value| value.to_str().ok()) != Some(current.as_str()) {
+            return Err(Status::unauthenticated("token expired"));
+        }
+        Ok(Response::new(EchoResponse {
+            message: request.into_inner().message,
+        }))
+    }
+
+    async fn echo_multiple(
+        &self,
+        _request: Request<EchoMultipleRequest>,
+    ) -> Result<Response<Self::EchoMultipleStream>, Status> {
+        Err(Status::unimplemented("echo_multiple"))
+    }
+
+    async fn echo_bi_di(
+        &self,
+        _request: Request<tonic::Streaming<EchoBiDiRequest>>,
+    ) -> Result<Response<Self::EchoBiDiStream>, Status> {
+        Err(Status::unimplemented("echo_bi_di"))
+    }
+}
+
+async fn serve(auth: &Auth) -> Result<String> {
+    common::serve(
+        Server::builder()
+            .add_service(AuthServiceServer::new(auth.clone()))
+            .add_service(EchoServiceServer::new(Echo {
+                issued: auth.issued.clone(),
+            })),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_reconnect_reauthenticates_with_the_same_options() -> Result<()> {
+    let auth = Auth::default();
+    let address = serve(&auth).await?;
+
+    let mut reconnectable = dial::DialOptions::builder()
+        .uri(&address)
+        .with_credentials(common::credentials())
+        .insecure()
+        .allow_insecure_credentials()
+        .disable_mdns()
+        .disable_webrtc()
+        .connect_reconnectable()
+        .await?;
+    assert_eq!(
+        echo_roundtrip(reconnectable.channel().clone(), "hi").await?,
+        "hi"
+    );
+
+    // expire the channel's token by issuing a newer one
+    auth.issued.fetch_add(1, Ordering::SeqCst);
+    let err = echo_roundtrip(reconnectable.channel().clone(), "hi")
+        .await
+        .unwrap_err();
+    let status = err.downcast_ref::<Status>().unwrap();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    let fresh = reconnectable.reconnect().await?;
+    assert!(matches!(fresh, ViamChannel::DirectPreAuthorized(_)));
+    assert_eq!(echo_roundtrip(fresh, "hi").await?, "hi");
+    assert_eq!(auth.issued.load(Ordering::SeqCst), 3);
+
+    // reconnecting in place swaps the fresh channel in for the expired one
+    auth.issued.fetch_add(1, Ordering::SeqCst);
+    reconnectable.reconnect_in_place().await?;
+    assert_eq!(
+        echo_roundtrip(reconnectable.channel().clone(), "hi").await?,
+        "hi"
+    );
+    assert_eq!(auth.issued.load(Ordering::SeqCst), 5);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reconnect_backs_off_and_reports_each_attempt() -> Result<()> {
+    let auth = Auth::default();
+    let address = serve(&auth).await?;
+
+    let attempts = Arc::new(Mutex::new(Vec::new()));
+    let reported = attempts.clone();
+    let mut reconnectable = dial::DialOptions::builder()
+        .uri(&address)
+        .with_credentials(common::credentials())
+        .insecure()
+        .allow_insecure_credentials()
+        .disable_mdns()
+        .disable_webrtc()
+        .connect_reconnectable()
+        .await?
+        .on_reconnect(move |attempt| reported.lock().unwrap().push(attempt))
+        .reconnect_backoff(Duration::from_millis(40), Duration::from_millis(80));
+
+    // each failed reconnect makes the next one wait longer, up to the cap
+    auth.refusing.store(true, Ordering::SeqCst);
+    let mut waits = Vec::new();
+    for _ in 0..6 {
+        let start = Instant::now();
+        assert!(reconnectable.reconnect().await.is_err());
+        waits.push(start.elapsed());
+    }
+    assert_eq!(*attempts.lock().unwrap(), vec![1, 2, 3, 4, 5, 6]);
+    // at least half of 40ms, 80ms and then the 80ms cap
+    assert!(waits[1] >= Duration::from_millis(20), "{waits:?}");
+    assert!(waits[2] >= Duration::from_millis(40), "{waits:?}");
+    assert!(waits[5] >= Duration::from_millis(40), "{waits:?}");
+    // uncapped, the sixth attempt would have waited at least 320ms
+    assert!(waits[5] < Duration::from_millis(300), "{waits:?}");
+
+    auth.refusing.store(false, Ordering::SeqCst);
+    reconnectable.reconnect_in_place().await?;
+    assert_eq!(
+        echo_roundtrip(reconnectable.channel().clone(), "hi").await?,
+        "hi"
+    );
+
+    // a successful reconnect starts the count over
+    reconnectable.reconnect().await?;
+    assert_eq!(*attempts.lock().unwrap(), vec![1, 2, 3, 4, 5, 6, 7, 1]);
+    Ok(())
+}
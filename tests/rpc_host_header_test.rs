@@ -0,0 +1,95 @@
+/// Tests that the rpc-host header is sent with calls over a direct channel, and with signaling
+/// calls, unless the dial leaves it out, using auth, echo and signaling servers run in-process.
+use anyhow::Result;
+use tonic::transport::Server;
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_server::EchoServiceServer;
+use viam_rust_utils::gen::proto::rpc::v1::auth_service_server::AuthServiceServer;
+use viam_rust_utils::gen::proto::rpc::webrtc::v1::signaling_service_server::SignalingServiceServer;
+use viam_rust_utils::rpc::dial::{self, DialBuilder, ViamChannel, WithCredentials};
+use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+mod common;
+
+// Serves auth, echo and a signaling server that refuses to signal, so that dials fall back to
+// a direct connection, returning their address and what they record.
+async fn serve() -> Result<(String, common::Recorder)> {
+    let recorder = common::Recorder::default();
+    let signaling = common::Signaling {
+        recorder: recorder.clone(),
+        refuse_config: true,
+    };
+    let address = common::serve(
+        Server::builder()
+            .add_service(AuthServiceServer::new(common::Auth(recorder.clone())))
+            .add_service(EchoServiceServer::new(common::Echo(recorder.clone())))
+            .add_service(SignalingServiceServer::new(signaling)),
+    )
+    .await?;
+    Ok((address, recorder))
+}
+
+fn builder(address: &str) -> DialBuilder<WithCredentials> {
+    dial::DialOptions::builder()
+        .uri(address)
+        .with_credentials(common::credentials())
+        .insecure()
+        .allow_insecure_credentials()
+        .disable_mdns()
+        .disable_webrtc()
+}
+
+// Makes a call over a channel dialed by the builder, and returns the rpc-host header the call
+// carried.
+async fn echoed_rpc_host(
+    builder: DialBuilder<WithCredentials>,
+    recorder: &common::Recorder,
+) -> Result<Option<String>> {
+    assert_eq!(echo_roundtrip(builder.connect().await?, "hi").await?, "hi");
+    let methods = recorder.methods();
+    assert_eq!(methods.last().map(String::as_str), Some("echo"));
+    Ok(recorder.header("rpc-host").pop().flatten())
+}
+
+#[tokio::test]
+async fn test_rpc_host_header_is_sent_by_default() -> Result<()> {
+    let (address, recorder) = serve().await?;
+    assert_eq!(
+        echoed_rpc_host(builder(&address), &recorder).await?,
+        Some(address)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rpc_host_header_can_be_left_out() -> Result<()> {
+    let (address, recorder) = serve().await?;
+    let builder = builder(&address).without_rpc_host_header();
+    assert_eq!(echoed_rpc_host(builder, &recorder).await?, None);
+    Ok(())
+}
+
+// Dials without credentials, letting the dial try webRTC first, and returns the rpc-host
+// header the signaling server saw.
+async fn signaled_rpc_host_without_credentials(omit: bool) -> Result<Option<String>> {
+    let (address, recorder) = serve().await?;
+    let mut builder = dial::DialOptions::builder()
+        .uri(&address)
+        .without_credentials()
+        .insecure()
+        .disable_mdns();
+    if omit {
+        builder = builder.without_rpc_host_header();
+    }
+    let channel = builder.connect().await?;
+    assert!(matches!(channel, ViamChannel::Direct(_)));
+    assert_eq!(recorder.methods(), vec!["optional_web_rtc_config"]);
+    Ok(recorder.header("rpc-host").remove(0))
+}
+
+#[tokio::test]
+async fn test_rpc_host_header_without_credentials() -> Result<()> {
+    let sent = signaled_rpc_host_without_credentials(false).await?;
+    assert!(sent.is_some_and(|rpc_host| rpc_host.starts_with("127.0.0.1:")));
+    assert_eq!(signaled_rpc_host_without_credentials(true).await?, None);
+    Ok(())
+}
@@ -0,0 +1,99 @@
+/// Tests that webRTC signaling goes to an explicitly configured signaling server, carrying any
+/// signaling headers, while authentication and the direct fallback use the dialed uri, using
+/// servers run in-process.
+use anyhow::Result;
+use tonic::transport::Server;
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_server::EchoServiceServer;
+use viam_rust_utils::gen::proto::rpc::v1::auth_service_server::AuthServiceServer;
+use viam_rust_utils::gen::proto::rpc::webrtc::v1::signaling_service_server::SignalingServiceServer;
+use viam_rust_utils::rpc::dial::{self, ViamChannel};
+use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+mod common;
+
+async fn serve_robot() -> Result<(String, common::Recorder)> {
+    let robot = common::Recorder::default();
+    let address = common::serve(
+        Server::builder()
+            .add_service(AuthServiceServer::new(common::Auth(robot.clone())))
+            .add_service(EchoServiceServer::new(common::Echo(robot.clone()))),
+    )
+    .await?;
+    Ok((address, robot))
+}
+
+async fn serve_signaling(refuse_config: bool) -> Result<(String, common::Recorder)> {
+    let recorder = common::Recorder::default();
+    let signaling = common::Signaling {
+        recorder: recorder.clone(),
+        refuse_config,
+    };
+    let address =
+        common::serve(Server::builder().add_service(SignalingServiceServer::new(signaling)))
+            .await?;
+    Ok((address, recorder))
+}
+
+#[tokio::test]
+async fn test_dial_with_separate_signaling_server() -> Result<()> {
+    let (robot_address, robot) = serve_robot().await?;
+    let (signaling_address, signaling) = serve_signaling(true).await?;
+
+    let c = dial::DialOptions::builder()
+        .uri(&robot_address)
+        .with_credentials(common::credentials())
+        .insecure()
+        .allow_insecure_credentials()
+        .disable_mdns()
+        .signaling_server(&signaling_address)
+        .connect()
+        .await?;
+    assert!(matches!(c, ViamChannel::DirectPreAuthorized(_)));
+
+    // signaling went to the signaling server, authorized with the robot's token and
+    // addressed to the robot.
+    assert_eq!(signaling.methods(), vec!["optional_web_rtc_config"]);
+    let metadata = &signaling.metadata()[0];
+    assert_eq!(
+        metadata.get("authorization").unwrap(),
+        &format!("Bearer {}", common::ACCESS_TOKEN)
+    );
+    assert_eq!(metadata.get("rpc-host").unwrap(), robot_address.as_str());
+
+    // authentication and the fallback connection stayed with the robot
+    assert_eq!(echo_roundtrip(c, "hi").await?, "hi");
+    assert_eq!(robot.methods(), vec!["authenticate", "echo"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dial_with_signaling_headers() -> Result<()> {
+    let (robot_address, robot) = serve_robot().await?;
+    let (signaling_address, signaling) = serve_signaling(false).await?;
+
+    let c = dial::DialOptions::builder()
+        .uri(&robot_address)
+        .without_credentials()
+        .insecure()
+        .disable_mdns()
+        .signaling_server(&signaling_address)
+        .signaling_header("x-gateway-route", "robots-east")
+        .signaling_header("x-tenant", "fleet")
+        .connect()
+        .await?;
+    assert!(matches!(c, ViamChannel::Direct(_)));
+
+    assert_eq!(signaling.methods(), vec!["optional_web_rtc_config", "call"]);
+    for metadata in signaling.metadata() {
+        assert_eq!(metadata.get("x-gateway-route").unwrap(), "robots-east");
+        assert_eq!(metadata.get("x-tenant").unwrap(), "fleet");
+    }
+
+    // calls over the connection don't carry the signaling headers
+    assert_eq!(echo_roundtrip(c, "hi").await?, "hi");
+    assert_eq!(robot.methods(), vec!["echo"]);
+    assert_eq!(robot.header("x-gateway-route"), vec![None]);
+
+    Ok(())
+}
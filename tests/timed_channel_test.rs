@@ -0,0 +1,92 @@
+/// Tests that a TimedChannel aborts calls that outlast its timeout, using a slow echo server
+/// run in-process.
+use anyhow::Result;
+use std::{pin::Pin, time::Duration};
+use tokio_stream::Stream;
+use tonic::{transport::Server, Code, Request, Response, Status, Streaming};
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_client::EchoServiceClient;
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_server::{
+    EchoService, EchoServiceServer,
+};
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::{
+    EchoBiDiRequest, EchoBiDiResponse, EchoMultipleRequest, EchoMultipleResponse, EchoRequest,
+    EchoResponse,
+};
+use viam_rust_utils::rpc::dial::{self, TimedChannel};
+
+mod common;
+
+const SLOW_ECHO_DELAY: Duration = Duration::from_secs(5);
+
+struct SlowEcho;
+
+#[tonic::async_trait]
+impl EchoService for SlowEcho {
+    async fn echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let message = request.into_inner().message;
+        if message == "slow" {
+            tokio::time::sleep(SLOW_ECHO_DELAY).await;
+        }
+        Ok(Response::new(EchoResponse { message }))
+    }
+
+    type EchoMultipleStream =
+        Pin<Box<dyn Stream<Item = Result<EchoMultipleResponse, Status>> + Send>>;
+
+    async fn echo_multiple(
+        &self,
+        _request: Request<EchoMultipleRequest>,
+    ) -> Result<Response<Self::EchoMultipleStream>, Status> {
+        Err(Status::unimplemented("not used by these tests"))
+    }
+
+    type EchoBiDiStream = Pin<Box<dyn Stream<Item = Result<EchoBiDiResponse, Status>> + Send>>;
+
+    async fn echo_bi_di(
+        &self,
+        _request: Request<Streaming<EchoBiDiRequest>>,
+    ) -> Result<Response<Self::EchoBiDiStream>, Status> {
+        Err(Status::unimplemented("not used by these tests"))
+    }
+}
+
+async fn dial_slow_echo() -> Result<dial::ViamChannel> {
+    let uri =
+        common::serve(Server::builder().add_service(EchoServiceServer::new(SlowEcho))).await?;
+
+    dial::DialOptions::builder()
+        .uri(&uri)
+        .without_credentials()
+        .insecure()
+        .disable_mdns()
+        .disable_webrtc()
+        .connect()
+        .await
+}
+
+#[tokio::test]
+async fn test_timed_channel_direct() -> Result<()> {
+    let c = dial_slow_echo().await?;
+    let timeout = Duration::from_millis(200);
+    let mut service = EchoServiceClient::new(TimedChannel::new(c, timeout));
+
+    let resp = service
+        .echo(EchoRequest {
+            message: "fast".to_string(),
+        })
+        .await?
+        .into_inner();
+    assert_eq!(resp.message, "fast".to_string());
+
+    let start = tokio::time::Instant::now();
+    let status = service
+        .echo(EchoRequest {
+            message: "slow".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded, "{status:?}");
+    assert!(start.elapsed() < SLOW_ECHO_DELAY);
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+/// Tests that tls_sni lets a robot be dialed by IP address while validating its named
+/// certificate, using an echo server run in-process behind a certificate issued for a name
+/// by a CA that the dials are given as a trust anchor.
+use anyhow::Result;
+use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+use viam_rust_utils::gen::proto::rpc::examples::echo::v1::echo_service_server::EchoServiceServer;
+use viam_rust_utils::rpc::dial;
+use viam_rust_utils::rpc::test_util::echo_roundtrip;
+
+mod common;
+
+const SERVER_NAME: &str = "robot.example";
+
+// Serves echo over TLS with a certificate for SERVER_NAME, returning its address and the PEM of
+// the CA that issued the certificate.
+async fn serve() -> Result<(String, String)> {
+    let ca_key = KeyPair::generate()?;
+    let mut ca_params = CertificateParams::new(Vec::<String>::new())?;
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = ca_params.self_signed(&ca_key)?;
+
+    let server_key = KeyPair::generate()?;
+    let server_cert = CertificateParams::new(vec![SERVER_NAME.to_string()])?.signed_by(
+        &server_key,
+        &ca,
+        &ca_key,
+    )?;
+
+    let identity = Identity::from_pem(server_cert.pem(), server_key.serialize_pem());
+    let router = Server::builder()
+        .tls_config(ServerTlsConfig::new().identity(identity))?
+        .add_service(EchoServiceServer::new(common::Echo::default()));
+    let address = common::serve(router).await?;
+    Ok((address, ca.pem()))
+}
+
+fn builder(address: &str) -> dial::DialBuilder<dial::WithoutCredentials> {
+    dial::DialOptions::builder()
+        .uri(address)
+        .without_credentials()
+        .disable_mdns()
+        .disable_webrtc()
+}
+
+#[tokio::test]
+async fn test_tls_sni() -> Result<()> {
+    let (address, ca) = serve().await?;
+
+    // The certificate doesn't name the IP address being connected to.
+    let err = builder(&address)
+        .tls_ca_certificate(&ca)
+        .connect()
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        format!("{err:#}").contains("invalid peer certificate: NotValidForName"),
+        "{err:#}"
+    );
+
+    // Nor is it trusted without its CA.
+    let err = builder(&address)
+        .tls_sni(SERVER_NAME)
+        .connect()
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        format!("{err:#}").contains("invalid peer certificate: UnknownIssuer"),
+        "{err:#}"
+    );
+
+    let c = builder(&address)
+        .tls_sni(SERVER_NAME)
+        .tls_ca_certificate(&ca)
+        .connect()
+        .await?;
+    assert_eq!(echo_roundtrip(c, "hi").await?, "hi");
+
+    Ok(())
+}
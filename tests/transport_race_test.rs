@@ -0,0 +1,47 @@
+/// Tests that connecting doesn't wait on mDNS when the uri itself connects quickly, using a
+/// server run in-process.
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use viam_rust_utils::rpc::dial::{self, ViamChannel};
+
+mod common;
+
+#[tokio::test]
+async fn test_connect_does_not_wait_for_mdns() -> Result<()> {
+    let (address, _) = common::serve_auth().await?;
+
+    // nothing answers mDNS queries for this address, so the mDNS lookup only gives up once
+    // its 1.5s timeout runs out.
+    let start = Instant::now();
+    let c = dial::DialOptions::builder()
+        .uri(&address)
+        .without_credentials()
+        .insecure()
+        .disable_webrtc()
+        .connect()
+        .await?;
+    assert!(matches!(c, ViamChannel::Direct(_)));
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "{:?}",
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let c = dial::DialOptions::builder()
+        .uri(&address)
+        .with_credentials(common::credentials())
+        .insecure()
+        .allow_insecure_credentials()
+        .disable_webrtc()
+        .connect()
+        .await?;
+    assert!(matches!(c, ViamChannel::DirectPreAuthorized(_)));
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "{:?}",
+        start.elapsed()
+    );
+
+    Ok(())
+}